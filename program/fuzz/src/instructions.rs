@@ -0,0 +1,645 @@
+use arbitrary::Arbitrary;
+use card::{
+    collections::{authority, deposit, fee},
+    find_config_program_address, find_deposit_program_address, find_escrow_program_address,
+    find_escrow_vault_program_address, find_mint_config_program_address, find_program_authority,
+    find_withdrawal_program_address,
+    instruction::{self as card_instruction, DepositArgs, InitEscrowArgs, WithdrawArgs},
+    state::{
+        config::{Config, Roles},
+        mint_config::{MintConfig, MintParams},
+    },
+};
+use card_fuzz::{native_account_data::NativeAccountData, native_processor::do_process_instruction};
+use honggfuzz::fuzz;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program, sysvar,
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+const INITIAL_LAMPORTS: u64 = 1_000_000_000_000;
+const INITIAL_TOKEN_AMOUNT: u64 = 1 << 40;
+const REFERENCE_COUNT: u8 = 2;
+
+#[derive(Debug, Arbitrary, Clone, Copy, PartialEq)]
+struct ReferenceId(u8);
+
+#[derive(Debug, Arbitrary, Clone, Copy, PartialEq)]
+enum AccountId {
+    UserWallet,
+    Authority,
+    Payer,
+    Attacker,
+    Receiver,
+    DepositCollection,
+    FeeCollection,
+    ProgramAuthority,
+    Config,
+    MintConfig(bool),
+    UserToken,
+    CollectionToken,
+    FeeToken,
+    VaultToken,
+    ReceiverToken,
+    AttackerToken,
+    Mint,
+    NativeMint,
+    Reference(ReferenceId),
+    Deposit(ReferenceId),
+    Withdraw(ReferenceId),
+    Escrow(ReferenceId),
+    EscrowVault(ReferenceId),
+    Rent,
+    SystemProgram,
+    TokenProgram,
+}
+
+#[derive(Debug, Arbitrary)]
+enum FuzzInstruction {
+    InitDeposit {
+        reference: ReferenceId,
+        amount: u64,
+        fee_bps: u16,
+    },
+    InitWithdrawal {
+        reference: ReferenceId,
+        amount: u64,
+        fee_bps: u16,
+        fixed_fee: u64,
+    },
+    InitEscrow {
+        reference: ReferenceId,
+        native: bool,
+        amount: u64,
+        fee_bps: u16,
+        fixed_fee: u64,
+    },
+    Settle {
+        reference: ReferenceId,
+        native: bool,
+    },
+    Cancel {
+        reference: ReferenceId,
+        native: bool,
+    },
+    Close {
+        reference: ReferenceId,
+    },
+    Raw {
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzAccountMeta {
+    account: AccountId,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+/// Mutations applied to the accounts a client would send
+#[derive(Debug, Arbitrary)]
+enum AccountsTamper {
+    ToggleSigner(u8),
+    Replace { position: u8, account: AccountId },
+    Truncate(u8),
+    Arbitrary(Vec<FuzzAccountMeta>),
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzCall {
+    instruction: FuzzInstruction,
+    tampers: Vec<AccountsTamper>,
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzData {
+    calls: Vec<FuzzCall>,
+}
+
+fn main() {
+    let mut card_accounts = CardAccounts::new();
+    loop {
+        fuzz!(|fuzz_data: FuzzData| {
+            card_accounts.reset();
+            run_fuzz(&mut card_accounts, fuzz_data);
+        });
+    }
+}
+
+struct CardAccounts {
+    reference_keys: Vec<Pubkey>,
+    program_authority: (Pubkey, u8),
+    initial_accounts: Vec<NativeAccountData>,
+    accounts: Vec<NativeAccountData>,
+}
+
+impl CardAccounts {
+    fn new() -> Self {
+        let reference_keys = (0..REFERENCE_COUNT)
+            .map(|i| Pubkey::new_from_array([0xf0 + i; 32]))
+            .collect::<Vec<_>>();
+        let program_authority = find_program_authority(&card::id());
+        let mut card_accounts = Self {
+            reference_keys,
+            program_authority,
+            initial_accounts: vec![],
+            accounts: vec![],
+        };
+
+        let rent = Rent::default();
+        let system_account = |key: Pubkey, lamports: u64| {
+            NativeAccountData::new(key, lamports, vec![], system_program::id())
+        };
+        let mut accounts = vec![
+            system_account(card_accounts.key(AccountId::UserWallet), INITIAL_LAMPORTS),
+            system_account(card_accounts.key(AccountId::Authority), INITIAL_LAMPORTS),
+            system_account(card_accounts.key(AccountId::Payer), INITIAL_LAMPORTS),
+            system_account(card_accounts.key(AccountId::Attacker), INITIAL_LAMPORTS),
+            system_account(card_accounts.key(AccountId::Receiver), INITIAL_LAMPORTS),
+            system_account(
+                card_accounts.key(AccountId::DepositCollection),
+                rent.minimum_balance(0),
+            ),
+            system_account(
+                card_accounts.key(AccountId::FeeCollection),
+                rent.minimum_balance(0),
+            ),
+            system_account(card_accounts.key(AccountId::ProgramAuthority), 0),
+        ];
+
+        let mut data = vec![0; Config::LEN];
+        Config {
+            is_initialized: true,
+            roles: Roles {
+                settler: authority::id(),
+                canceler: authority::id(),
+                deposit_approver: authority::id(),
+                withdrawal_approver: authority::id(),
+                rent_closer: authority::id(),
+                config_admin: authority::id(),
+            },
+            paused_flags: 0,
+            bump: find_config_program_address(&card::id()).1,
+            authority_bump: find_program_authority(&card::id()).1,
+        }
+        .pack_into_slice(&mut data);
+        accounts.push(NativeAccountData::new(
+            card_accounts.key(AccountId::Config),
+            rent.minimum_balance(Config::LEN),
+            data,
+            card::id(),
+        ));
+
+        // both mints are supported for any amount, at a default fee above what callers ask
+        for (native, mint, deposit_collection, fee_collection) in [
+            (
+                false,
+                AccountId::Mint,
+                AccountId::CollectionToken,
+                AccountId::FeeToken,
+            ),
+            (
+                true,
+                AccountId::NativeMint,
+                AccountId::DepositCollection,
+                AccountId::FeeCollection,
+            ),
+        ] {
+            let mut data = vec![0; MintConfig::LEN];
+            MintConfig {
+                is_initialized: true,
+                mint: card_accounts.key(mint),
+                params: MintParams {
+                    enabled: true,
+                    min_amount: 0,
+                    max_amount: u64::MAX,
+                    fee_bps: 10000,
+                    fixed_fee: 1 << 32,
+                    deposit_collection: card_accounts.key(deposit_collection),
+                    fee_collection: card_accounts.key(fee_collection),
+                },
+                has_fee_schedule: false,
+                fee_schedule_bump: 0,
+                bump: find_mint_config_program_address(&card::id(), &card_accounts.key(mint)).1,
+            }
+            .pack_into_slice(&mut data);
+            accounts.push(NativeAccountData::new(
+                card_accounts.key(AccountId::MintConfig(native)),
+                rent.minimum_balance(MintConfig::LEN),
+                data,
+                card::id(),
+            ));
+        }
+
+        let mint = card_accounts.key(AccountId::Mint);
+        let token_accounts = [
+            (
+                AccountId::UserToken,
+                AccountId::UserWallet,
+                INITIAL_TOKEN_AMOUNT,
+            ),
+            (AccountId::CollectionToken, AccountId::DepositCollection, 0),
+            (AccountId::FeeToken, AccountId::FeeCollection, 0),
+            (AccountId::VaultToken, AccountId::ProgramAuthority, 0),
+            (AccountId::ReceiverToken, AccountId::Receiver, 0),
+            (AccountId::AttackerToken, AccountId::Attacker, 0),
+        ];
+        let mut supply = 0;
+        for (id, owner, amount) in token_accounts.iter() {
+            let mut data = vec![0; TokenAccount::LEN];
+            TokenAccount {
+                mint,
+                owner: card_accounts.key(*owner),
+                amount: *amount,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            }
+            .pack_into_slice(&mut data);
+            supply += amount;
+            accounts.push(NativeAccountData::new(
+                card_accounts.key(*id),
+                rent.minimum_balance(TokenAccount::LEN),
+                data,
+                spl_token::id(),
+            ));
+        }
+
+        for (id, key) in [
+            (AccountId::Mint, mint),
+            (AccountId::NativeMint, spl_token::native_mint::id()),
+        ] {
+            let mut data = vec![0; Mint::LEN];
+            Mint {
+                mint_authority: COption::None,
+                supply: if id == AccountId::Mint { supply } else { 0 },
+                decimals: 9,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            }
+            .pack_into_slice(&mut data);
+            accounts.push(NativeAccountData::new(
+                key,
+                rent.minimum_balance(Mint::LEN),
+                data,
+                spl_token::id(),
+            ));
+        }
+
+        for i in 0..REFERENCE_COUNT {
+            let reference = ReferenceId(i);
+            accounts.push(system_account(
+                card_accounts.key(AccountId::Reference(reference)),
+                0,
+            ));
+            accounts.push(system_account(
+                card_accounts.key(AccountId::Deposit(reference)),
+                0,
+            ));
+            accounts.push(system_account(
+                card_accounts.key(AccountId::Withdraw(reference)),
+                0,
+            ));
+            accounts.push(system_account(
+                card_accounts.key(AccountId::Escrow(reference)),
+                0,
+            ));
+            accounts.push(system_account(
+                card_accounts.key(AccountId::EscrowVault(reference)),
+                0,
+            ));
+        }
+
+        let mut rent_account = NativeAccountData::new(
+            sysvar::rent::id(),
+            1,
+            bincode::serialize(&rent).unwrap(),
+            sysvar::id(),
+        );
+        rent_account.lamports = rent.minimum_balance(rent_account.data.len());
+        accounts.push(rent_account);
+        accounts.push(NativeAccountData::new(
+            system_program::id(),
+            1,
+            vec![],
+            pubkey!("NativeLoader1111111111111111111111111111111"),
+        ));
+        accounts.push(NativeAccountData::new(
+            spl_token::id(),
+            1,
+            vec![],
+            solana_program::bpf_loader::id(),
+        ));
+
+        card_accounts.initial_accounts = accounts.clone();
+        card_accounts.accounts = accounts;
+        card_accounts
+    }
+
+    fn reset(&mut self) {
+        self.accounts.clone_from(&self.initial_accounts);
+    }
+
+    fn reference(&self, reference: ReferenceId) -> Pubkey {
+        self.reference_keys[(reference.0 % REFERENCE_COUNT) as usize]
+    }
+
+    fn key(&self, id: AccountId) -> Pubkey {
+        match id {
+            AccountId::UserWallet => Pubkey::new_from_array([1; 32]),
+            AccountId::Authority => authority::id(),
+            AccountId::Payer => Pubkey::new_from_array([2; 32]),
+            AccountId::Attacker => Pubkey::new_from_array([3; 32]),
+            AccountId::Receiver => Pubkey::new_from_array([4; 32]),
+            AccountId::DepositCollection => deposit::id(),
+            AccountId::FeeCollection => fee::id(),
+            AccountId::ProgramAuthority => self.program_authority.0,
+            AccountId::Config => find_config_program_address(&card::id()).0,
+            AccountId::MintConfig(native) => {
+                let mint = if native {
+                    spl_token::native_mint::id()
+                } else {
+                    self.key(AccountId::Mint)
+                };
+                find_mint_config_program_address(&card::id(), &mint).0
+            }
+            AccountId::UserToken => Pubkey::new_from_array([5; 32]),
+            AccountId::CollectionToken => Pubkey::new_from_array([6; 32]),
+            AccountId::FeeToken => Pubkey::new_from_array([7; 32]),
+            AccountId::VaultToken => Pubkey::new_from_array([8; 32]),
+            AccountId::ReceiverToken => Pubkey::new_from_array([9; 32]),
+            AccountId::AttackerToken => Pubkey::new_from_array([10; 32]),
+            AccountId::Mint => Pubkey::new_from_array([11; 32]),
+            AccountId::NativeMint => spl_token::native_mint::id(),
+            AccountId::Reference(reference) => self.reference(reference),
+            AccountId::Deposit(reference) => {
+                find_deposit_program_address(&card::id(), &self.reference(reference)).0
+            }
+            AccountId::Withdraw(reference) => {
+                find_withdrawal_program_address(&card::id(), &self.reference(reference)).0
+            }
+            AccountId::Escrow(reference) => {
+                find_escrow_program_address(&card::id(), &self.reference(reference)).0
+            }
+            AccountId::EscrowVault(reference) => {
+                find_escrow_vault_program_address(&card::id(), &self.reference(reference)).0
+            }
+            AccountId::Rent => sysvar::rent::id(),
+            AccountId::SystemProgram => system_program::id(),
+            AccountId::TokenProgram => spl_token::id(),
+        }
+    }
+
+    /// Builds the instruction exactly as a well-behaved client would
+    fn instruction(&self, fuzz_instruction: &FuzzInstruction) -> Instruction {
+        let key = |id| self.key(id);
+        let escrow_accounts = |reference: ReferenceId, native: bool| {
+            if native {
+                (
+                    AccountId::EscrowVault(reference),
+                    AccountId::UserWallet,
+                    AccountId::DepositCollection,
+                    AccountId::FeeCollection,
+                    AccountId::NativeMint,
+                )
+            } else {
+                (
+                    AccountId::VaultToken,
+                    AccountId::UserToken,
+                    AccountId::CollectionToken,
+                    AccountId::FeeToken,
+                    AccountId::Mint,
+                )
+            }
+        };
+        match *fuzz_instruction {
+            FuzzInstruction::InitDeposit {
+                reference,
+                amount,
+                fee_bps,
+            } => card_instruction::deposit(
+                &card::id(),
+                &key(AccountId::UserWallet),
+                &key(AccountId::Authority),
+                &key(AccountId::Payer),
+                &key(AccountId::Deposit(reference)),
+                &key(AccountId::UserToken),
+                &key(AccountId::CollectionToken),
+                &key(AccountId::FeeToken),
+                &key(AccountId::Mint),
+                DepositArgs {
+                    amount,
+                    fee_bps,
+                    key: self.reference(reference),
+                    bump: find_deposit_program_address(&card::id(), &self.reference(reference)).1,
+                },
+            ),
+            FuzzInstruction::InitWithdrawal {
+                reference,
+                amount,
+                fee_bps,
+                fixed_fee,
+            } => card_instruction::withdraw(
+                &card::id(),
+                &key(AccountId::UserWallet),
+                &key(AccountId::Authority),
+                &key(AccountId::Payer),
+                &key(AccountId::Withdraw(reference)),
+                &key(AccountId::UserToken),
+                &key(AccountId::ReceiverToken),
+                &key(AccountId::FeeToken),
+                &key(AccountId::Mint),
+                WithdrawArgs {
+                    amount,
+                    fee_bps,
+                    key: self.reference(reference),
+                    bump: find_withdrawal_program_address(&card::id(), &self.reference(reference))
+                        .1,
+                    fixed_fee,
+                },
+            ),
+            FuzzInstruction::InitEscrow {
+                reference,
+                native,
+                amount,
+                fee_bps,
+                fixed_fee,
+            } => {
+                let (vault, src, dst, fee, mint) = escrow_accounts(reference, native);
+                card_instruction::init_escrow(
+                    &card::id(),
+                    &key(AccountId::UserWallet),
+                    &key(AccountId::Authority),
+                    &key(AccountId::Payer),
+                    &key(AccountId::Escrow(reference)),
+                    &key(AccountId::ProgramAuthority),
+                    &key(vault),
+                    &key(src),
+                    &key(dst),
+                    &key(fee),
+                    &key(mint),
+                    &key(AccountId::Reference(reference)),
+                    InitEscrowArgs {
+                        amount,
+                        fee_bps,
+                        fixed_fee,
+                        bump: find_escrow_program_address(&card::id(), &self.reference(reference))
+                            .1,
+                        merchant: None,
+                        split: None,
+                        hold: false,
+                        mcc: 0,
+                    },
+                )
+            }
+            FuzzInstruction::Settle { reference, native } => {
+                let (vault, _, dst, fee, mint) = escrow_accounts(reference, native);
+                card_instruction::settle_escrow(
+                    &card::id(),
+                    &key(AccountId::Authority),
+                    &key(dst),
+                    &key(fee),
+                    &key(vault),
+                    &key(AccountId::Escrow(reference)),
+                    &key(mint),
+                    &key(AccountId::ProgramAuthority),
+                )
+            }
+            FuzzInstruction::Cancel { reference, native } => {
+                let (vault, src, _, _, mint) = escrow_accounts(reference, native);
+                card_instruction::cancel_escrow(
+                    &card::id(),
+                    &key(AccountId::Authority),
+                    &key(AccountId::Escrow(reference)),
+                    &key(src),
+                    &key(vault),
+                    &key(mint),
+                    &key(AccountId::ProgramAuthority),
+                )
+            }
+            FuzzInstruction::Close { reference } => card_instruction::close_escrow(
+                &card::id(),
+                &key(AccountId::Authority),
+                &key(AccountId::Escrow(reference)),
+                &key(AccountId::Payer),
+            ),
+            FuzzInstruction::Raw { ref data } => Instruction {
+                program_id: card::id(),
+                accounts: vec![],
+                data: data.clone(),
+            },
+        }
+    }
+
+    fn tamper(&self, instruction: &mut Instruction, tamper: &AccountsTamper) {
+        let accounts = &mut instruction.accounts;
+        match *tamper {
+            AccountsTamper::ToggleSigner(position) => {
+                if let Some(meta) = accounts.get_mut(position as usize) {
+                    meta.is_signer = !meta.is_signer;
+                }
+            }
+            AccountsTamper::Replace { position, account } => {
+                if let Some(meta) = accounts.get_mut(position as usize) {
+                    meta.pubkey = self.key(account);
+                }
+            }
+            AccountsTamper::Truncate(len) => accounts.truncate(len as usize),
+            AccountsTamper::Arbitrary(ref metas) => {
+                *accounts = metas
+                    .iter()
+                    .map(|meta| AccountMeta {
+                        pubkey: self.key(meta.account),
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                    .collect();
+            }
+        }
+        // nobody holds the private key of a program address
+        for meta in accounts.iter_mut() {
+            if !meta.pubkey.is_on_curve() {
+                meta.is_signer = false;
+            }
+        }
+    }
+
+    /// Keys allowed to move funds, the config may have been rotated by an earlier call
+    fn approvers(&self) -> Vec<Pubkey> {
+        let config_key = self.key(AccountId::Config);
+        let mut approvers = vec![authority::id()];
+        if let Some(config) = self
+            .accounts
+            .iter()
+            .find(|account| account.key == config_key)
+            .and_then(|account| Config::unpack(&account.data).ok())
+        {
+            let roles = config.roles;
+            approvers.extend_from_slice(&[
+                roles.settler,
+                roles.canceler,
+                roles.deposit_approver,
+                roles.withdrawal_approver,
+                roles.rent_closer,
+            ]);
+        }
+        approvers
+    }
+
+    fn balances(&self) -> Vec<(Pubkey, u64, u64)> {
+        self.accounts
+            .iter()
+            .map(|account| {
+                let amount = if account.owner == spl_token::id()
+                    && account.data.len() == TokenAccount::LEN
+                {
+                    TokenAccount::unpack(&account.data)
+                        .map(|token| token.amount)
+                        .unwrap_or_default()
+                } else {
+                    0
+                };
+                (account.key, account.lamports, amount)
+            })
+            .collect()
+    }
+}
+
+fn run_fuzz(card_accounts: &mut CardAccounts, fuzz_data: FuzzData) {
+    for call in fuzz_data.calls.iter() {
+        let mut instruction = card_accounts.instruction(&call.instruction);
+        for tamper in call.tampers.iter() {
+            card_accounts.tamper(&mut instruction, tamper);
+        }
+
+        let approvers = card_accounts.approvers();
+        let before = card_accounts.balances();
+        if do_process_instruction(&mut card_accounts.accounts, &instruction).is_err() {
+            assert_eq!(before, card_accounts.balances());
+            continue;
+        }
+        let after = card_accounts.balances();
+
+        let tokens_before: u128 = before.iter().map(|(_, _, amount)| *amount as u128).sum();
+        let tokens_after: u128 = after.iter().map(|(_, _, amount)| *amount as u128).sum();
+        assert_eq!(tokens_before, tokens_after, "token supply changed");
+
+        if before != after {
+            let approver_signed = instruction
+                .accounts
+                .iter()
+                .any(|meta| approvers.contains(&meta.pubkey) && meta.is_signer);
+            assert!(
+                approver_signed,
+                "funds moved without an approver signature: {:?}",
+                call
+            );
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod native_account_data;
+pub mod native_processor;
@@ -0,0 +1,74 @@
+use solana_program::{account_info::AccountInfo, clock::Epoch, pubkey::Pubkey};
+
+/// Room every synthetic account gets behind its data so the system program
+/// stub can `Allocate` in place, the same way the runtime reserves space
+/// for serialized accounts.
+pub const MAX_ACCOUNT_DATA_LEN: usize = 10 * 1024;
+
+/// Owned account state the harness keeps between instructions
+#[derive(Clone, Debug, PartialEq)]
+pub struct NativeAccountData {
+    pub key: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+}
+
+impl NativeAccountData {
+    pub fn new(key: Pubkey, lamports: u64, data: Vec<u8>, owner: Pubkey) -> Self {
+        Self {
+            key,
+            lamports,
+            data,
+            owner,
+        }
+    }
+
+    pub fn new_from_account_info(account_info: &AccountInfo) -> Self {
+        Self {
+            key: *account_info.key,
+            lamports: **account_info.lamports.borrow(),
+            data: account_info.data.borrow().to_vec(),
+            owner: *account_info.owner,
+        }
+    }
+}
+
+/// Scratch copy of an account handed to the processor for one instruction.
+/// The data buffer is always `MAX_ACCOUNT_DATA_LEN` long and the
+/// `AccountInfo` only exposes the first `len` bytes of it.
+pub struct ScratchAccount {
+    pub key: Pubkey,
+    pub lamports: u64,
+    pub buffer: Vec<u8>,
+    pub len: usize,
+    pub owner: Pubkey,
+}
+
+impl ScratchAccount {
+    pub fn new(account: &NativeAccountData) -> Self {
+        assert!(account.data.len() <= MAX_ACCOUNT_DATA_LEN);
+        let mut buffer = vec![0; MAX_ACCOUNT_DATA_LEN];
+        buffer[..account.data.len()].copy_from_slice(&account.data);
+        Self {
+            key: account.key,
+            lamports: account.lamports,
+            buffer,
+            len: account.data.len(),
+            owner: account.owner,
+        }
+    }
+
+    pub fn as_account_info(&mut self, is_signer: bool, is_writable: bool) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            is_signer,
+            is_writable,
+            &mut self.lamports,
+            &mut self.buffer[..self.len],
+            &self.owner,
+            false,
+            Epoch::default(),
+        )
+    }
+}
@@ -0,0 +1,273 @@
+//! Runs the card processor natively, routing its CPIs to the token program
+//! and to a minimal system program, and applying the account checks the
+//! runtime would apply once the instruction returns.
+
+use std::{convert::TryInto, sync::Once};
+
+use card::processor::Processor;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
+    program_error::ProgramError, program_stubs, pubkey::Pubkey, rent::Rent,
+    system_instruction::SystemInstruction, system_program,
+};
+
+use crate::native_account_data::{NativeAccountData, ScratchAccount, MAX_ACCOUNT_DATA_LEN};
+
+struct TestSyscallStubs;
+impl program_stubs::SyscallStubs for TestSyscallStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &card::id()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut new_account_infos = vec![];
+        for meta in instruction.accounts.iter() {
+            let account_info = account_infos
+                .iter()
+                .find(|account_info| *account_info.key == meta.pubkey)
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut new_account_info = account_info.clone();
+            new_account_info.is_signer = account_info.is_signer || signers.contains(&meta.pubkey);
+            // privilege escalation checks
+            if meta.is_signer && !new_account_info.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if meta.is_writable && !account_info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            new_account_infos.push(new_account_info);
+        }
+
+        if instruction.program_id == spl_token::id() {
+            spl_token::processor::Processor::process(
+                &instruction.program_id,
+                &new_account_infos,
+                &instruction.data,
+            )
+        } else if instruction.program_id == system_program::id() {
+            process_system_instruction(&new_account_infos, &instruction.data)
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut _ as *mut Rent) = Rent::default();
+        }
+        solana_program::entrypoint::SUCCESS
+    }
+}
+
+fn test_syscall_stubs() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(TestSyscallStubs {}));
+    });
+}
+
+/// The subset of the system program the card program relies on
+fn process_system_instruction(accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+    let instruction: SystemInstruction =
+        bincode::deserialize(input).map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        SystemInstruction::Transfer { lamports } => {
+            let from = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let to = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            system_transfer(from, to, lamports)
+        }
+        SystemInstruction::Allocate { space } => {
+            let account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            system_allocate(account, space)
+        }
+        SystemInstruction::Assign { owner } => {
+            let account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            system_assign(account, &owner)
+        }
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let from = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let to = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if to.lamports() > 0 {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            system_allocate(to, space)?;
+            system_assign(to, &owner)?;
+            system_transfer(from, to, lamports)
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn system_transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // the system program can only debit accounts it owns that carry no data
+    if *from.owner != system_program::id() || !from.data_is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if from.lamports() < lamports {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    **from.try_borrow_mut_lamports()? -= lamports;
+    **to.try_borrow_mut_lamports()? += lamports;
+    Ok(())
+}
+
+fn system_allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *account.owner != system_program::id() || !account.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let space: usize = space
+        .try_into()
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    if space > MAX_ACCOUNT_DATA_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut data = account.try_borrow_mut_data()?;
+    let data_ptr = data.as_mut_ptr();
+    // SAFETY: every account handed to the processor is backed by a
+    // `ScratchAccount` buffer of `MAX_ACCOUNT_DATA_LEN` zeroed bytes.
+    *data = unsafe { std::slice::from_raw_parts_mut(data_ptr, space) };
+    Ok(())
+}
+
+fn system_assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner == owner {
+        return Ok(());
+    }
+    if !account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *account.owner != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+/// Checks the runtime applies to the accounts of a successful instruction
+fn verify_account_changes(
+    before: &[NativeAccountData],
+    after: &[NativeAccountData],
+    is_writable: &[bool],
+) -> ProgramResult {
+    let lamports_before: u128 = before.iter().map(|a| a.lamports as u128).sum();
+    let lamports_after: u128 = after.iter().map(|a| a.lamports as u128).sum();
+    if lamports_before != lamports_after {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let rent = Rent::default();
+    for ((pre, post), is_writable) in before.iter().zip(after).zip(is_writable) {
+        if !is_writable && pre != post {
+            return Err(ProgramError::InvalidArgument);
+        }
+        let resized = pre.data.len() != post.data.len();
+        if (pre.lamports != post.lamports || resized)
+            && post.lamports != 0
+            && !rent.is_exempt(post.lamports, post.data.len())
+        {
+            return Err(ProgramError::AccountNotRentExempt);
+        }
+    }
+    Ok(())
+}
+
+/// Process `instruction` against `accounts`, which are only updated if the
+/// instruction succeeds, the way a transaction is committed on chain.
+pub fn do_process_instruction(
+    accounts: &mut [NativeAccountData],
+    instruction: &Instruction,
+) -> ProgramResult {
+    test_syscall_stubs();
+
+    let mut keys: Vec<Pubkey> = vec![];
+    let mut is_signer: Vec<bool> = vec![];
+    let mut is_writable: Vec<bool> = vec![];
+    for meta in instruction.accounts.iter() {
+        match keys.iter().position(|key| *key == meta.pubkey) {
+            Some(index) => {
+                is_signer[index] |= meta.is_signer;
+                is_writable[index] |= meta.is_writable;
+            }
+            None => {
+                keys.push(meta.pubkey);
+                is_signer.push(meta.is_signer);
+                is_writable.push(meta.is_writable);
+            }
+        }
+    }
+
+    let before = keys
+        .iter()
+        .map(|key| {
+            accounts
+                .iter()
+                .find(|account| account.key == *key)
+                .cloned()
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut scratch: Vec<ScratchAccount> = before.iter().map(ScratchAccount::new).collect();
+
+    let (result, after) = {
+        let account_infos = scratch
+            .iter_mut()
+            .zip(is_signer.iter().zip(&is_writable))
+            .map(|(account, (is_signer, is_writable))| {
+                account.as_account_info(*is_signer, *is_writable)
+            })
+            .collect::<Vec<_>>();
+        let instruction_account_infos = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                let index = keys.iter().position(|key| *key == meta.pubkey).unwrap();
+                account_infos[index].clone()
+            })
+            .collect::<Vec<_>>();
+
+        let result = Processor::process(
+            &instruction.program_id,
+            &instruction_account_infos,
+            &instruction.data,
+        );
+        let after = account_infos
+            .iter()
+            .map(NativeAccountData::new_from_account_info)
+            .collect::<Vec<_>>();
+        (result, after)
+    };
+    result?;
+    verify_account_changes(&before, &after, &is_writable)?;
+
+    for mut account in after {
+        // accounts left without lamports are purged at the end of the transaction
+        if account.lamports == 0 {
+            account.data.clear();
+            account.owner = system_program::id();
+        }
+        let entry = accounts
+            .iter_mut()
+            .find(|entry| entry.key == account.key)
+            .unwrap();
+        *entry = account;
+    }
+    Ok(())
+}
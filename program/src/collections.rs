@@ -1,4 +1,3 @@
-
 pub mod deposit {
     solana_program::declare_id!("3zvXV9s2F8yQt54GdDLprLxHARkXpuZwm4FaUsAJTRmF");
 }
@@ -9,4 +8,4 @@ pub mod fee {
 
 pub mod authority {
     solana_program::declare_id!("6nbUEwjGayAyAvbjzUkKZJAYH6etu51jj1zMuQgxbQyW");
-}
\ No newline at end of file
+}
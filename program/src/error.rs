@@ -1,6 +1,6 @@
 // inside error.rs
-use thiserror::Error;
 use solana_program::program_error::ProgramError;
+use thiserror::Error;
 
 #[derive(Error, Debug, Copy, Clone)]
 pub enum CardError {
@@ -51,10 +51,118 @@ pub enum CardError {
     InvalidDepositTokenOwner,
     #[error("Invalid withdraw token owner")]
     InvalidWithdrawTokenOwner,
+    #[error("Invalid vault key")]
+    InvalidVaultKey,
+    #[error("Invalid wrapped SOL account key")]
+    InvalidWrapKey,
+    #[error("Invalid associated token account")]
+    InvalidAssociatedTokenAccount,
+    #[error("Invalid authorization")]
+    InvalidAuthorization,
+    #[error("Authorization expired")]
+    AuthorizationExpired,
+    #[error("Invalid multisig threshold or signers")]
+    InvalidMultisigConfig,
+    #[error("Not enough multisig signers")]
+    NotEnoughMultisigSigners,
+    #[error("Invalid config account")]
+    InvalidConfig,
+    #[error("Escrow settler approval missing")]
+    MissingSettlerRole,
+    #[error("Escrow canceler approval missing")]
+    MissingCancelerRole,
+    #[error("Deposit approver approval missing")]
+    MissingDepositApproverRole,
+    #[error("Withdrawal approver approval missing")]
+    MissingWithdrawalApproverRole,
+    #[error("Rent closer approval missing")]
+    MissingRentCloserRole,
+    #[error("Config admin approval missing")]
+    MissingConfigAdminRole,
+    #[error("Instruction is paused")]
+    ProgramPaused,
+    #[error("Mint not supported")]
+    MintNotSupported,
+    #[error("Invalid mint config")]
+    InvalidMintConfig,
+    #[error("Amount outside the mint's limits")]
+    AmountOutOfRange,
+    #[error("Invalid collection account")]
+    InvalidCollectionAccount,
+    #[error("Invalid fee schedule")]
+    InvalidFeeSchedule,
+    #[error("Invalid merchant")]
+    InvalidMerchant,
+    #[error("Invalid merchant settings")]
+    InvalidMerchantConfig,
+    #[error("Merchant suspended")]
+    MerchantSuspended,
+    #[error("Invalid payees")]
+    InvalidPayees,
+    #[error("Invalid payee account")]
+    InvalidPayeeAccount,
+    #[error("Account not settled")]
+    AccountNotSettled,
+    #[error("Refund exceeds the settled amount")]
+    RefundTooLarge,
+    #[error("Escrow disputed")]
+    EscrowDisputed,
+    #[error("Escrow can't be disputed")]
+    DisputeNotAllowed,
+    #[error("Escrow not disputed")]
+    EscrowNotDisputed,
+    #[error("Invalid card")]
+    InvalidCard,
+    #[error("Card frozen")]
+    CardFrozen,
+    #[error("Card closed")]
+    CardClosed,
+    #[error("Invalid card funding account")]
+    InvalidCardFundingAccount,
+    #[error("Card transaction limit exceeded")]
+    TransactionLimitExceeded,
+    #[error("Card daily limit exceeded")]
+    DailyLimitExceeded,
+    #[error("Card monthly limit exceeded")]
+    MonthlyLimitExceeded,
+    #[error("Holds need an SPL token")]
+    HoldNotSupported,
+    #[error("Card not frozen")]
+    CardNotFrozen,
+    #[error("Card frozen by the issuer")]
+    CardFrozenByIssuer,
+    #[error("Merchant category blocked by the card")]
+    MerchantCategoryBlocked,
+    #[error("Invalid merchant categories")]
+    InvalidMerchantCategories,
+    #[error("Merchant categories set by the issuer")]
+    CategoriesSetByIssuer,
+    #[error("Invalid mandate")]
+    InvalidMandate,
+    #[error("Mandate not active")]
+    MandateInactive,
+    #[error("Mandate already charged this period")]
+    MandateAlreadyCharged,
+    #[error("Mandate amount exceeded")]
+    MandateAmountExceeded,
+    #[error("Payout still locked")]
+    PayoutLocked,
+    #[error("Payout already unlocked")]
+    PayoutUnlocked,
+    #[error("Payout already executed or canceled")]
+    PayoutNotPending,
+    #[error("Account has no earlier layout to migrate from")]
+    NotMigratable,
+    #[error("Canceling a hold needs the signature of the source owner")]
+    MissingSourceOwnerSignature,
+    #[error("Invalid mandate delegate")]
+    InvalidMandateDelegate,
+    #[error("Refund approved against another refunded amount")]
+    StaleRefund,
 }
 
 impl From<CardError> for ProgramError {
     fn from(e: CardError) -> Self {
         ProgramError::Custom(e as u32)
     }
-}
\ No newline at end of file
+}
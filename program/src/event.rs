@@ -0,0 +1,21 @@
+//! Events logged by the program
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub enum CardEvent {
+    EscrowSettled {
+        escrow: Pubkey,
+        amount: u64,
+        fee: u64,
+    },
+    /// The escrow was left out of a batch, `error` being the `ProgramError` it failed with
+    EscrowSettlementSkipped { escrow: Pubkey, error: u64 },
+}
+
+/// Log `event` as program data, for clients to read back from the transaction logs
+pub fn emit(event: &CardEvent) {
+    sol_log_data(&[&event.try_to_vec().unwrap()]);
+}
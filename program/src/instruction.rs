@@ -1,16 +1,78 @@
 //! Instruction types
 #![allow(missing_docs)]
 
+use crate::{
+    error::CardError,
+    find_card_program_address, find_config_program_address, find_deposit_program_address,
+    find_escrow_vault_program_address, find_fee_schedule_program_address,
+    find_mandate_delegate_program_address, find_mandate_program_address,
+    find_merchant_program_address, find_mint_config_program_address, find_payout_program_address,
+    find_program_authority, find_program_data_address,
+    state::{
+        card::{CardParams, MerchantCategories},
+        config::Roles,
+        escrow::Split,
+        fee_schedule::FeeRules,
+        mandate::MandateTerms,
+        merchant::{MerchantParams, MerchantStatus},
+        mint_config::MintParams,
+    },
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
+    clock::UnixTimestamp,
+    hash::{hash, Hash},
     instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
     pubkey::Pubkey,
     system_program, sysvar,
 };
+use std::io::{self, Read, Write};
+
+/// Implement `BorshDeserialize` for instruction arguments, the fields listed
+/// after `appended` being read as their default when the data of an older
+/// client ends before them, so new fields are only ever appended there
+macro_rules! deserialize_args {
+    ($name:ident {
+        $($field:ident: $ty:ty),* $(,)?
+    } $(appended {
+        $($appended:ident: $appended_ty:ty),* $(,)?
+    })?) => {
+        impl BorshDeserialize for $name {
+            fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+                $(let $field = <$ty>::deserialize_reader(reader)?;)*
+                // the appended fields end the instruction data
+                let mut appended = Vec::new();
+                reader.read_to_end(&mut appended)?;
+                let buf = &mut appended.as_slice();
+                let args = $name {
+                    $($field,)*
+                    $($($appended: deserialize_appended::<$appended_ty>(buf)?,)*)?
+                };
+                if !buf.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Not all bytes read",
+                    ));
+                }
+                Ok(args)
+            }
+        }
+    };
+}
+
+/// Read an argument appended after the first release of its instruction, its
+/// default when the data of an older client ends before it
+fn deserialize_appended<T: BorshDeserialize + Default>(buf: &mut &[u8]) -> io::Result<T> {
+    if buf.is_empty() {
+        return Ok(T::default());
+    }
+    T::deserialize(buf)
+}
 
 /// Initialize a funding arguments
 #[repr(C)]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
 /// Initialize a funding params
 pub struct DepositArgs {
     pub amount: u64,
@@ -19,9 +81,16 @@ pub struct DepositArgs {
     pub bump: u8,
 }
 
+deserialize_args!(DepositArgs {
+    amount: u64,
+    fee_bps: u16,
+    key: Pubkey,
+    bump: u8,
+});
+
 /// Initialize a funding arguments
 #[repr(C)]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
 /// Initialize a funding params
 pub struct WithdrawArgs {
     pub amount: u64,
@@ -31,19 +100,418 @@ pub struct WithdrawArgs {
     pub fixed_fee: u64,
 }
 
+deserialize_args!(WithdrawArgs {
+    amount: u64,
+    fee_bps: u16,
+    key: Pubkey,
+    bump: u8,
+    fixed_fee: u64,
+});
+
 /// Initialize a escrow arguments
 #[repr(C)]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
-/// Initialize a escrow params
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Initialize a escrow params, the fields after `bump` being appended since the
+/// first release
 pub struct InitEscrowArgs {
     pub amount: u64,
     pub fee_bps: u16,
     pub fixed_fee: u64,
     pub bump: u8,
+    pub merchant: Option<Pubkey>,
+    pub split: Option<Split>,
+    /// Hold the funds in the source token account rather than moving them to a
+    /// vault, the source being passed as the vault too
+    pub hold: bool,
+    /// Merchant category code of the payment, checked against the categories of the card
+    pub mcc: u16,
+}
+
+deserialize_args!(InitEscrowArgs {
+    amount: u64,
+    fee_bps: u16,
+    fixed_fee: u64,
+    bump: u8,
+} appended {
+    merchant: Option<Pubkey>,
+    split: Option<Split>,
+    hold: bool,
+    mcc: u16,
+});
+
+/// Refund a settled escrow arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Refund a settled escrow params
+pub struct RefundArgs {
+    pub amount: u64,
+    /// Whether the share of the fee matching the refunded amount is returned too
+    pub refund_fee: bool,
+    /// Amount the escrow had refunded before this refund, so that an approval
+    /// is spent by the refund it approves
+    pub refunded_amount: u64,
+}
+
+deserialize_args!(RefundArgs {
+    amount: u64,
+    refund_fee: bool,
+    refunded_amount: u64,
+});
+
+/// Dispute an escrow arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Dispute an escrow params
+pub struct DisputeArgs {
+    pub reason: u16,
+}
+
+deserialize_args!(DisputeArgs { reason: u16 });
+
+/// Side a dispute is resolved in favour of
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum DisputeOutcome {
+    /// The merchant keeps the payment, a pending escrow is settled
+    Release,
+    /// The payer gets the payment back, a pending escrow is canceled and a
+    /// settled one refunded with its fee
+    Refund,
+}
+
+/// Resolve a dispute arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Resolve a dispute params
+pub struct ResolveDisputeArgs {
+    pub outcome: DisputeOutcome,
+}
+
+deserialize_args!(ResolveDisputeArgs {
+    outcome: DisputeOutcome,
+});
+
+/// Issue a card arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Issue a card params
+pub struct InitCardArgs {
+    pub owner: Pubkey,
+    pub params: CardParams,
+    pub bump: u8,
+}
+
+deserialize_args!(InitCardArgs {
+    owner: Pubkey,
+    params: CardParams,
+    bump: u8,
+});
+
+/// Update a card arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Update a card params
+pub struct UpdateCardArgs {
+    pub params: CardParams,
+}
+
+deserialize_args!(UpdateCardArgs { params: CardParams });
+
+/// Set the categories of a card arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Set the categories of a card params
+pub struct SetCardCategoriesArgs {
+    pub categories: MerchantCategories,
+}
+
+deserialize_args!(SetCardCategoriesArgs {
+    categories: MerchantCategories
+});
+
+/// Sign a mandate arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Sign a mandate params
+pub struct InitMandateArgs {
+    pub merchant: Pubkey,
+    pub terms: MandateTerms,
+    pub bump: u8,
+}
+
+deserialize_args!(InitMandateArgs {
+    merchant: Pubkey,
+    terms: MandateTerms,
+    bump: u8,
+});
+
+/// Charge a mandate arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Charge a mandate params
+pub struct ChargeMandateArgs {
+    pub amount: u64,
+}
+
+deserialize_args!(ChargeMandateArgs { amount: u64 });
+
+/// Schedule a payout arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Schedule a payout params
+pub struct SchedulePayoutArgs {
+    pub amount: u64,
+    pub unlock_at: UnixTimestamp,
+    pub key: Pubkey,
+    pub bump: u8,
+}
+
+deserialize_args!(SchedulePayoutArgs {
+    amount: u64,
+    unlock_at: UnixTimestamp,
+    key: Pubkey,
+    bump: u8,
+});
+
+/// Settle a batch of escrows arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Settle a batch of escrows params
+pub struct SettleBatchArgs {
+    /// Number of escrow, vault and destination account triples
+    pub count: u8,
+}
+
+deserialize_args!(SettleBatchArgs { count: u8 });
+
+/// A deposit of a batch
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct BatchDeposit {
+    pub amount: u64,
+    pub key: Pubkey,
+    pub bump: u8,
+}
+
+/// Initialize a batch of deposits arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Initialize a batch of deposits params
+pub struct DepositBatchArgs {
+    pub fee_bps: u16,
+    /// Deposits in the order of their accounts
+    pub deposits: Vec<BatchDeposit>,
+}
+
+deserialize_args!(DepositBatchArgs {
+    fee_bps: u16,
+    deposits: Vec<BatchDeposit>,
+});
+
+/// Initialize the multisig arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Initialize the multisig params
+pub struct InitMultisigArgs {
+    pub threshold: u8,
+    pub signers: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+deserialize_args!(InitMultisigArgs {
+    threshold: u8,
+    signers: Vec<Pubkey>,
+    bump: u8,
+});
+
+/// Initialize the config arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Initialize the config params
+pub struct InitConfigArgs {
+    pub roles: Roles,
+    pub bump: u8,
+}
+
+deserialize_args!(InitConfigArgs {
+    roles: Roles,
+    bump: u8,
+});
+
+/// Set the config roles arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Set the config roles params
+pub struct SetConfigArgs {
+    pub roles: Roles,
+}
+
+deserialize_args!(SetConfigArgs { roles: Roles });
+
+/// Pause instructions arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Pause instructions params
+pub struct SetPauseArgs {
+    pub paused_flags: u64,
+}
+
+deserialize_args!(SetPauseArgs { paused_flags: u64 });
+
+/// Initialize a mint config arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Initialize a mint config params
+pub struct InitMintConfigArgs {
+    pub params: MintParams,
+    pub bump: u8,
+}
+
+deserialize_args!(InitMintConfigArgs {
+    params: MintParams,
+    bump: u8,
+});
+
+/// Update a mint config arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Update a mint config params
+pub struct SetMintConfigArgs {
+    pub params: MintParams,
+}
+
+deserialize_args!(SetMintConfigArgs { params: MintParams });
+
+/// Initialize a fee schedule arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Initialize a fee schedule params
+pub struct InitFeeScheduleArgs {
+    pub rules: FeeRules,
+    pub bump: u8,
+}
+
+deserialize_args!(InitFeeScheduleArgs {
+    rules: FeeRules,
+    bump: u8,
+});
+
+/// Update a fee schedule arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Update a fee schedule params
+pub struct SetFeeScheduleArgs {
+    pub rules: FeeRules,
+}
+
+deserialize_args!(SetFeeScheduleArgs { rules: FeeRules });
+
+/// Onboard a merchant arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Onboard a merchant params
+pub struct OnboardMerchantArgs {
+    pub id: Pubkey,
+    pub params: MerchantParams,
+    pub bump: u8,
+}
+
+deserialize_args!(OnboardMerchantArgs {
+    id: Pubkey,
+    params: MerchantParams,
+    bump: u8,
+});
+
+/// Update a merchant arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Update a merchant params
+pub struct UpdateMerchantArgs {
+    pub params: MerchantParams,
+}
+
+deserialize_args!(UpdateMerchantArgs {
+    params: MerchantParams
+});
+
+/// Set a merchant status arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Set a merchant status params
+pub struct SetMerchantStatusArgs {
+    pub status: MerchantStatus,
+}
+
+deserialize_args!(SetMerchantStatusArgs {
+    status: MerchantStatus
+});
+
+/// Update the multisig arguments
+#[repr(C)]
+#[derive(BorshSerialize, PartialEq, Debug, Clone)]
+/// Update the multisig params
+pub struct SetMultisigArgs {
+    pub threshold: u8,
+    pub signers: Vec<Pubkey>,
+}
+
+deserialize_args!(SetMultisigArgs {
+    threshold: u8,
+    signers: Vec<Pubkey>,
+});
+
+/// Escrow action an authorization approves
+#[repr(C)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone, Copy)]
+pub enum AuthorizationAction {
+    InitEscrow,
+    Settle,
+    Cancel,
+    Refund,
 }
 
+/// Authorization message the authority signs off-chain, verified by an
+/// Ed25519 program instruction right before the card instruction
 #[repr(C)]
-#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Clone,)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+pub struct Authorization {
+    pub program_id: Pubkey,
+    pub reference: Pubkey,
+    pub amount: u64,
+    pub action: AuthorizationAction,
+    pub expiry: UnixTimestamp,
+    /// Hash of the arguments of the approved instruction, the default hash for
+    /// instructions without any
+    pub args_hash: Hash,
+}
+
+impl Authorization {
+    /// Prefix of every signed authorization, so no other message the authority
+    /// signs can pass for one
+    pub const DOMAIN: &'static [u8] = b"card:authorization:v1";
+
+    /// Hash of the Borsh encoding of instruction `args`
+    pub fn hash_args<T: BorshSerialize>(args: &T) -> Result<Hash, ProgramError> {
+        let data = args
+            .try_to_vec()
+            .map_err(|e| ProgramError::BorshIoError(e.to_string()))?;
+        Ok(hash(&data))
+    }
+
+    /// Bytes the authority signs, the domain followed by the Borsh encoding
+    pub fn message(&self) -> Result<Vec<u8>, ProgramError> {
+        let mut message = Self::DOMAIN.to_vec();
+        self.serialize(&mut message)
+            .map_err(|e| ProgramError::BorshIoError(e.to_string()))?;
+        Ok(message)
+    }
+}
+
+/// Instructions of the program, each encoded as its discriminator followed by the
+/// Borsh encoding of its arguments. Discriminators never change, a new instruction
+/// takes the next free one, and arguments only gain fields at their end, read as
+/// their default when left out by older clients
+#[derive(Debug, PartialEq, Clone)]
 pub enum CardInstruction {
     /// Accounts expected:
     ///
@@ -51,12 +519,17 @@ pub enum CardInstruction {
     /// 1. `[signer]` The authority responsible for approving due to some external conditions
     /// 2. `[signer]` The fee payer
     /// 3. `[writable]` The deposit account, it will hold all necessary info about the transaction.
-    /// 4. `[writable]` The source token account that will fund the transaction
+    /// 4. `[writable]` The source token account that will fund the transaction, for native SOL the user's wallet
     /// 5. `[writable]` The collection token account that will receive the amount
     /// 6. `[writable]` The collection fee token account that will receive the fee if the transaction is successful
     /// 7. `[]` The token mint
     /// 8. `[]` The rent sysvar
-    /// 9. `[]` The token program
+    /// 9. `[]` The system program
+    /// 10. `[]` The token program
+    /// 11. `[]` The PDA account, only when wrapping native SOL
+    /// 12. `[writable]` The temporary wrapped SOL account, only when wrapping native SOL
+    ///
+    /// The config, mint config, fee schedule and `[writable]` user card accounts come last
     InitDeposit(DepositArgs),
 
     /// Accounts expected:
@@ -65,12 +538,24 @@ pub enum CardInstruction {
     /// 1. `[signer]` The authority responsible for approving due to some external conditions
     /// 2. `[signer]` The fee payer
     /// 3. `[writable]` The withdraw account, it will hold all necessary info about the transaction.
-    /// 4. `[writable]` The source token account that will send the refund
-    /// 5. `[writable]` The destination token account that will receive the refund
+    /// 4. `[writable]` The source token account that will send the refund, for native SOL it can be the wallet
+    /// 5. `[writable]` The destination token account that will receive the refund, for native SOL it can be a wallet
     /// 6. `[writable]` The source token account that will send the refund
     /// 7. `[]` The token mint
     /// 8. `[]` The rent sysvar
-    /// 9. `[]` The token program
+    /// 9. `[]` The system program
+    /// 10. `[]` The token program
+    ///
+    /// Optionally, to create the destination and fee associated token accounts if missing:
+    /// 11. `[]` The associated token program
+    /// 12. `[]` The destination owner
+    /// 13. `[]` The fee collection account
+    ///
+    /// Followed, only when wrapping or unwrapping native SOL, by:
+    /// * `[]` The PDA account
+    /// * `[writable]` The temporary wrapped SOL account
+    ///
+    /// The config, mint config, fee schedule and `[writable]` wallet card accounts come last
     InitWithdrawal(WithdrawArgs),
     /// Starts the trade by creating and populating an escrow account and transferring ownership of the given temp token account to the PDA
     ///
@@ -78,11 +563,13 @@ pub enum CardInstruction {
     /// Accounts expected:
     ///
     /// 0. `[signer]`   The account of the wallet owner initializing the escrow
-    /// 1. `[signer]`   The escrow authority responsible for approving / refunding payments due to some external conditions
+    /// 1. `[signer]`   The escrow authority responsible for approving / refunding payments due to some external conditions, not a signer when approved through an `Authorization`,
+    ///    or the multisig account with its signers passed last
     /// 2. `[signer]`   The fee payer
     /// 3. `[writable]` The escrow account, it will hold all necessary info about the trade.
     /// 4. `[]` The vault owner
-    /// 5. `[writable]` The vault token account that holds the token amount
+    /// 5. `[writable]` The vault token account that holds the token amount, for native SOL the escrow vault address,
+    ///    for a hold the source token account
     /// 6. `[]` The src token account that will receive the amount if the transaction is canceled
     /// 7. `[]` The dst token account that will receive the amount if the transaction is successful
     /// 8. `[]` The fee token account that will receive the fee if the transaction is successful
@@ -91,13 +578,22 @@ pub enum CardInstruction {
     /// 11. `[]` The rent sysvar
     /// 12. `[]` The system program
     /// 13. `[]` The token program
-    InitEscrow (InitEscrowArgs),
+    ///
+    /// Optionally, to create the destination and fee associated token accounts if missing:
+    /// 14. `[]` The associated token program
+    /// 15. `[]` The deposit collection account
+    /// 16. `[]` The fee collection account
+    ///
+    /// The config, mint config, fee schedule and `[writable]` wallet card accounts come next, followed by the merchant
+    /// account when paying a merchant and the instructions sysvar when approved through an `Authorization`
+    InitEscrow(InitEscrowArgs),
     /// Settle the payment
     ///
     ///
     /// Accounts expected:
     ///
-    /// 0. `[signer]` The account of the authority
+    /// 0. `[signer]` The account of the authority, not a signer when approved through an `Authorization`,
+    ///    or the multisig account with its signers passed last
     /// 1. `[writable]` The destination token account for the token they will receive should the trade go through
     /// 2. `[writable]` The fee token account for the token they will receive should the trade go through
     /// 3. `[writable]` The vault token account to get tokens from and eventually close
@@ -105,212 +601,1729 @@ pub enum CardInstruction {
     /// 5. `[]` The token mint
     /// 6. `[]` The PDA account
     /// 7. `[]` The token program
+    /// 8. `[]` The system program
+    /// 9. `[]` The config account
+    /// 10. `[]` The merchant account, only when the escrow pays a merchant
+    /// 11. `[]` The instructions sysvar, only when approved through an `Authorization`
+    ///
+    /// When the escrow is split, the `[writable]` payee token accounts come last
     Settle,
     /// Cancel the escrow
     ///
     ///
     /// Accounts expected:
     ///
-    /// 0. `[signer]` The account of the authority
+    /// 0. `[signer]` The account of the authority, not a signer when approved through an `Authorization`,
+    ///    or the multisig account with its signers passed last
     /// 1. `[writable]` The escrow account holding the escrow info   
     /// 2. `[writable]` The src token account of the payer that initialized the escrow  
     /// 3. `[writable]` The vault token account to get tokens from and eventually close
-    /// 4. `[]` The token mint 
+    /// 4. `[]` The token mint
     /// 5. `[]` The PDA account
     /// 6. `[]` The token program
+    /// 7. `[]` The system program
+    /// 8. `[]` The config account
+    /// 9. `[]` The instructions sysvar, only when approved through an `Authorization`
+    ///
+    /// The `[writable]` card account the escrow was charged to comes last, when it was, to
+    /// give the amount back to its counters. The owner of the source token account may sign
+    /// last when canceling a hold, taking it off its allowance
     Cancel,
     /// Close the escrow
     ///
     ///
     /// Accounts expected:
     ///
-    /// 0. `[signer]` The account of the authority
+    /// 0. `[signer]` The account of the authority, or the multisig account with its signers passed last
     /// 1. `[writable]` The escrow account holding the escrow info     
     /// 2. `[writable]` The fee payer's main account to send their rent fees to
+    /// 3. `[]` The system program
+    /// 4. `[]` The config account
     Close,
-}
-
-/// Create `Deposit` instruction
-pub fn deposit(
-    program_id: &Pubkey,
-    user: &Pubkey,
-    authority: &Pubkey,
-    payer: &Pubkey,
-    deposit: &Pubkey,
-    source_token: &Pubkey,
-    collection_token: &Pubkey,
-    collection_fee_token: &Pubkey,
-    mint: &Pubkey,
-    reference: &Pubkey,
-    args: DepositArgs,
-) -> Instruction {
-    let accounts = vec![
-        AccountMeta::new_readonly(*user, true),
-        AccountMeta::new_readonly(*authority, true),
-        AccountMeta::new(*payer, true),
-        AccountMeta::new(*deposit, false),
-        AccountMeta::new(*source_token, false),
-        AccountMeta::new(*collection_token, false),
-        AccountMeta::new(*collection_fee_token, false),
-        AccountMeta::new_readonly(*mint, false),
-        AccountMeta::new_readonly(*reference, false),
-        AccountMeta::new_readonly(sysvar::rent::id(), false),
-        AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
-
-    Instruction::new_with_borsh(*program_id, &CardInstruction::InitDeposit(args), accounts)
-}
-
-/// Create `Withdraw` instruction
-pub fn withdraw(
-    program_id: &Pubkey,
-    wallet: &Pubkey,
-    authority: &Pubkey,
-    payer: &Pubkey,
-    withdraw: &Pubkey,
-    source_token: &Pubkey,
-    destination_token: &Pubkey,
-    collection_fee_token: &Pubkey,
-    mint: &Pubkey,
-    args: WithdrawArgs,
-) -> Instruction {
-    let accounts = vec![
-        AccountMeta::new_readonly(*wallet, true),
-        AccountMeta::new_readonly(*authority, true),
-        AccountMeta::new(*payer, true),
-        AccountMeta::new(*withdraw, false),
-        AccountMeta::new(*source_token, false),
-        AccountMeta::new(*destination_token, false),
-        AccountMeta::new(*collection_fee_token, false),
-        AccountMeta::new_readonly(*mint, false),
-        AccountMeta::new_readonly(sysvar::rent::id(), false),
-        AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
-
-    Instruction::new_with_borsh(
-        *program_id,
-        &CardInstruction::InitWithdrawal(args),
-        accounts,
-    )
-}
-
-/// Create `InitEscrow` instruction
-pub fn init_escrow(
-    program_id: &Pubkey,
-    wallet: &Pubkey,
-    authority: &Pubkey,
-    payer: &Pubkey,
-    escrow: &Pubkey,
-    vault_owner: &Pubkey,
-    vault_token: &Pubkey,
-    source_token: &Pubkey,
-    destination_token: &Pubkey,
-    collection_fee_token: &Pubkey,
-    mint: &Pubkey,
-    reference: &Pubkey,
-    args: InitEscrowArgs,
-) -> Instruction {
-    let accounts = vec![
-        AccountMeta::new_readonly(*wallet, true),
-        AccountMeta::new_readonly(*authority, true),
-        AccountMeta::new(*payer, true),
-        AccountMeta::new(*escrow, false),
-        AccountMeta::new_readonly(*vault_owner, false),
-        AccountMeta::new(*vault_token, false),
-        AccountMeta::new(*source_token, false),
-        AccountMeta::new(*destination_token, false),
-        AccountMeta::new(*collection_fee_token, false),
-        AccountMeta::new_readonly(*mint, false),
-        AccountMeta::new_readonly(*reference, false),
-        AccountMeta::new_readonly(sysvar::rent::id(), false),
-        AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(spl_token::id(), false),
-    ];
-
-    Instruction::new_with_borsh(
-        *program_id,
-        &CardInstruction::InitEscrow(args),
-        accounts,
-    )
-}
-
+    /// Create the M-of-N multisig approval authority
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin
+    /// 1. `[signer]` The fee payer
+    /// 2. `[writable]` The multisig account
+    /// 3. `[]` The rent sysvar
+    /// 4. `[]` The system program
+    /// 5. `[]` The config account
+    InitMultisig(InitMultisigArgs),
+    /// Change the signers and threshold of the multisig approval authority
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[writable]` The multisig account
+    /// 1. ..1+M `[signer]` M of the current signers
+    SetMultisig(SetMultisigArgs),
+    /// Create the config holding the keys of each role
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The upgrade authority of the program
+    /// 1. `[signer]` The fee payer
+    /// 2. `[writable]` The config account
+    /// 3. `[]` The rent sysvar
+    /// 4. `[]` The system program
+    /// 5. `[]` The program data account
+    InitConfig(InitConfigArgs),
+    /// Hand the roles to new keys
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[writable]` The config account
+    SetConfig(SetConfigArgs),
+    /// Pause or resume instructions, `paused_flags` is a mask of the `PAUSE_*` bits
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[writable]` The config account
+    SetPause(SetPauseArgs),
+    /// Support a mint
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[signer]` The fee payer
+    /// 2. `[writable]` The mint config account
+    /// 3. `[]` The token mint
+    /// 4. `[]` The rent sysvar
+    /// 5. `[]` The system program
+    /// 6. `[]` The config account
+    InitMintConfig(InitMintConfigArgs),
+    /// Change the parameters of a supported mint, or disable it
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[writable]` The mint config account
+    /// 2. `[]` The config account
+    SetMintConfig(SetMintConfigArgs),
+    /// Schedule the fees of a mint, replacing its default fee
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[signer]` The fee payer
+    /// 2. `[writable]` The fee schedule account
+    /// 3. `[]` The token mint
+    /// 4. `[]` The rent sysvar
+    /// 5. `[]` The system program
+    /// 6. `[]` The config account
+    /// 7. `[writable]` The mint config account, recording that the mint has a schedule
+    InitFeeSchedule(InitFeeScheduleArgs),
+    /// Change the fee rules of a mint
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[writable]` The fee schedule account
+    /// 2. `[]` The config account
+    SetFeeSchedule(SetFeeScheduleArgs),
+    /// Register a merchant and the accounts it is settled to
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[signer]` The fee payer
+    /// 2. `[writable]` The merchant account
+    /// 3. `[]` The rent sysvar
+    /// 4. `[]` The system program
+    /// 5. `[]` The config account
+    OnboardMerchant(OnboardMerchantArgs),
+    /// Change the settlement accounts and fee override of a merchant
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[writable]` The merchant account
+    /// 2. `[]` The config account
+    UpdateMerchant(UpdateMerchantArgs),
+    /// Suspend a merchant, or reinstate it
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[writable]` The merchant account
+    /// 2. `[]` The config account
+    SetMerchantStatus(SetMerchantStatusArgs),
+    /// Return up to the settled amount of an escrow to its source. The refund names
+    /// the amount refunded before it, so each approval refunds once
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The account of the authority, not a signer when approved through an `Authorization`,
+    ///    or the multisig account with its signers passed last
+    /// 1. `[writable]` The escrow account holding the escrow info
+    /// 2. `[writable]` The src token account of the payer that initialized the escrow
+    /// 3. `[writable]` The destination token account the escrow was settled to
+    /// 4. `[signer]` The owner of the destination token account, for native SOL the destination itself
+    /// 5. `[]` The token mint
+    /// 6. `[]` The token program
+    /// 7. `[]` The system program
+    /// 8. `[]` The config account
+    ///
+    /// When refunding the fee:
+    /// 9. `[writable]` The fee token account the escrow was settled to
+    /// 10. `[signer]` The owner of the fee token account, for native SOL the fee account itself
+    ///
+    /// The instructions sysvar comes last when approved through an `Authorization`
+    Refund(RefundArgs),
+    /// Freeze a pending or recently settled escrow until the dispute is resolved. A settled
+    /// escrow split between payees can't be disputed, it can't be refunded
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The account of the authority, or the multisig account with its signers passed last
+    /// 1. `[writable]` The escrow account holding the escrow info
+    /// 2. `[]` The clock sysvar
+    /// 3. `[]` The config account
+    Dispute(DisputeArgs),
+    /// Resolve a dispute, moving the funds the way `outcome` says
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The account of the authority, or the multisig account with its signers passed last
+    /// 1. `[writable]` The escrow account holding the escrow info
+    ///
+    /// Followed by the accounts of the instruction completing the outcome: `Settle` to release a
+    /// pending escrow, `Cancel` to refund it, or `Refund` with the fee accounts to refund a settled
+    /// one. Releasing a settled escrow moves no funds and only needs the config account.
+    /// Releasing a pending escrow is refused while settlements are paused.
+    ///
+    /// The funds of a settled escrow have left the program, so refunding it needs the
+    /// signatures `Refund` asks of the owners of the destination and fee accounts: the
+    /// merchant has to take part, the canceler can't claw the payment back alone.
+    ResolveDispute(ResolveDisputeArgs),
+    /// Issue a card to a cardholder
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[signer]` The fee payer
+    /// 2. `[writable]` The card account
+    /// 3. `[]` The rent sysvar
+    /// 4. `[]` The system program
+    /// 5. `[]` The config account
+    InitCard(InitCardArgs),
+    /// Change the funding account and limits of a card
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The config admin, or the multisig account with its signers passed last
+    /// 1. `[writable]` The card account
+    /// 2. `[]` The config account
+    UpdateCard(UpdateCardArgs),
+    /// Stop all spending with a card, either by the cardholder or by the issuer
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The cardholder, the config admin or the multisig account with its signers passed last
+    /// 1. `[writable]` The card account
+    /// 2. `[]` The config account
+    FreezeCard,
+    /// Lift the freeze of a card, the cardholder can only lift their own
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The cardholder, the config admin or the multisig account with its signers passed last
+    /// 1. `[writable]` The card account
+    /// 2. `[]` The config account
+    UnfreezeCard,
+    /// Replace the merchant categories a card may be spent at, the cardholder can't
+    /// change the categories set by the issuer
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The cardholder, the config admin or the multisig account with its signers passed last
+    /// 1. `[writable]` The card account
+    /// 2. `[]` The config account
+    SetCardCategories(SetCardCategoriesArgs),
+    /// Sign a mandate letting a merchant charge the source token account once per period,
+    /// the owner delegates the source token account to the mandate delegate. A token account
+    /// has a single delegate, so approving a hold or another mandate on the same source
+    /// stops the charges of this one until the owner approves its delegate again
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The owner of the source token account
+    /// 1. `[signer]` The fee payer
+    /// 2. `[writable]` The mandate account
+    /// 3. `[]` The source token account
+    /// 4. `[]` The merchant account
+    /// 5. `[]` The rent sysvar
+    /// 6. `[]` The system program
+    InitMandate(InitMandateArgs),
+    /// Charge a mandate, at most once per period, as a purchase in the category of the merchant
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The merchant id, the settler or the multisig account with its signers passed last
+    /// 1. `[writable]` The mandate account
+    /// 2. `[writable]` The source token account
+    /// 3. `[writable]` The settlement token account of the merchant
+    /// 4. `[]` The mandate delegate
+    /// 5. `[]` The token program
+    ///
+    /// The config, merchant and `[writable]` owner card accounts come last
+    ChargeMandate(ChargeMandateArgs),
+    /// Cancel a mandate, closing its account and revoking the mandate delegate if it is still
+    /// the delegate of the source token account
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The owner of the mandate
+    /// 1. `[writable]` The mandate account
+    /// 2. `[writable]` The account receiving the rent
+    /// 3. `[writable]` The source token account
+    /// 4. `[]` The token program
+    CancelMandate,
+    /// Move an amount into a vault of the program authority, to be paid out once unlocked
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The owner of the source token account
+    /// 1. `[signer]` The withdrawal approver, or the multisig account with its signers passed last
+    /// 2. `[signer]` The fee payer
+    /// 3. `[writable]` The scheduled payout account
+    /// 4. `[writable]` The source token account
+    /// 5. `[writable]` The vault token account, owned by the PDA account
+    /// 6. `[]` The beneficiary token account
+    /// 7. `[]` The token mint
+    /// 8. `[]` The rent sysvar
+    /// 9. `[]` The system program
+    /// 10. `[]` The token program
+    ///
+    /// The config, mint config and `[writable]` owner card accounts come last
+    SchedulePayout(SchedulePayoutArgs),
+    /// Pay out an unlocked payout to its beneficiary, anyone may crank it
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[writable]` The scheduled payout account
+    /// 1. `[writable]` The vault token account
+    /// 2. `[writable]` The beneficiary token account
+    /// 3. `[]` The PDA account
+    /// 4. `[]` The token program
+    /// 5. `[writable]` The account that paid the rent of the scheduled payout, getting it back
+    /// 6. `[]` The config account
+    ///
+    /// Paused along with withdrawals
+    ExecutePayout,
+    /// Return a payout to its source before it unlocks
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The withdrawal approver, or the multisig account with its signers passed last
+    /// 1. `[writable]` The scheduled payout account
+    /// 2. `[writable]` The vault token account
+    /// 3. `[writable]` The source token account
+    /// 4. `[]` The PDA account
+    /// 5. `[]` The token program
+    /// 6. `[writable]` The account that paid the rent of the scheduled payout, getting it back
+    /// 7. `[]` The config account
+    ///
+    /// Never paused, so funds can always go back to their source
+    CancelPayout,
+    /// Settle escrows of the same mint sharing a fee token account, the fees of the
+    /// escrows sharing a token vault being collected at once. Escrows that can't be
+    /// settled are skipped, each escrow being reported in a `CardEvent`.
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The settler, or the multisig account with its signers passed last
+    /// 1. `[writable]` The fee token account
+    /// 2. `[]` The token mint
+    /// 3. `[]` The PDA account
+    /// 4. `[]` The token program
+    /// 5. `[]` The system program
+    /// 6. `[]` The config account
+    ///
+    /// Followed, for each escrow, by:
+    /// * `[writable]` The escrow account
+    /// * `[writable]` The vault token account
+    /// * `[writable]` The destination token account
+    ///
+    /// The merchant accounts of the escrows paying a merchant and the `[writable]`
+    /// payee token accounts of the split escrows come last
+    SettleBatch(SettleBatchArgs),
+
+    /// Record deposits of the same source, moving their summed amount and
+    /// summed fee in a single transfer each. A card counts the batch as one
+    /// transaction, its summed amount checked against the per transaction limit.
+    /// A batch holds at most `MAX_BATCH_DEPOSITS` deposits
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The account of the source owner
+    /// 1. `[signer]` The authority responsible for approving due to some external conditions
+    /// 2. `[signer]` The fee payer
+    /// 3. `[writable]` The source token account that will fund the deposits
+    /// 4. `[writable]` The collection token account that will receive the amounts
+    /// 5. `[writable]` The collection fee token account that will receive the fees
+    /// 6. `[]` The token mint
+    /// 7. `[]` The rent sysvar
+    /// 8. `[]` The system program
+    /// 9. `[]` The token program
+    ///
+    /// Followed by the `[writable]` deposit account of each deposit.
+    ///
+    /// The config, mint config, fee schedule and `[writable]` source owner card accounts come last
+    InitDepositBatch(DepositBatchArgs),
+    /// Grow an escrow written by the first release of the program to the current
+    /// layout, filling in the fields that layout lacks
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[writable, signer]` The payer of the extra rent
+    /// 1. `[writable]` The escrow account
+    /// 2. `[]` The system program
+    ///
+    /// For a native escrow, whose lamports move from the program authority to its vault:
+    /// 3. `[writable]` The program authority
+    /// 4. `[writable]` The vault of the escrow
+    Migrate,
+    /// Close a card, either by the cardholder or by the issuer, deleting the account so the
+    /// wallet spends without a card until it is issued one again. The cardholder can't close
+    /// a card the issuer froze
+    ///
+    ///
+    /// Accounts expected:
+    ///
+    /// 0. `[signer]` The cardholder, the config admin or the multisig account with its signers passed last
+    /// 1. `[writable]` The card account
+    /// 2. `[writable]` The account receiving the rent of the card
+    /// 3. `[]` The config account
+    CloseCard,
+}
+
+impl CardInstruction {
+    pub const INIT_DEPOSIT: u8 = 0;
+    pub const INIT_WITHDRAWAL: u8 = 1;
+    pub const INIT_ESCROW: u8 = 2;
+    pub const SETTLE: u8 = 3;
+    pub const CANCEL: u8 = 4;
+    pub const CLOSE: u8 = 5;
+    pub const INIT_MULTISIG: u8 = 6;
+    pub const SET_MULTISIG: u8 = 7;
+    pub const INIT_CONFIG: u8 = 8;
+    pub const SET_CONFIG: u8 = 9;
+    pub const SET_PAUSE: u8 = 10;
+    pub const INIT_MINT_CONFIG: u8 = 11;
+    pub const SET_MINT_CONFIG: u8 = 12;
+    pub const INIT_FEE_SCHEDULE: u8 = 13;
+    pub const SET_FEE_SCHEDULE: u8 = 14;
+    pub const ONBOARD_MERCHANT: u8 = 15;
+    pub const UPDATE_MERCHANT: u8 = 16;
+    pub const SET_MERCHANT_STATUS: u8 = 17;
+    pub const REFUND: u8 = 18;
+    pub const DISPUTE: u8 = 19;
+    pub const RESOLVE_DISPUTE: u8 = 20;
+    pub const INIT_CARD: u8 = 21;
+    pub const UPDATE_CARD: u8 = 22;
+    pub const FREEZE_CARD: u8 = 23;
+    pub const UNFREEZE_CARD: u8 = 24;
+    pub const SET_CARD_CATEGORIES: u8 = 25;
+    pub const INIT_MANDATE: u8 = 26;
+    pub const CHARGE_MANDATE: u8 = 27;
+    pub const CANCEL_MANDATE: u8 = 28;
+    pub const SCHEDULE_PAYOUT: u8 = 29;
+    pub const EXECUTE_PAYOUT: u8 = 30;
+    pub const CANCEL_PAYOUT: u8 = 31;
+    pub const SETTLE_BATCH: u8 = 32;
+    pub const INIT_DEPOSIT_BATCH: u8 = 33;
+    pub const MIGRATE: u8 = 34;
+    pub const CLOSE_CARD: u8 = 35;
+
+    /// Discriminator of the instruction, the first byte of its data
+    pub fn discriminator(&self) -> u8 {
+        match self {
+            CardInstruction::InitDeposit(_) => Self::INIT_DEPOSIT,
+            CardInstruction::InitWithdrawal(_) => Self::INIT_WITHDRAWAL,
+            CardInstruction::InitEscrow(_) => Self::INIT_ESCROW,
+            CardInstruction::Settle => Self::SETTLE,
+            CardInstruction::Cancel => Self::CANCEL,
+            CardInstruction::Close => Self::CLOSE,
+            CardInstruction::InitMultisig(_) => Self::INIT_MULTISIG,
+            CardInstruction::SetMultisig(_) => Self::SET_MULTISIG,
+            CardInstruction::InitConfig(_) => Self::INIT_CONFIG,
+            CardInstruction::SetConfig(_) => Self::SET_CONFIG,
+            CardInstruction::SetPause(_) => Self::SET_PAUSE,
+            CardInstruction::InitMintConfig(_) => Self::INIT_MINT_CONFIG,
+            CardInstruction::SetMintConfig(_) => Self::SET_MINT_CONFIG,
+            CardInstruction::InitFeeSchedule(_) => Self::INIT_FEE_SCHEDULE,
+            CardInstruction::SetFeeSchedule(_) => Self::SET_FEE_SCHEDULE,
+            CardInstruction::OnboardMerchant(_) => Self::ONBOARD_MERCHANT,
+            CardInstruction::UpdateMerchant(_) => Self::UPDATE_MERCHANT,
+            CardInstruction::SetMerchantStatus(_) => Self::SET_MERCHANT_STATUS,
+            CardInstruction::Refund(_) => Self::REFUND,
+            CardInstruction::Dispute(_) => Self::DISPUTE,
+            CardInstruction::ResolveDispute(_) => Self::RESOLVE_DISPUTE,
+            CardInstruction::InitCard(_) => Self::INIT_CARD,
+            CardInstruction::UpdateCard(_) => Self::UPDATE_CARD,
+            CardInstruction::FreezeCard => Self::FREEZE_CARD,
+            CardInstruction::UnfreezeCard => Self::UNFREEZE_CARD,
+            CardInstruction::SetCardCategories(_) => Self::SET_CARD_CATEGORIES,
+            CardInstruction::InitMandate(_) => Self::INIT_MANDATE,
+            CardInstruction::ChargeMandate(_) => Self::CHARGE_MANDATE,
+            CardInstruction::CancelMandate => Self::CANCEL_MANDATE,
+            CardInstruction::SchedulePayout(_) => Self::SCHEDULE_PAYOUT,
+            CardInstruction::ExecutePayout => Self::EXECUTE_PAYOUT,
+            CardInstruction::CancelPayout => Self::CANCEL_PAYOUT,
+            CardInstruction::SettleBatch(_) => Self::SETTLE_BATCH,
+            CardInstruction::InitDepositBatch(_) => Self::INIT_DEPOSIT_BATCH,
+            CardInstruction::Migrate => Self::MIGRATE,
+            CardInstruction::CloseCard => Self::CLOSE_CARD,
+        }
+    }
+
+    /// Decode instruction `data`, failing with `InvalidInstruction` on an unknown
+    /// discriminator and `InvalidInstructionData` on malformed arguments
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let (&discriminator, mut args) = data.split_first().ok_or(CardError::InvalidInstruction)?;
+        let instruction = Self::deserialize_args(discriminator, &mut args)
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+            .ok_or(CardError::InvalidInstruction)?;
+        if !args.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(instruction)
+    }
+
+    /// Arguments of the instruction of `discriminator`, none for an unknown one
+    fn deserialize_args<R: Read>(discriminator: u8, reader: &mut R) -> io::Result<Option<Self>> {
+        Ok(Some(match discriminator {
+            Self::INIT_DEPOSIT => {
+                CardInstruction::InitDeposit(DepositArgs::deserialize_reader(reader)?)
+            }
+            Self::INIT_WITHDRAWAL => {
+                CardInstruction::InitWithdrawal(WithdrawArgs::deserialize_reader(reader)?)
+            }
+            Self::INIT_ESCROW => {
+                CardInstruction::InitEscrow(InitEscrowArgs::deserialize_reader(reader)?)
+            }
+            Self::SETTLE => CardInstruction::Settle,
+            Self::CANCEL => CardInstruction::Cancel,
+            Self::CLOSE => CardInstruction::Close,
+            Self::INIT_MULTISIG => {
+                CardInstruction::InitMultisig(InitMultisigArgs::deserialize_reader(reader)?)
+            }
+            Self::SET_MULTISIG => {
+                CardInstruction::SetMultisig(SetMultisigArgs::deserialize_reader(reader)?)
+            }
+            Self::INIT_CONFIG => {
+                CardInstruction::InitConfig(InitConfigArgs::deserialize_reader(reader)?)
+            }
+            Self::SET_CONFIG => {
+                CardInstruction::SetConfig(SetConfigArgs::deserialize_reader(reader)?)
+            }
+            Self::SET_PAUSE => CardInstruction::SetPause(SetPauseArgs::deserialize_reader(reader)?),
+            Self::INIT_MINT_CONFIG => {
+                CardInstruction::InitMintConfig(InitMintConfigArgs::deserialize_reader(reader)?)
+            }
+            Self::SET_MINT_CONFIG => {
+                CardInstruction::SetMintConfig(SetMintConfigArgs::deserialize_reader(reader)?)
+            }
+            Self::INIT_FEE_SCHEDULE => {
+                CardInstruction::InitFeeSchedule(InitFeeScheduleArgs::deserialize_reader(reader)?)
+            }
+            Self::SET_FEE_SCHEDULE => {
+                CardInstruction::SetFeeSchedule(SetFeeScheduleArgs::deserialize_reader(reader)?)
+            }
+            Self::ONBOARD_MERCHANT => {
+                CardInstruction::OnboardMerchant(OnboardMerchantArgs::deserialize_reader(reader)?)
+            }
+            Self::UPDATE_MERCHANT => {
+                CardInstruction::UpdateMerchant(UpdateMerchantArgs::deserialize_reader(reader)?)
+            }
+            Self::SET_MERCHANT_STATUS => CardInstruction::SetMerchantStatus(
+                SetMerchantStatusArgs::deserialize_reader(reader)?,
+            ),
+            Self::REFUND => CardInstruction::Refund(RefundArgs::deserialize_reader(reader)?),
+            Self::DISPUTE => CardInstruction::Dispute(DisputeArgs::deserialize_reader(reader)?),
+            Self::RESOLVE_DISPUTE => {
+                CardInstruction::ResolveDispute(ResolveDisputeArgs::deserialize_reader(reader)?)
+            }
+            Self::INIT_CARD => CardInstruction::InitCard(InitCardArgs::deserialize_reader(reader)?),
+            Self::UPDATE_CARD => {
+                CardInstruction::UpdateCard(UpdateCardArgs::deserialize_reader(reader)?)
+            }
+            Self::FREEZE_CARD => CardInstruction::FreezeCard,
+            Self::UNFREEZE_CARD => CardInstruction::UnfreezeCard,
+            Self::SET_CARD_CATEGORIES => CardInstruction::SetCardCategories(
+                SetCardCategoriesArgs::deserialize_reader(reader)?,
+            ),
+            Self::INIT_MANDATE => {
+                CardInstruction::InitMandate(InitMandateArgs::deserialize_reader(reader)?)
+            }
+            Self::CHARGE_MANDATE => {
+                CardInstruction::ChargeMandate(ChargeMandateArgs::deserialize_reader(reader)?)
+            }
+            Self::CANCEL_MANDATE => CardInstruction::CancelMandate,
+            Self::SCHEDULE_PAYOUT => {
+                CardInstruction::SchedulePayout(SchedulePayoutArgs::deserialize_reader(reader)?)
+            }
+            Self::EXECUTE_PAYOUT => CardInstruction::ExecutePayout,
+            Self::CANCEL_PAYOUT => CardInstruction::CancelPayout,
+            Self::SETTLE_BATCH => {
+                CardInstruction::SettleBatch(SettleBatchArgs::deserialize_reader(reader)?)
+            }
+            Self::INIT_DEPOSIT_BATCH => {
+                CardInstruction::InitDepositBatch(DepositBatchArgs::deserialize_reader(reader)?)
+            }
+            Self::MIGRATE => CardInstruction::Migrate,
+            Self::CLOSE_CARD => CardInstruction::CloseCard,
+            _ => return Ok(None),
+        }))
+    }
+}
+
+impl BorshSerialize for CardInstruction {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&[self.discriminator()])?;
+        match self {
+            CardInstruction::InitDeposit(args) => args.serialize(writer),
+            CardInstruction::InitWithdrawal(args) => args.serialize(writer),
+            CardInstruction::InitEscrow(args) => args.serialize(writer),
+            CardInstruction::InitMultisig(args) => args.serialize(writer),
+            CardInstruction::SetMultisig(args) => args.serialize(writer),
+            CardInstruction::InitConfig(args) => args.serialize(writer),
+            CardInstruction::SetConfig(args) => args.serialize(writer),
+            CardInstruction::SetPause(args) => args.serialize(writer),
+            CardInstruction::InitMintConfig(args) => args.serialize(writer),
+            CardInstruction::SetMintConfig(args) => args.serialize(writer),
+            CardInstruction::InitFeeSchedule(args) => args.serialize(writer),
+            CardInstruction::SetFeeSchedule(args) => args.serialize(writer),
+            CardInstruction::OnboardMerchant(args) => args.serialize(writer),
+            CardInstruction::UpdateMerchant(args) => args.serialize(writer),
+            CardInstruction::SetMerchantStatus(args) => args.serialize(writer),
+            CardInstruction::Refund(args) => args.serialize(writer),
+            CardInstruction::Dispute(args) => args.serialize(writer),
+            CardInstruction::ResolveDispute(args) => args.serialize(writer),
+            CardInstruction::InitCard(args) => args.serialize(writer),
+            CardInstruction::UpdateCard(args) => args.serialize(writer),
+            CardInstruction::SetCardCategories(args) => args.serialize(writer),
+            CardInstruction::InitMandate(args) => args.serialize(writer),
+            CardInstruction::ChargeMandate(args) => args.serialize(writer),
+            CardInstruction::SchedulePayout(args) => args.serialize(writer),
+            CardInstruction::SettleBatch(args) => args.serialize(writer),
+            CardInstruction::InitDepositBatch(args) => args.serialize(writer),
+            CardInstruction::Settle
+            | CardInstruction::Cancel
+            | CardInstruction::Close
+            | CardInstruction::FreezeCard
+            | CardInstruction::UnfreezeCard
+            | CardInstruction::CancelMandate
+            | CardInstruction::ExecutePayout
+            | CardInstruction::CancelPayout
+            | CardInstruction::Migrate
+            | CardInstruction::CloseCard => Ok(()),
+        }
+    }
+}
+
+impl BorshDeserialize for CardInstruction {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let discriminator = u8::deserialize_reader(reader)?;
+        Self::deserialize_args(discriminator, reader)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Unknown card instruction"))
+    }
+}
+
+/// Create `Deposit` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    deposit: &Pubkey,
+    source_token: &Pubkey,
+    collection_token: &Pubkey,
+    collection_fee_token: &Pubkey,
+    mint: &Pubkey,
+    args: DepositArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*user, true),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*deposit, false),
+        AccountMeta::new(*source_token, false),
+        AccountMeta::new(*collection_token, false),
+        AccountMeta::new(*collection_fee_token, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(find_mint_config_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_fee_schedule_program_address(program_id, mint).0, false),
+        AccountMeta::new(find_card_program_address(program_id, user).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::InitDeposit(args), accounts)
+}
+
+/// Create `Withdraw` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    withdraw: &Pubkey,
+    source_token: &Pubkey,
+    destination_token: &Pubkey,
+    collection_fee_token: &Pubkey,
+    mint: &Pubkey,
+    args: WithdrawArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*wallet, true),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*withdraw, false),
+        AccountMeta::new(*source_token, false),
+        AccountMeta::new(*destination_token, false),
+        AccountMeta::new(*collection_fee_token, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(find_mint_config_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_fee_schedule_program_address(program_id, mint).0, false),
+        AccountMeta::new(find_card_program_address(program_id, wallet).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::InitWithdrawal(args),
+        accounts,
+    )
+}
+
+/// Create `Deposit` instruction funded with native SOL from the user's wallet
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_native(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    deposit: &Pubkey,
+    collection_token: &Pubkey,
+    collection_fee_token: &Pubkey,
+    vault_owner: &Pubkey,
+    wrap: &Pubkey,
+    args: DepositArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*user, true),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*deposit, false),
+        AccountMeta::new(*user, false),
+        AccountMeta::new(*collection_token, false),
+        AccountMeta::new(*collection_fee_token, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*vault_owner, false),
+        AccountMeta::new(*wrap, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(
+            find_mint_config_program_address(program_id, &spl_token::native_mint::id()).0,
+            false,
+        ),
+        AccountMeta::new_readonly(
+            find_fee_schedule_program_address(program_id, &spl_token::native_mint::id()).0,
+            false,
+        ),
+        AccountMeta::new(find_card_program_address(program_id, user).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::InitDeposit(args), accounts)
+}
+
+/// Create `Withdraw` instruction wrapping native SOL from the wallet and / or
+/// unwrapping it to a destination wallet
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_native(
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    withdraw: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    collection_fee_token: &Pubkey,
+    vault_owner: &Pubkey,
+    wrap: &Pubkey,
+    args: WithdrawArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*wallet, true),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*withdraw, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new(*collection_fee_token, false),
+        AccountMeta::new_readonly(spl_token::native_mint::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(*vault_owner, false),
+        AccountMeta::new(*wrap, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(
+            find_mint_config_program_address(program_id, &spl_token::native_mint::id()).0,
+            false,
+        ),
+        AccountMeta::new_readonly(
+            find_fee_schedule_program_address(program_id, &spl_token::native_mint::id()).0,
+            false,
+        ),
+        AccountMeta::new(find_card_program_address(program_id, wallet).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::InitWithdrawal(args),
+        accounts,
+    )
+}
+
+/// Opt an `InitEscrow` or `InitWithdrawal` instruction in to creating the
+/// destination and fee associated token accounts when missing
+pub fn with_associated_token_accounts(
+    mut instruction: Instruction,
+    destination_owner: &Pubkey,
+    fee_owner: &Pubkey,
+) -> Instruction {
+    let index = instruction
+        .accounts
+        .iter()
+        .position(|meta| meta.pubkey == spl_token::id())
+        .map_or(instruction.accounts.len(), |index| index + 1);
+    instruction.accounts.splice(
+        index..index,
+        [
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(*destination_owner, false),
+            AccountMeta::new_readonly(*fee_owner, false),
+        ],
+    );
+    instruction
+}
+
+/// Have an `InitEscrow`, `Settle` or `Cancel` instruction approved by an
+/// Ed25519 signature of the authority in the preceding instruction rather than
+/// by the authority signing the transaction
+pub fn with_ed25519_authorization(mut instruction: Instruction, authority: &Pubkey) -> Instruction {
+    for meta in instruction.accounts.iter_mut() {
+        if meta.pubkey == *authority {
+            meta.is_signer = false;
+        }
+    }
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    instruction
+}
+
+/// Create `InitEscrow` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn init_escrow(
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    escrow: &Pubkey,
+    vault_owner: &Pubkey,
+    vault_token: &Pubkey,
+    source_token: &Pubkey,
+    destination_token: &Pubkey,
+    collection_fee_token: &Pubkey,
+    mint: &Pubkey,
+    reference: &Pubkey,
+    args: InitEscrowArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*wallet, true),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(*vault_owner, false),
+        AccountMeta::new(*vault_token, false),
+        AccountMeta::new(*source_token, false),
+        AccountMeta::new(*destination_token, false),
+        AccountMeta::new(*collection_fee_token, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*reference, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(find_mint_config_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_fee_schedule_program_address(program_id, mint).0, false),
+        AccountMeta::new(find_card_program_address(program_id, wallet).0, false),
+    ];
+    let mut instruction = Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::InitEscrow(args.clone()),
+        accounts,
+    );
+    if let Some(merchant) = args.merchant {
+        instruction = with_merchant(instruction, program_id, &merchant);
+    }
+    instruction
+}
+
+/// Pass the merchant account of `merchant` to an `InitEscrow` or `Settle`
+/// instruction paying that merchant
+pub fn with_merchant(
+    mut instruction: Instruction,
+    program_id: &Pubkey,
+    merchant: &Pubkey,
+) -> Instruction {
+    instruction.accounts.push(AccountMeta::new_readonly(
+        find_merchant_program_address(program_id, merchant).0,
+        false,
+    ));
+    instruction
+}
+
+/// Pass the card account of `wallet` to a `Cancel` instruction of an escrow charged to it
+pub fn with_card(
+    mut instruction: Instruction,
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+) -> Instruction {
+    instruction.accounts.push(AccountMeta::new(
+        find_card_program_address(program_id, wallet).0,
+        false,
+    ));
+    instruction
+}
+
+/// Pass the payee token accounts to a `Settle` instruction splitting the escrow
+pub fn with_payees(mut instruction: Instruction, payees: &[Pubkey]) -> Instruction {
+    instruction
+        .accounts
+        .extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    instruction
+}
+
 /// Create `SettleEscrow` instruction
+#[allow(clippy::too_many_arguments)]
 pub fn settle_escrow(
     program_id: &Pubkey,
     authority: &Pubkey,
     destination_token: &Pubkey,
     collection_fee_token: &Pubkey,
-    vault_token: &Pubkey,
-    escrow: &Pubkey,
+    vault_token: &Pubkey,
+    escrow: &Pubkey,
+    mint: &Pubkey,
+    vault_owner: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*destination_token, false),
+        AccountMeta::new(*collection_fee_token, false),
+        AccountMeta::new(*vault_token, false),
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*vault_owner, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::Settle, accounts)
+}
+
+/// Have the owner of the source token account sign a `Cancel` instruction of a
+/// hold, taking the hold off its allowance, which stands otherwise
+pub fn with_source_owner(mut instruction: Instruction, owner: &Pubkey) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*owner, true));
+    instruction
+}
+
+/// Create `CancelEscrow` instruction
+pub fn cancel_escrow(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    escrow: &Pubkey,
+    src_token: &Pubkey,
+    vault_token: &Pubkey,
+    mint: &Pubkey,
+    vault_owner: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new(*src_token, false),
+        AccountMeta::new(*vault_token, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*vault_owner, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::Cancel, accounts)
+}
+
+/// Create `CloseEscrow` instruction
+pub fn close_escrow(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    escrow: &Pubkey,
+    fee_payer: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new(*fee_payer, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::Close, accounts)
+}
+
+/// Create `InitMultisig` instruction
+pub fn init_multisig(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    multisig: &Pubkey,
+    args: InitMultisigArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*multisig, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::InitMultisig(args), accounts)
+}
+
+/// Create `SetMultisig` instruction
+pub fn set_multisig(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    signers: &[&Pubkey],
+    args: SetMultisigArgs,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*multisig, false)];
+    accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true)),
+    );
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::SetMultisig(args), accounts)
+}
+
+/// Have an instruction approved by members of the multisig, passed in place of
+/// the authority, rather than by the authority
+pub fn with_multisig_approval(
+    mut instruction: Instruction,
+    multisig: &Pubkey,
+    signers: &[&Pubkey],
+) -> Instruction {
+    for meta in instruction.accounts.iter_mut() {
+        if meta.pubkey == *multisig {
+            meta.is_signer = false;
+        }
+    }
+    instruction.accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true)),
+    );
+    instruction
+}
+
+/// Create `InitConfig` instruction
+pub fn init_config(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    args: InitConfigArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_program_data_address(program_id), false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::InitConfig(args), accounts)
+}
+
+/// Create `SetConfig` instruction
+pub fn set_config(program_id: &Pubkey, admin: &Pubkey, roles: Roles) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::SetConfig(SetConfigArgs { roles }),
+        accounts,
+    )
+}
+
+/// Create `SetPause` instruction
+pub fn set_pause(program_id: &Pubkey, admin: &Pubkey, paused_flags: u64) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::SetPause(SetPauseArgs { paused_flags }),
+        accounts,
+    )
+}
+
+/// Create `InitMintConfig` instruction
+pub fn init_mint_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    args: InitMintConfigArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_mint_config_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::InitMintConfig(args),
+        accounts,
+    )
+}
+
+/// Create `SetMintConfig` instruction
+pub fn set_mint_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    params: MintParams,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(find_mint_config_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::SetMintConfig(SetMintConfigArgs { params }),
+        accounts,
+    )
+}
+
+/// Create `InitFeeSchedule` instruction
+pub fn init_fee_schedule(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    payer: &Pubkey,
+    mint: &Pubkey,
+    args: InitFeeScheduleArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_fee_schedule_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new(find_mint_config_program_address(program_id, mint).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::InitFeeSchedule(args),
+        accounts,
+    )
+}
+
+/// Create `SetFeeSchedule` instruction
+pub fn set_fee_schedule(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    mint: &Pubkey,
+    rules: FeeRules,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(find_fee_schedule_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::SetFeeSchedule(SetFeeScheduleArgs { rules }),
+        accounts,
+    )
+}
+
+/// Create `OnboardMerchant` instruction
+pub fn onboard_merchant(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    payer: &Pubkey,
+    args: OnboardMerchantArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_merchant_program_address(program_id, &args.id).0, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::OnboardMerchant(args),
+        accounts,
+    )
+}
+
+/// Create `UpdateMerchant` instruction
+pub fn update_merchant(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    merchant: &Pubkey,
+    params: MerchantParams,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(find_merchant_program_address(program_id, merchant).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::UpdateMerchant(UpdateMerchantArgs { params }),
+        accounts,
+    )
+}
+
+/// Create `SetMerchantStatus` instruction
+pub fn set_merchant_status(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    merchant: &Pubkey,
+    status: MerchantStatus,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(find_merchant_program_address(program_id, merchant).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::SetMerchantStatus(SetMerchantStatusArgs { status }),
+        accounts,
+    )
+}
+
+/// Create `Refund` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn refund(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    escrow: &Pubkey,
+    src_token: &Pubkey,
+    destination_token: &Pubkey,
+    destination_owner: &Pubkey,
+    collection_fee_token: &Pubkey,
+    collection_fee_owner: &Pubkey,
     mint: &Pubkey,
-    vault_owner: &Pubkey,
+    args: RefundArgs,
 ) -> Instruction {
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new_readonly(*authority, true),
-        AccountMeta::new(*destination_token, false),
-        AccountMeta::new(*collection_fee_token, false),
-        AccountMeta::new(*vault_token, false),
         AccountMeta::new(*escrow, false),
+        AccountMeta::new(*src_token, false),
+        AccountMeta::new(*destination_token, false),
+        AccountMeta::new_readonly(*destination_owner, true),
         AccountMeta::new_readonly(*mint, false),
-        AccountMeta::new_readonly(*vault_owner, false),
         AccountMeta::new_readonly(spl_token::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+    if args.refund_fee {
+        accounts.push(AccountMeta::new(*collection_fee_token, false));
+        accounts.push(AccountMeta::new_readonly(*collection_fee_owner, true));
+    }
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::Refund(args), accounts)
+}
+
+/// Create `Dispute` instruction
+pub fn dispute(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    escrow: &Pubkey,
+    args: DisputeArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*escrow, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::Dispute(args), accounts)
+}
+
+/// Create `ResolveDispute` instruction, `completion` being the `Settle`, `Cancel`
+/// or `Refund` instruction moving the funds, none when releasing a settled escrow
+pub fn resolve_dispute(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    escrow: &Pubkey,
+    args: ResolveDisputeArgs,
+    completion: Option<Instruction>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*escrow, false),
+    ];
+    match completion {
+        Some(completion) => accounts.extend(completion.accounts),
+        None => accounts.push(AccountMeta::new_readonly(
+            find_config_program_address(program_id).0,
+            false,
+        )),
+    }
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::ResolveDispute(args),
+        accounts,
+    )
+}
+
+/// Create `InitCard` instruction
+pub fn init_card(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    payer: &Pubkey,
+    args: InitCardArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_card_program_address(program_id, &args.owner).0, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::InitCard(args), accounts)
+}
+
+/// Create `UpdateCard` instruction
+pub fn update_card(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    owner: &Pubkey,
+    params: CardParams,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(find_card_program_address(program_id, owner).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
     ];
 
     Instruction::new_with_borsh(
         *program_id,
-        &CardInstruction::Settle,
+        &CardInstruction::UpdateCard(UpdateCardArgs { params }),
         accounts,
     )
 }
 
-/// Create `CancelEscrow` instruction
-pub fn cancel_escrow(
+/// Create `FreezeCard` instruction
+pub fn freeze_card(program_id: &Pubkey, authority: &Pubkey, owner: &Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(find_card_program_address(program_id, owner).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::FreezeCard, accounts)
+}
+
+/// Create `UnfreezeCard` instruction
+pub fn unfreeze_card(program_id: &Pubkey, authority: &Pubkey, owner: &Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(find_card_program_address(program_id, owner).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::UnfreezeCard, accounts)
+}
+
+/// Create `CloseCard` instruction, the rent of the card going to `receiver`
+pub fn close_card(
     program_id: &Pubkey,
     authority: &Pubkey,
-    escrow: &Pubkey,
-    src_token: &Pubkey,
+    owner: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(find_card_program_address(program_id, owner).0, false),
+        AccountMeta::new(*receiver, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::CloseCard, accounts)
+}
+
+/// Create `SetCardCategories` instruction
+pub fn set_card_categories(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    owner: &Pubkey,
+    categories: MerchantCategories,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(find_card_program_address(program_id, owner).0, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::SetCardCategories(SetCardCategoriesArgs { categories }),
+        accounts,
+    )
+}
+
+/// Create `InitMandate` instruction
+pub fn init_mandate(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    payer: &Pubkey,
+    source_token: &Pubkey,
+    args: InitMandateArgs,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(
+            find_mandate_program_address(program_id, owner, &args.merchant).0,
+            false,
+        ),
+        AccountMeta::new_readonly(*source_token, false),
+        AccountMeta::new_readonly(
+            find_merchant_program_address(program_id, &args.merchant).0,
+            false,
+        ),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::InitMandate(args), accounts)
+}
+
+/// Create `ChargeMandate` instruction
+pub fn charge_mandate(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    owner: &Pubkey,
+    merchant: &Pubkey,
+    source_token: &Pubkey,
+    settlement_token: &Pubkey,
+    args: ChargeMandateArgs,
+) -> Instruction {
+    let mandate = find_mandate_program_address(program_id, owner, merchant).0;
+    let accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(mandate, false),
+        AccountMeta::new(*source_token, false),
+        AccountMeta::new(*settlement_token, false),
+        AccountMeta::new_readonly(
+            find_mandate_delegate_program_address(program_id, &mandate).0,
+            false,
+        ),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(find_merchant_program_address(program_id, merchant).0, false),
+        AccountMeta::new(find_card_program_address(program_id, owner).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::ChargeMandate(args), accounts)
+}
+
+/// Create `CancelMandate` instruction
+pub fn cancel_mandate(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    merchant: &Pubkey,
+    receiver: &Pubkey,
+    source_token: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new(
+            find_mandate_program_address(program_id, owner, merchant).0,
+            false,
+        ),
+        AccountMeta::new(*receiver, false),
+        AccountMeta::new(*source_token, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::CancelMandate, accounts)
+}
+
+/// Create `SchedulePayout` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn schedule_payout(
+    program_id: &Pubkey,
+    wallet: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    source_token: &Pubkey,
     vault_token: &Pubkey,
+    beneficiary_token: &Pubkey,
     mint: &Pubkey,
-    vault_owner: &Pubkey,
+    args: SchedulePayoutArgs,
 ) -> Instruction {
     let accounts = vec![
+        AccountMeta::new_readonly(*wallet, true),
         AccountMeta::new_readonly(*authority, true),
-        AccountMeta::new(*escrow, false),
-        AccountMeta::new(*src_token, false),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(find_payout_program_address(program_id, &args.key).0, false),
+        AccountMeta::new(*source_token, false),
         AccountMeta::new(*vault_token, false),
+        AccountMeta::new_readonly(*beneficiary_token, false),
         AccountMeta::new_readonly(*mint, false),
-        AccountMeta::new_readonly(*vault_owner, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(find_mint_config_program_address(program_id, mint).0, false),
+        AccountMeta::new(find_card_program_address(program_id, wallet).0, false),
     ];
 
     Instruction::new_with_borsh(
         *program_id,
-        &CardInstruction::Cancel,
+        &CardInstruction::SchedulePayout(args),
         accounts,
     )
 }
 
-/// Create `CloseEscrow` instruction
-pub fn close_escrow(
+/// Create `ExecutePayout` instruction
+pub fn execute_payout(
+    program_id: &Pubkey,
+    key: &Pubkey,
+    vault_token: &Pubkey,
+    beneficiary_token: &Pubkey,
+    rent_payer: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(find_payout_program_address(program_id, key).0, false),
+        AccountMeta::new(*vault_token, false),
+        AccountMeta::new(*beneficiary_token, false),
+        AccountMeta::new_readonly(find_program_authority(program_id).0, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(*rent_payer, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::ExecutePayout, accounts)
+}
+
+/// Create `CancelPayout` instruction
+pub fn cancel_payout(
     program_id: &Pubkey,
     authority: &Pubkey,
-    escrow: &Pubkey,
-    fee_payer: &Pubkey,
+    key: &Pubkey,
+    vault_token: &Pubkey,
+    source_token: &Pubkey,
+    rent_payer: &Pubkey,
 ) -> Instruction {
     let accounts = vec![
         AccountMeta::new_readonly(*authority, true),
-        AccountMeta::new(*escrow, false),
-        AccountMeta::new(*fee_payer, false),
+        AccountMeta::new(find_payout_program_address(program_id, key).0, false),
+        AccountMeta::new(*vault_token, false),
+        AccountMeta::new(*source_token, false),
+        AccountMeta::new_readonly(find_program_authority(program_id).0, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new(*rent_payer, false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::CancelPayout, accounts)
+}
+
+/// Create `SettleBatch` instruction settling `escrows`, given as escrow, vault
+/// and destination token accounts
+pub fn settle_batch(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    fee_token: &Pubkey,
+    mint: &Pubkey,
+    escrows: &[(Pubkey, Pubkey, Pubkey)],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*fee_token, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(find_program_authority(program_id).0, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+    ];
+    for (escrow, vault_token, dst_token) in escrows {
+        accounts.push(AccountMeta::new(*escrow, false));
+        accounts.push(AccountMeta::new(*vault_token, false));
+        accounts.push(AccountMeta::new(*dst_token, false));
+    }
+
+    Instruction::new_with_borsh(
+        *program_id,
+        &CardInstruction::SettleBatch(SettleBatchArgs {
+            count: escrows.len() as u8,
+        }),
+        accounts,
+    )
+}
+
+/// Create `InitDepositBatch` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_batch(
+    program_id: &Pubkey,
+    user: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    source_token: &Pubkey,
+    collection_token: &Pubkey,
+    collection_fee_token: &Pubkey,
+    mint: &Pubkey,
+    args: DepositBatchArgs,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*user, true),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*source_token, false),
+        AccountMeta::new(*collection_token, false),
+        AccountMeta::new(*collection_fee_token, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
     ];
+    accounts.extend(args.deposits.iter().map(|deposit| {
+        AccountMeta::new(
+            find_deposit_program_address(program_id, &deposit.key).0,
+            false,
+        )
+    }));
+    accounts.extend([
+        AccountMeta::new_readonly(find_config_program_address(program_id).0, false),
+        AccountMeta::new_readonly(find_mint_config_program_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_fee_schedule_program_address(program_id, mint).0, false),
+        AccountMeta::new(find_card_program_address(program_id, user).0, false),
+    ]);
 
     Instruction::new_with_borsh(
         *program_id,
-        &CardInstruction::Close,
+        &CardInstruction::InitDepositBatch(args),
         accounts,
     )
-}
\ No newline at end of file
+}
+
+/// Create `Migrate` instruction
+pub fn migrate(program_id: &Pubkey, payer: &Pubkey, account: &Pubkey) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new(*account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    Instruction::new_with_borsh(*program_id, &CardInstruction::Migrate, accounts)
+}
+
+/// Pass the program authority and the vault of the escrow of `reference` to a
+/// `Migrate` instruction of a native escrow
+pub fn with_native_vault(
+    mut instruction: Instruction,
+    program_id: &Pubkey,
+    reference: &Pubkey,
+) -> Instruction {
+    instruction.accounts.extend([
+        AccountMeta::new(find_program_authority(program_id).0, false),
+        AccountMeta::new(
+            find_escrow_vault_program_address(program_id, reference).0,
+            false,
+        ),
+    ]);
+    instruction
+}
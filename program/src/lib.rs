@@ -1,17 +1,34 @@
+/// `msg!` compiled in only with the `verbose` feature, formatting logs costing compute units
+macro_rules! verbose_msg {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "verbose")]
+        ::solana_program::msg!($($arg)*);
+    };
+}
+
 pub mod collections;
 pub mod error;
+pub mod event;
 pub mod instruction;
 pub mod processor;
 pub mod state;
 pub mod utils;
 
 pub const PREFIX: &str = "card";
+pub const WRAP_PREFIX: &str = "wrap";
 
 #[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
 
-use solana_program::{declare_id, pubkey::Pubkey};
-use state::{deposit::Deposit, withdraw::Withdraw, escrow::Escrow};
+use solana_program::{
+    bpf_loader_upgradeable, declare_id,
+    pubkey::{Pubkey, PubkeyError},
+};
+use state::{
+    card::Card, config::Config, deposit::Deposit, escrow::Escrow, fee_schedule::FeeSchedule,
+    mandate::Mandate, merchant::Merchant, mint_config::MintConfig, multisig::Multisig,
+    payout::ScheduledPayout, withdraw::Withdraw,
+};
 
 declare_id!("cardFRMHxFN4X1urijmqb7gWSMT7bAep4Pd4LuLciG3");
 
@@ -20,6 +37,19 @@ pub fn find_program_authority(program_id: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[PREFIX.as_bytes(), program_id.as_ref()], program_id)
 }
 
+/// Program authority from its canonical `bump`, cheaper than finding it
+pub fn create_program_authority(program_id: &Pubkey, bump: u8) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), &[bump]],
+        program_id,
+    )
+}
+
+/// Generates the address of the program data account the upgradeable loader keeps
+pub fn find_program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
 /// Generates deposit program address
 pub fn find_deposit_program_address(program_id: &Pubkey, reference: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -46,6 +76,19 @@ pub fn find_withdrawal_program_address(program_id: &Pubkey, reference: &Pubkey)
     )
 }
 
+/// Generates scheduled payout program address
+pub fn find_payout_program_address(program_id: &Pubkey, key: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            key.as_ref(),
+            ScheduledPayout::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
 /// Generates escrow program address
 pub fn find_escrow_program_address(program_id: &Pubkey, reference: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -57,4 +100,226 @@ pub fn find_escrow_program_address(program_id: &Pubkey, reference: &Pubkey) -> (
         ],
         program_id,
     )
-}
\ No newline at end of file
+}
+
+/// Generates the system-owned vault holding a native SOL escrow
+pub fn find_escrow_vault_program_address(program_id: &Pubkey, reference: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            reference.as_ref(),
+            Escrow::VAULT_PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Generates the temporary wrapped SOL account address
+pub fn find_wrap_program_address(program_id: &Pubkey, reference: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            reference.as_ref(),
+            WRAP_PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Generates the multisig approval authority address
+pub fn find_multisig_program_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            Multisig::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Generates the config address
+pub fn find_config_program_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            Config::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Config address from its canonical `bump`, cheaper than finding it
+pub fn create_config_program_address(program_id: &Pubkey, bump: u8) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            Config::PREFIX.as_bytes(),
+            &[bump],
+        ],
+        program_id,
+    )
+}
+
+/// Generates mint config program address
+pub fn find_mint_config_program_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mint.as_ref(),
+            MintConfig::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Mint config address from its canonical `bump`, cheaper than finding it
+pub fn create_mint_config_program_address(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mint.as_ref(),
+            MintConfig::PREFIX.as_bytes(),
+            &[bump],
+        ],
+        program_id,
+    )
+}
+
+/// Generates fee schedule program address
+pub fn find_fee_schedule_program_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mint.as_ref(),
+            FeeSchedule::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Fee schedule address from its canonical `bump`, cheaper than finding it
+pub fn create_fee_schedule_program_address(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mint.as_ref(),
+            FeeSchedule::PREFIX.as_bytes(),
+            &[bump],
+        ],
+        program_id,
+    )
+}
+
+/// Generates card program address
+pub fn find_card_program_address(program_id: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            owner.as_ref(),
+            Card::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Card address from its canonical `bump`, cheaper than finding it
+pub fn create_card_program_address(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            owner.as_ref(),
+            Card::PREFIX.as_bytes(),
+            &[bump],
+        ],
+        program_id,
+    )
+}
+
+/// Generates mandate program address
+pub fn find_mandate_program_address(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    merchant: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            owner.as_ref(),
+            merchant.as_ref(),
+            Mandate::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Generates the address a mandate charges its source token account as, the
+/// delegate its owner approves
+pub fn find_mandate_delegate_program_address(
+    program_id: &Pubkey,
+    mandate: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mandate.as_ref(),
+            Mandate::DELEGATE_PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Generates merchant program address
+pub fn find_merchant_program_address(program_id: &Pubkey, id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            id.as_ref(),
+            Merchant::PREFIX.as_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Merchant address from its canonical `bump`, cheaper than finding it
+pub fn create_merchant_program_address(
+    program_id: &Pubkey,
+    id: &Pubkey,
+    bump: u8,
+) -> Result<Pubkey, PubkeyError> {
+    Pubkey::create_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            id.as_ref(),
+            Merchant::PREFIX.as_bytes(),
+            &[bump],
+        ],
+        program_id,
+    )
+}
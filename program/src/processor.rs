@@ -1,12 +1,25 @@
-use borsh::BorshDeserialize;
-use crate::instruction::CardInstruction;
-
+use crate::{
+    instruction::CardInstruction,
+    state::config::{
+        PAUSE_CHARGE_MANDATE, PAUSE_CLOSE, PAUSE_INIT_DEPOSIT, PAUSE_INIT_ESCROW,
+        PAUSE_INIT_WITHDRAWAL, PAUSE_SETTLE,
+    },
+    utils::assert_not_paused,
+};
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey};
 
+pub mod card;
+pub mod config;
 pub mod deposit;
-pub mod withdraw;
 pub mod escrow;
-
+pub mod fee_schedule;
+pub mod mandate;
+pub mod merchant;
+pub mod migrate;
+pub mod mint_config;
+pub mod multisig;
+pub mod payout;
+pub mod withdraw;
 
 pub struct Processor;
 impl Processor {
@@ -15,15 +28,22 @@ impl Processor {
         accounts: &[AccountInfo],
         instruction_data: &[u8],
     ) -> ProgramResult {
-        msg!("Start deserialize card instruction");
-        let instruction = CardInstruction::try_from_slice(instruction_data)?;
-        msg!("Successfully deserialized card instruction");
+        verbose_msg!("Start deserialize card instruction");
+        let instruction = CardInstruction::unpack(instruction_data)?;
+        verbose_msg!("Successfully deserialized card instruction");
+        if let Some(flag) = pause_flag(&instruction) {
+            assert_not_paused(program_id, accounts, flag)?;
+        }
 
         match instruction {
             CardInstruction::InitDeposit(args) => {
                 msg!("Instruction: Init deposit");
                 deposit::init(program_id, accounts, args)
             }
+            CardInstruction::InitDepositBatch(args) => {
+                msg!("Instruction: Init deposit batch");
+                deposit::init_batch(program_id, accounts, args)
+            }
             CardInstruction::InitWithdrawal(args) => {
                 msg!("Instruction: Init withdraw");
                 withdraw::init(program_id, accounts, args)
@@ -36,6 +56,10 @@ impl Processor {
                 msg!("Instruction: Settle Escrow");
                 escrow::process_settlement(accounts, program_id)
             }
+            CardInstruction::SettleBatch(args) => {
+                msg!("Instruction: Settle batch");
+                escrow::process_settle_batch(accounts, args, program_id)
+            }
             CardInstruction::Cancel => {
                 msg!("Instruction: Cancel Escrow");
                 escrow::process_cancel(accounts, program_id)
@@ -44,6 +68,162 @@ impl Processor {
                 msg!("Instruction: Close");
                 escrow::process_close(accounts, program_id)
             }
+            CardInstruction::InitMultisig(args) => {
+                msg!("Instruction: Init multisig");
+                multisig::init(program_id, accounts, args)
+            }
+            CardInstruction::SetMultisig(args) => {
+                msg!("Instruction: Set multisig");
+                multisig::set(program_id, accounts, args)
+            }
+            CardInstruction::InitConfig(args) => {
+                msg!("Instruction: Init config");
+                config::init(program_id, accounts, args)
+            }
+            CardInstruction::SetConfig(args) => {
+                msg!("Instruction: Set config");
+                config::set(program_id, accounts, args)
+            }
+            CardInstruction::SetPause(args) => {
+                msg!("Instruction: Set pause");
+                config::set_pause(program_id, accounts, args)
+            }
+            CardInstruction::InitMintConfig(args) => {
+                msg!("Instruction: Init mint config");
+                mint_config::init(program_id, accounts, args)
+            }
+            CardInstruction::SetMintConfig(args) => {
+                msg!("Instruction: Set mint config");
+                mint_config::set(program_id, accounts, args)
+            }
+            CardInstruction::InitFeeSchedule(args) => {
+                msg!("Instruction: Init fee schedule");
+                fee_schedule::init(program_id, accounts, args)
+            }
+            CardInstruction::SetFeeSchedule(args) => {
+                msg!("Instruction: Set fee schedule");
+                fee_schedule::set(program_id, accounts, args)
+            }
+            CardInstruction::OnboardMerchant(args) => {
+                msg!("Instruction: Onboard merchant");
+                merchant::onboard(program_id, accounts, args)
+            }
+            CardInstruction::UpdateMerchant(args) => {
+                msg!("Instruction: Update merchant");
+                merchant::update(program_id, accounts, args)
+            }
+            CardInstruction::SetMerchantStatus(args) => {
+                msg!("Instruction: Set merchant status");
+                merchant::set_status(program_id, accounts, args)
+            }
+            CardInstruction::Refund(args) => {
+                msg!("Instruction: Refund");
+                escrow::process_refund(accounts, args, program_id)
+            }
+            CardInstruction::Dispute(args) => {
+                msg!("Instruction: Dispute");
+                escrow::process_dispute(accounts, args, program_id)
+            }
+            CardInstruction::ResolveDispute(args) => {
+                msg!("Instruction: Resolve dispute");
+                escrow::process_resolve_dispute(accounts, args, program_id)
+            }
+            CardInstruction::InitCard(args) => {
+                msg!("Instruction: Init card");
+                card::init(program_id, accounts, args)
+            }
+            CardInstruction::UpdateCard(args) => {
+                msg!("Instruction: Update card");
+                card::update(program_id, accounts, args)
+            }
+            CardInstruction::FreezeCard => {
+                msg!("Instruction: Freeze card");
+                card::freeze(program_id, accounts)
+            }
+            CardInstruction::UnfreezeCard => {
+                msg!("Instruction: Unfreeze card");
+                card::unfreeze(program_id, accounts)
+            }
+            CardInstruction::SetCardCategories(args) => {
+                msg!("Instruction: Set card categories");
+                card::set_categories(program_id, accounts, args)
+            }
+            CardInstruction::InitMandate(args) => {
+                msg!("Instruction: Init mandate");
+                mandate::init(program_id, accounts, args)
+            }
+            CardInstruction::ChargeMandate(args) => {
+                msg!("Instruction: Charge mandate");
+                mandate::charge(program_id, accounts, args)
+            }
+            CardInstruction::CancelMandate => {
+                msg!("Instruction: Cancel mandate");
+                mandate::cancel(program_id, accounts)
+            }
+            CardInstruction::SchedulePayout(args) => {
+                msg!("Instruction: Schedule payout");
+                payout::schedule(program_id, accounts, args)
+            }
+            CardInstruction::ExecutePayout => {
+                msg!("Instruction: Execute payout");
+                payout::execute(program_id, accounts)
+            }
+            CardInstruction::CancelPayout => {
+                msg!("Instruction: Cancel payout");
+                payout::cancel(program_id, accounts)
+            }
+            CardInstruction::Migrate => {
+                msg!("Instruction: Migrate");
+                migrate::process(program_id, accounts)
+            }
+            CardInstruction::CloseCard => {
+                msg!("Instruction: Close card");
+                card::close(program_id, accounts)
+            }
+        }
+    }
+}
+
+/// The pause bit of the instruction. Canceling, refunding, disputes, card and
+/// mandate management, canceling payouts, migrations and the admin instructions
+/// can't be paused
+fn pause_flag(instruction: &CardInstruction) -> Option<u64> {
+    match instruction {
+        CardInstruction::InitDeposit(_) | CardInstruction::InitDepositBatch(_) => {
+            Some(PAUSE_INIT_DEPOSIT)
         }
+        CardInstruction::InitWithdrawal(_)
+        | CardInstruction::SchedulePayout(_)
+        | CardInstruction::ExecutePayout => Some(PAUSE_INIT_WITHDRAWAL),
+        CardInstruction::InitEscrow(_) => Some(PAUSE_INIT_ESCROW),
+        CardInstruction::Settle | CardInstruction::SettleBatch(_) => Some(PAUSE_SETTLE),
+        CardInstruction::Close => Some(PAUSE_CLOSE),
+        CardInstruction::ChargeMandate(_) => Some(PAUSE_CHARGE_MANDATE),
+        CardInstruction::Cancel
+        | CardInstruction::Refund(_)
+        | CardInstruction::Dispute(_)
+        | CardInstruction::ResolveDispute(_)
+        | CardInstruction::InitMultisig(_)
+        | CardInstruction::SetMultisig(_)
+        | CardInstruction::InitConfig(_)
+        | CardInstruction::SetConfig(_)
+        | CardInstruction::SetPause(_)
+        | CardInstruction::InitMintConfig(_)
+        | CardInstruction::SetMintConfig(_)
+        | CardInstruction::InitFeeSchedule(_)
+        | CardInstruction::SetFeeSchedule(_)
+        | CardInstruction::OnboardMerchant(_)
+        | CardInstruction::UpdateMerchant(_)
+        | CardInstruction::SetMerchantStatus(_)
+        | CardInstruction::InitCard(_)
+        | CardInstruction::UpdateCard(_)
+        | CardInstruction::FreezeCard
+        | CardInstruction::UnfreezeCard
+        | CardInstruction::SetCardCategories(_)
+        | CardInstruction::InitMandate(_)
+        | CardInstruction::CancelMandate
+        | CardInstruction::CancelPayout
+        | CardInstruction::Migrate
+        | CardInstruction::CloseCard => None,
     }
 }
@@ -0,0 +1,182 @@
+//! Card instruction processing
+
+use crate::{
+    error::CardError,
+    find_card_program_address,
+    instruction::{InitCardArgs, SetCardCategoriesArgs, UpdateCardArgs},
+    state::{
+        card::{Card, CardAuthority, CardStatus},
+        config::Role,
+    },
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+
+/// Process InitCard instruction
+pub fn init(program_id: &Pubkey, accounts: &[AccountInfo], args: InitCardArgs) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let card_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    if card_info.lamports() > 0 && !card_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    // the bump is found once here, later instructions recreating the address from it
+    if args.bump != find_card_program_address(program_id, &args.owner).1 {
+        return Err(CardError::InvalidCard.into());
+    }
+
+    create_new_account_raw(
+        program_id,
+        card_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        Card::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.owner.as_ref(),
+            Card::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut card = Card::unpack_unchecked(&card_info.data.borrow())?;
+    if card.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    card.is_initialized = true;
+    card.owner = args.owner;
+    card.status = CardStatus::Active;
+    card.params = args.params;
+    card.bump = args.bump;
+    Card::pack(card, &mut card_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process UpdateCard instruction
+pub fn update(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateCardArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let card_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(card_info, program_id)?;
+    let mut card: Card = assert_initialized(card_info)?;
+    card.params = args.params;
+    Card::pack(card, &mut card_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process FreezeCard instruction
+pub fn freeze(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let card_info = next_account_info(account_info_iter)?;
+
+    assert_owned_by(card_info, program_id)?;
+    let mut card: Card = assert_initialized(card_info)?;
+    let authority = assert_card_authority(program_id, authority_info, accounts, &card)?;
+    card.status = CardStatus::Frozen;
+    // the issuer takes over a freeze of the holder, never the other way around
+    if authority > card.frozen_by {
+        card.frozen_by = authority;
+    }
+    Card::pack(card, &mut card_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process UnfreezeCard instruction
+pub fn unfreeze(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let card_info = next_account_info(account_info_iter)?;
+
+    assert_owned_by(card_info, program_id)?;
+    let mut card: Card = assert_initialized(card_info)?;
+    let authority = assert_card_authority(program_id, authority_info, accounts, &card)?;
+    if card.status != CardStatus::Frozen {
+        return Err(CardError::CardNotFrozen.into());
+    }
+    if authority < card.frozen_by {
+        return Err(CardError::CardFrozenByIssuer.into());
+    }
+    card.status = CardStatus::Active;
+    card.frozen_by = CardAuthority::None;
+    Card::pack(card, &mut card_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process CloseCard instruction, the wallet spending without a card afterwards
+pub fn close(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let card_info = next_account_info(account_info_iter)?;
+    let receiver_info = next_account_info(account_info_iter)?;
+
+    assert_owned_by(card_info, program_id)?;
+    let card: Card = assert_initialized(card_info)?;
+    let authority = assert_card_authority(program_id, authority_info, accounts, &card)?;
+    // the holder can't shake off a freeze of the issuer by closing the card
+    if card.status == CardStatus::Frozen && authority < card.frozen_by {
+        return Err(CardError::CardFrozenByIssuer.into());
+    }
+    card_info.data.borrow_mut().fill(0);
+    empty_account_balance(card_info, receiver_info)
+}
+
+/// Process SetCardCategories instruction
+pub fn set_categories(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetCardCategoriesArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let card_info = next_account_info(account_info_iter)?;
+
+    assert_owned_by(card_info, program_id)?;
+    let mut card: Card = assert_initialized(card_info)?;
+    let authority = assert_card_authority(program_id, authority_info, accounts, &card)?;
+    if authority < card.categories_set_by {
+        return Err(CardError::CategoriesSetByIssuer.into());
+    }
+    if !args.categories.is_valid() {
+        return Err(CardError::InvalidMerchantCategories.into());
+    }
+    card.categories = args.categories;
+    card.categories_set_by = authority;
+    Card::pack(card, &mut card_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Assert `authority_info` is the cardholder or the config admin and tell which
+fn assert_card_authority(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo,
+    accounts: &[AccountInfo],
+    card: &Card,
+) -> Result<CardAuthority, ProgramError> {
+    if cmp_pubkeys(authority_info.key, &card.owner) {
+        assert_signer(authority_info)?;
+        return Ok(CardAuthority::Holder);
+    }
+    assert_role(program_id, authority_info, accounts, Role::ConfigAdmin)?;
+    Ok(CardAuthority::Issuer)
+}
@@ -0,0 +1,106 @@
+//! Config instruction processing
+
+use crate::{
+    create_config_program_address,
+    error::CardError,
+    find_config_program_address, find_program_authority,
+    instruction::{InitConfigArgs, SetConfigArgs, SetPauseArgs},
+    state::config::{Config, Role, PAUSE_ALL},
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+
+/// Process InitConfig instruction, the upgrade authority hands the roles out once
+pub fn init(program_id: &Pubkey, accounts: &[AccountInfo], args: InitConfigArgs) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+    let program_data_info = next_account_info(account_info_iter)?;
+
+    assert_signer(authority_info)?;
+    assert_upgrade_authority(program_id, program_data_info, authority_info)?;
+    if config_info.lamports() > 0 && !config_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    // the bumps are found once here, later instructions recreating the addresses from them
+    let (config_key, bump) = find_config_program_address(program_id);
+    if args.bump != bump {
+        return Err(CardError::InvalidConfig.into());
+    }
+    assert_account_key(config_info, &config_key, Some(CardError::InvalidConfig))?;
+
+    create_new_account_raw(
+        program_id,
+        config_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        Config::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            Config::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut config = Config::unpack_unchecked(&config_info.data.borrow())?;
+    if config.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    config.is_initialized = true;
+    config.roles = args.roles;
+    config.paused_flags = 0;
+    config.bump = bump;
+    config.authority_bump = find_program_authority(program_id).1;
+    Config::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process SetConfig instruction
+pub fn set(program_id: &Pubkey, accounts: &[AccountInfo], args: SetConfigArgs) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(config_info, program_id)?;
+    let mut config = Config::unpack(&config_info.data.borrow())?;
+    let config_key = create_config_program_address(program_id, config.bump)
+        .map_err(|_| CardError::InvalidConfig)?;
+    assert_account_key(config_info, &config_key, Some(CardError::InvalidConfig))?;
+    config.roles = args.roles;
+    Config::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process SetPause instruction
+pub fn set_pause(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetPauseArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let config_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(config_info, program_id)?;
+    let mut config = Config::unpack(&config_info.data.borrow())?;
+    let config_key = create_config_program_address(program_id, config.bump)
+        .map_err(|_| CardError::InvalidConfig)?;
+    assert_account_key(config_info, &config_key, Some(CardError::InvalidConfig))?;
+    config.paused_flags = args.paused_flags & PAUSE_ALL;
+    Config::pack(config, &mut config_info.data.borrow_mut())?;
+    Ok(())
+}
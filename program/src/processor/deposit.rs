@@ -1,11 +1,19 @@
 //! Init pass instruction processing
 
 use crate::{
-    collections::{authority, deposit, fee},
+    collections::{deposit, fee},
     error::CardError,
-    instruction::DepositArgs,
+    find_wrap_program_address,
+    instruction::{DepositArgs, DepositBatchArgs},
+    state::{
+        config::Role,
+        deposit::{Deposit, MAX_BATCH_DEPOSITS},
+        fee_schedule::FeeOperation,
+        mint_config::MintConfig,
+        FLAG_ACCOUNT_SIZE,
+    },
     utils::*,
-    PREFIX, state::{FLAG_ACCOUNT_SIZE, deposit::Deposit},
+    PREFIX, WRAP_PREFIX,
 };
 
 use solana_program::{
@@ -14,7 +22,6 @@ use solana_program::{
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
-    msg
 };
 
 use spl_token::state::Account;
@@ -34,45 +41,306 @@ pub fn init(program_id: &Pubkey, accounts: &[AccountInfo], args: DepositArgs) ->
     let system_account_info = next_account_info(account_info_iter)?;
 
     assert_signer(user_info)?;
-    assert_signer(authority_info)?;
-
-    assert_account_key(
-        authority_info,
-        &authority::id(),
-        Some(CardError::InvalidAuthorityId),
+    assert_role(program_id, authority_info, accounts, Role::DepositApprover)?;
+    let mint_config = assert_mint_supported(program_id, accounts, mint_info.key, args.amount)?;
+    charge_card(
+        program_id,
+        accounts,
+        user_info.key,
+        source_token_info.key,
+        args.amount,
+        Spend::Transfer,
+    )?;
+    assert_collection_accounts(
+        &mint_config,
+        mint_info,
+        collection_token_info,
+        collection_fee_token_info,
     )?;
     if deposit_info.lamports() > 0 && !deposit_info.data_is_empty() {
         return Err(ProgramError::AccountAlreadyInitialized);
     }
+    // native SOL can be deposited straight from the user's wallet
+    let wrap_source = cmp_pubkeys(mint_info.key, &spl_token::native_mint::id())
+        && cmp_pubkeys(source_token_info.key, user_info.key);
+    if !wrap_source {
+        assert_source_token(source_token_info, user_info, mint_info)?;
+    }
+    let fee = scheduled_fee(
+        load_fee_schedule(program_id, accounts, &mint_config)?.as_ref(),
+        &mint_config,
+        None,
+        FeeOperation::Deposit,
+        args.amount,
+        calculate_fee(args.amount, args.fee_bps as u64)?,
+    )?;
+
+    if wrap_source {
+        let token_program_info = next_account_info(account_info_iter)?;
+        let program_authority_info = next_account_info(account_info_iter)?;
+        let wrap_info = next_account_info(account_info_iter)?;
+
+        assert_account_key(token_program_info, &spl_token::id(), None)?;
+
+        let authority_bump = load_config(program_id, accounts)?.authority_bump;
+        assert_program_authority(
+            program_id,
+            program_authority_info,
+            authority_bump,
+            CardError::InvalidVaultOwner,
+        )?;
+        let (wrap_key, wrap_bump) = find_wrap_program_address(program_id, &args.key);
+        assert_account_key(wrap_info, &wrap_key, Some(CardError::InvalidWrapKey))?;
+        let authority_signer_seeds = [PREFIX.as_bytes(), program_id.as_ref(), &[authority_bump]];
+
+        create_wrapped_native_account(
+            wrap_info,
+            mint_info,
+            program_authority_info,
+            rent_info,
+            payer_info,
+            system_account_info,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                args.key.as_ref(),
+                WRAP_PREFIX.as_bytes(),
+                &[wrap_bump],
+            ],
+        )?;
+        let wrapped = args
+            .amount
+            .checked_add(fee)
+            .ok_or::<ProgramError>(CardError::AmountOverflow.into())?;
+        wrap_native(user_info, wrap_info, wrapped)?;
+        transfer(
+            false,
+            wrap_info,
+            collection_token_info,
+            program_authority_info,
+            args.amount,
+            &[&authority_signer_seeds],
+        )?;
+        transfer(
+            false,
+            wrap_info,
+            collection_fee_token_info,
+            program_authority_info,
+            fee,
+            &[&authority_signer_seeds],
+        )?;
+        // the emptied wrapped account hands its rent back to the payer
+        close_token_account(
+            wrap_info,
+            payer_info,
+            program_authority_info,
+            &[&authority_signer_seeds],
+        )?;
+    } else {
+        transfer(
+            false,
+            source_token_info,
+            collection_token_info,
+            user_info,
+            args.amount,
+            &[],
+        )?;
+        transfer(
+            false,
+            source_token_info,
+            collection_fee_token_info,
+            user_info,
+            fee,
+            &[],
+        )?;
+    }
+
+    create_deposit(
+        program_id,
+        deposit_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        &args.key,
+        args.bump,
+    )
+}
+
+/// Process InitDepositBatch instruction
+pub fn init_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: DepositBatchArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let user_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let source_token_info = next_account_info(account_info_iter)?;
+    let collection_token_info = next_account_info(account_info_iter)?;
+    let collection_fee_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(user_info)?;
+    assert_role(program_id, authority_info, accounts, Role::DepositApprover)?;
+    assert_account_key(token_program_info, &spl_token::id(), None)?;
+    if args.deposits.is_empty() || args.deposits.len() > MAX_BATCH_DEPOSITS {
+        return Err(CardError::InvalidInstruction.into());
+    }
+    let mint_config =
+        assert_mint_supported(program_id, accounts, mint_info.key, args.deposits[0].amount)?;
+    let fee_schedule = load_fee_schedule(program_id, accounts, &mint_config)?;
+    // the fee of each deposit is scheduled on its own amount
+    let mut amount: u64 = 0;
+    let mut fee: u64 = 0;
+    for deposit in &args.deposits {
+        if deposit.amount < mint_config.params.min_amount
+            || deposit.amount > mint_config.params.max_amount
+        {
+            return Err(CardError::AmountOutOfRange.into());
+        }
+        let deposit_fee = scheduled_fee(
+            fee_schedule.as_ref(),
+            &mint_config,
+            None,
+            FeeOperation::Deposit,
+            deposit.amount,
+            calculate_fee(deposit.amount, args.fee_bps as u64)?,
+        )?;
+        amount = amount
+            .checked_add(deposit.amount)
+            .ok_or::<ProgramError>(CardError::AmountOverflow.into())?;
+        fee = fee
+            .checked_add(deposit_fee)
+            .ok_or::<ProgramError>(CardError::AmountOverflow.into())?;
+    }
+    // the batch leaves the source in one transfer, so the card counts it as one transaction
+    charge_card(
+        program_id,
+        accounts,
+        user_info.key,
+        source_token_info.key,
+        amount,
+        Spend::Transfer,
+    )?;
+    assert_collection_accounts(
+        &mint_config,
+        mint_info,
+        collection_token_info,
+        collection_fee_token_info,
+    )?;
+    assert_source_token(source_token_info, user_info, mint_info)?;
+
+    transfer(
+        false,
+        source_token_info,
+        collection_token_info,
+        user_info,
+        amount,
+        &[],
+    )?;
+    transfer(
+        false,
+        source_token_info,
+        collection_fee_token_info,
+        user_info,
+        fee,
+        &[],
+    )?;
+
+    for deposit in &args.deposits {
+        let deposit_info = next_account_info(account_info_iter)?;
+        if deposit_info.lamports() > 0 && !deposit_info.data_is_empty() {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        create_deposit(
+            program_id,
+            deposit_info,
+            rent_info,
+            payer_info,
+            system_account_info,
+            &deposit.key,
+            deposit.bump,
+        )?;
+    }
+    Ok(())
+}
+
+fn assert_source_token(
+    source_token_info: &AccountInfo,
+    user_info: &AccountInfo,
+    mint_info: &AccountInfo,
+) -> ProgramResult {
     assert_owned_by(source_token_info, &spl_token::id())?;
     let source_token: Account = assert_initialized(source_token_info)?;
-    msg!("Assert source token {} is owned by user {}", source_token_info.key, user_info.key);
+    verbose_msg!(
+        "Assert source token {} is owned by user {}",
+        source_token_info.key,
+        user_info.key
+    );
     assert_token_owned_by(&source_token, user_info.key)?;
     if source_token.mint != *mint_info.key {
         return Err(CardError::InvalidMint.into());
     }
-    msg!("Assertion ok {}", source_token_info.key);
+    verbose_msg!("Assertion ok {}", source_token_info.key);
+    Ok(())
+}
+
+fn assert_collection_accounts(
+    mint_config: &MintConfig,
+    mint_info: &AccountInfo,
+    collection_token_info: &AccountInfo,
+    collection_fee_token_info: &AccountInfo,
+) -> ProgramResult {
+    assert_account_key(
+        collection_token_info,
+        &mint_config.params.deposit_collection,
+        Some(CardError::InvalidCollectionAccount),
+    )?;
+    assert_account_key(
+        collection_fee_token_info,
+        &mint_config.params.fee_collection,
+        Some(CardError::InvalidCollectionAccount),
+    )?;
     assert_owned_by(collection_token_info, &spl_token::id())?;
     let collection_token: Account = assert_initialized(collection_token_info)?;
-    msg!("Assert collection token {} is owned by deposit {}", collection_token_info.key, deposit::id());
+    verbose_msg!(
+        "Assert collection token {} is owned by deposit {}",
+        collection_token_info.key,
+        deposit::id()
+    );
     assert_token_owned_by(&collection_token, &deposit::id())?;
     if collection_token.mint != *mint_info.key {
         return Err(CardError::InvalidMint.into());
     }
-    msg!("Assertion ok {}", collection_token_info.key);
+    verbose_msg!("Assertion ok {}", collection_token_info.key);
     assert_owned_by(collection_fee_token_info, &spl_token::id())?;
     let collection_fee_token: Account = assert_initialized(collection_fee_token_info)?;
-    msg!("Assert collection fee token {} is owned by fee {}", collection_fee_token_info.key, fee::id());
+    verbose_msg!(
+        "Assert collection fee token {} is owned by fee {}",
+        collection_fee_token_info.key,
+        fee::id()
+    );
     assert_token_owned_by(&collection_fee_token, &fee::id())?;
     if collection_fee_token.mint != *mint_info.key {
         return Err(CardError::InvalidMint.into());
     }
-    msg!("Assertion ok {}", collection_fee_token_info.key);
-    let fee = calculate_fee(args.amount, args.fee_bps as u64)?;
-
-    transfer(false, source_token_info, collection_token_info, user_info, args.amount, &[])?;
-    transfer(false, source_token_info, collection_fee_token_info, user_info, fee, &[])?;
+    verbose_msg!("Assertion ok {}", collection_fee_token_info.key);
+    Ok(())
+}
 
+fn create_deposit<'a>(
+    program_id: &Pubkey,
+    deposit_info: &AccountInfo<'a>,
+    rent_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_account_info: &AccountInfo<'a>,
+    key: &Pubkey,
+    bump: u8,
+) -> ProgramResult {
     create_new_account_raw(
         program_id,
         deposit_info,
@@ -83,9 +351,9 @@ pub fn init(program_id: &Pubkey, accounts: &[AccountInfo], args: DepositArgs) ->
         &[
             PREFIX.as_bytes(),
             program_id.as_ref(),
-            args.key.as_ref(),
+            key.as_ref(),
             Deposit::PREFIX.as_bytes(),
-            &[args.bump],
+            &[bump],
         ],
     )?;
     let mut funding = Deposit::unpack_unchecked(&deposit_info.data.borrow())?;
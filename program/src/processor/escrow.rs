@@ -1,26 +1,43 @@
 use crate::{
-    collections::{authority, deposit, fee},
+    collections::{deposit, fee},
     error::CardError::{
         self, AccountAlreadyCanceled, AccountAlreadySettled, AccountNotSettledOrCanceled,
     },
-    find_program_authority,
-    instruction::InitEscrowArgs,
-    state::escrow::Escrow,
+    event::{emit, CardEvent},
+    find_escrow_vault_program_address,
+    instruction::{
+        Authorization, AuthorizationAction, DisputeArgs, DisputeOutcome, InitEscrowArgs,
+        RefundArgs, ResolveDisputeArgs, SettleBatchArgs,
+    },
+    state::{
+        config::{Role, PAUSE_SETTLE},
+        escrow::{DisputeStatus, Escrow, PodEscrow, PodSplit, DISPUTE_WINDOW},
+        fee_schedule::FeeOperation,
+    },
     utils::{
-        assert_account_key, assert_initialized, assert_owned_by, assert_signer,
-        assert_token_owned_by, calculate_fee, cmp_pubkeys, create_new_account_raw,
-        empty_account_balance, transfer,
+        assert_account_key, assert_authorized, assert_initialized, assert_mint_supported,
+        assert_not_paused, assert_owned_by, assert_program_authority, assert_role, assert_signer,
+        assert_token_owned_by, calculate_fee, charge_card, cmp_pubkeys,
+        create_associated_token_account, create_new_account_raw, empty_account_balance,
+        load_config, load_fee_schedule, load_merchant, native_transfer,
+        next_associated_token_program, scheduled_fee, spl_token_approve, spl_token_revoke,
+        transfer, uncharge_card, Spend,
     },
     PREFIX,
 };
 
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::{Clock, UnixTimestamp},
     entrypoint::ProgramResult,
-    msg,
+    hash::Hash,
     program_error::ProgramError,
+    program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::Sysvar,
 };
 use spl_token::state::Account as TokenAccount;
 
@@ -36,24 +53,18 @@ pub fn process_init_escrow(
     assert_signer(wallet_info)?;
 
     let authority_info = next_account_info(account_info_iter)?;
-    assert_signer(authority_info)?;
-
-    assert_account_key(
-        authority_info,
-        &authority::id(),
-        Some(CardError::InvalidAuthorityId),
-    )?;
-
     let fee_payer_info = next_account_info(account_info_iter)?;
     let escrow_info = next_account_info(account_info_iter)?;
     let vault_owner_info = next_account_info(account_info_iter)?;
 
-    let (vault_owner_key, _) = find_program_authority(program_id);
-    assert_account_key(
+    let authority_bump = load_config(program_id, accounts)?.authority_bump;
+    assert_program_authority(
+        program_id,
         vault_owner_info,
-        &vault_owner_key,
-        Some(CardError::InvalidVaultOwner),
+        authority_bump,
+        CardError::InvalidVaultOwner,
     )?;
+    let vault_owner_key = *vault_owner_info.key;
 
     let vault_token_info = next_account_info(account_info_iter)?;
     let src_token_info = next_account_info(account_info_iter)?;
@@ -61,26 +72,122 @@ pub fn process_init_escrow(
     let fee_token_info = next_account_info(account_info_iter)?;
     let mint_info = next_account_info(account_info_iter)?;
     let reference_info = next_account_info(account_info_iter)?;
+    assert_authorized(
+        program_id,
+        authority_info,
+        accounts,
+        Role::Settler,
+        reference_info.key,
+        args.amount,
+        AuthorizationAction::InitEscrow,
+        Authorization::hash_args(&args)?,
+    )?;
     let rent_info = next_account_info(account_info_iter)?;
     let system_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter);
     let is_native = cmp_pubkeys(mint_info.key, &spl_token::native_mint::id());
+    if args.hold && is_native {
+        return Err(CardError::HoldNotSupported.into());
+    }
+    let mint_config = assert_mint_supported(program_id, accounts, mint_info.key, args.amount)?;
+    let card = charge_card(
+        program_id,
+        accounts,
+        wallet_info.key,
+        src_token_info.key,
+        args.amount,
+        Spend::Purchase(args.merchant.map(|_| args.mcc)),
+    )?;
+    let merchant = args
+        .merchant
+        .map(|id| load_merchant(program_id, accounts, &id))
+        .transpose()?;
+    let settlement = match &merchant {
+        Some(merchant) => *merchant
+            .settlement(mint_info.key)
+            .ok_or::<ProgramError>(CardError::InvalidMerchant.into())?,
+        None => mint_config.params.deposit_collection,
+    };
+    assert_account_key(
+        dst_token_info,
+        &settlement,
+        Some(CardError::InvalidCollectionAccount),
+    )?;
+    if let Some(split) = &args.split {
+        // a merchant is paid its settlement account in full
+        if merchant.is_some() || !split.is_valid(args.amount) {
+            return Err(CardError::InvalidPayees.into());
+        }
+    }
+    assert_account_key(
+        fee_token_info,
+        &mint_config.params.fee_collection,
+        Some(CardError::InvalidCollectionAccount),
+    )?;
+
+    if let Some(associated_token_program_info) = next_associated_token_program(account_info_iter) {
+        let token_program_info = token_program_info?;
+        let deposit_collection_info = next_account_info(account_info_iter)?;
+        let fee_collection_info = next_account_info(account_info_iter)?;
+        if merchant.is_none() {
+            assert_account_key(
+                deposit_collection_info,
+                &deposit::id(),
+                Some(CardError::InvalidDepositTokenOwner),
+            )?;
+        }
+        assert_account_key(
+            fee_collection_info,
+            &fee::id(),
+            Some(CardError::InvalidFeeTokenOwner),
+        )?;
+        if !is_native {
+            // merchants register their settlement accounts up front
+            if merchant.is_none() {
+                create_associated_token_account(
+                    fee_payer_info,
+                    dst_token_info,
+                    deposit_collection_info,
+                    mint_info,
+                    system_account_info,
+                    token_program_info,
+                    associated_token_program_info,
+                )?;
+            }
+            create_associated_token_account(
+                fee_payer_info,
+                fee_token_info,
+                fee_collection_info,
+                mint_info,
+                system_account_info,
+                token_program_info,
+                associated_token_program_info,
+            )?;
+        }
+    }
 
+    let mut vault_bump = 0;
     if is_native {
+        let (vault_key, bump) = find_escrow_vault_program_address(program_id, reference_info.key);
+        vault_bump = bump;
         assert_account_key(
             vault_token_info,
-            vault_owner_info.key,
-            Some(CardError::InvalidVaultOwner),
+            &vault_key,
+            Some(CardError::InvalidVaultKey),
         )?;
+        assert_owned_by(vault_token_info, &system_program::id())?;
         assert_account_key(
             src_token_info,
             wallet_info.key,
             Some(CardError::InvalidSrcTokenOwner),
         )?;
-        assert_account_key(
-            dst_token_info,
-            &deposit::id(),
-            Some(CardError::InvalidDepositTokenOwner),
-        )?;
+        if merchant.is_none() {
+            assert_account_key(
+                dst_token_info,
+                &deposit::id(),
+                Some(CardError::InvalidDepositTokenOwner),
+            )?;
+        }
         assert_account_key(
             fee_token_info,
             &fee::id(),
@@ -95,29 +202,78 @@ pub fn process_init_escrow(
         let src_token: TokenAccount = assert_initialized(src_token_info)?;
         let dst_token: TokenAccount = assert_initialized(dst_token_info)?;
         let fee_token: TokenAccount = assert_initialized(fee_token_info)?;
-        assert_token_owned_by(&vault_token, &vault_owner_key)?;
+        if args.hold {
+            assert_account_key(
+                vault_token_info,
+                src_token_info.key,
+                Some(CardError::InvalidVaultKey),
+            )?;
+        } else {
+            assert_token_owned_by(&vault_token, &vault_owner_key)?;
+        }
         assert_token_owned_by(&src_token, wallet_info.key)?;
-        assert_token_owned_by(&dst_token, &deposit::id())?;
+        if merchant.is_none() {
+            assert_token_owned_by(&dst_token, &deposit::id())?;
+        } else if !cmp_pubkeys(&dst_token.mint, mint_info.key) {
+            return Err(CardError::InvalidMint.into());
+        }
         assert_token_owned_by(&fee_token, &fee::id())?;
     }
 
     let fee_from_bps = calculate_fee(args.amount, args.fee_bps as u64)?;
-    let total_fee = fee_from_bps
+    let requested_fee = fee_from_bps
         .checked_add(args.fixed_fee)
         .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    let total_fee = scheduled_fee(
+        load_fee_schedule(program_id, accounts, &mint_config)?.as_ref(),
+        &mint_config,
+        merchant.as_ref(),
+        FeeOperation::Escrow,
+        args.amount,
+        requested_fee,
+    )?;
     let total = args
         .amount
         .checked_add(total_fee)
         .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
 
-    transfer(
-        is_native,
-        src_token_info,
-        vault_token_info,
-        wallet_info,
-        total,
-        &[],
-    )?;
+    if is_native {
+        // the fee payer keeps the vault rent exempt, the reserve comes back on close
+        let rent = Rent::from_account_info(rent_info)?;
+        let reserve = rent
+            .minimum_balance(0)
+            .saturating_sub(vault_token_info.lamports());
+        if reserve > 0 {
+            native_transfer(fee_payer_info, vault_token_info, reserve, &[])?;
+        }
+    }
+
+    if args.hold {
+        // like a card authorization, the funds stay put until captured on settlement;
+        // the allowance adds up with the other holds on the same source
+        let src_token: TokenAccount = assert_initialized(src_token_info)?;
+        if src_token.amount < total {
+            return Err(ProgramError::InsufficientFunds);
+        }
+        let held = if src_token.delegate == COption::Some(vault_owner_key) {
+            src_token.delegated_amount
+        } else {
+            0
+        };
+        let allowance = held
+            .checked_add(total)
+            .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+        spl_token_approve(src_token_info, vault_owner_info, wallet_info, allowance)?;
+    } else {
+        transfer(
+            is_native,
+            src_token_info,
+            vault_token_info,
+            wallet_info,
+            total,
+            &[],
+        )?;
+    }
 
     create_new_account_raw(
         program_id,
@@ -135,138 +291,393 @@ pub fn process_init_escrow(
         ],
     )?;
 
-    let mut escrow = Escrow::unpack_unchecked(&escrow_info.data.borrow())?;
+    let charged_at = match card {
+        Some(_) => Clock::get()?.unix_timestamp,
+        None => 0,
+    };
+    let mut data = escrow_info.data.borrow_mut();
+    let escrow = PodEscrow::load_mut_unchecked(&mut data)?;
     if escrow.is_initialized() {
         return Err(ProgramError::AccountAlreadyInitialized);
     }
-    escrow.is_initialized = true;
-    escrow.is_settled = false;
-    escrow.is_canceled = false;
-    escrow.fee_bps = args.fee_bps;
-    escrow.fixed_fee = args.fixed_fee;
-    escrow.src_token = *src_token_info.key;
-    escrow.dst_token = *dst_token_info.key;
-    escrow.vault_token = *vault_token_info.key;
-    escrow.fee_token = *fee_token_info.key;
-    escrow.amount = args.amount;
-    escrow.mint = *mint_info.key;
-    escrow.reference = *reference_info.key;
-
-    Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+    *escrow = PodEscrow {
+        is_initialized: true.into(),
+        fee_bps: args.fee_bps.into(),
+        fixed_fee: args.fixed_fee.into(),
+        src_token: *src_token_info.key,
+        dst_token: *dst_token_info.key,
+        vault_token: *vault_token_info.key,
+        fee_token: *fee_token_info.key,
+        amount: args.amount.into(),
+        mint: *mint_info.key,
+        reference: *reference_info.key,
+        fee: total_fee.into(),
+        merchant: args.merchant.unwrap_or_default(),
+        split: PodSplit::from(&args.split.unwrap_or_default()),
+        is_hold: args.hold.into(),
+        authority_bump,
+        vault_bump,
+        card: card.unwrap_or_default(),
+        charged_at: charged_at.into(),
+        ..PodEscrow::default()
+    };
     Ok(())
 }
 
 //inside: impl Processor {}
 pub fn process_settlement(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
-    msg!("Process settlement");
+    verbose_msg!("Process settlement");
     let account_info_iter = &mut accounts.iter();
     let authority_info = next_account_info(account_info_iter)?;
 
-    assert_signer(authority_info)?;
-    assert_account_key(
-        authority_info,
-        &authority::id(),
-        Some(CardError::InvalidAuthorityId),
-    )?;
-
     let dst_token_info = next_account_info(account_info_iter)?;
     let fee_token_info = next_account_info(account_info_iter)?;
 
     let vault_token_info = next_account_info(account_info_iter)?;
-    assert_owned_by(vault_token_info, &spl_token::id())?;
 
     let escrow_info = next_account_info(account_info_iter)?;
-    let mut escrow = Escrow::unpack(&escrow_info.data.borrow())?;
-
-    if escrow.is_canceled {
-        return Err(AccountAlreadyCanceled.into());
-    }
-    if escrow.is_settled {
-        return Err(AccountAlreadySettled.into());
-    }
-
-    assert_account_key(
-        dst_token_info,
-        &escrow.dst_token,
-        Some(CardError::InvalidDstTokenOwner),
+    let escrow = load_settleable_escrow(program_id, escrow_info)?;
+    assert_authorized(
+        program_id,
+        authority_info,
+        accounts,
+        Role::Settler,
+        &escrow.reference,
+        escrow.amount.into(),
+        AuthorizationAction::Settle,
+        Hash::default(),
     )?;
-    assert_account_key(
+    let mint_info = next_account_info(account_info_iter)?;
+    assert_settlement_accounts(
+        program_id,
+        accounts,
+        &escrow,
+        dst_token_info,
         fee_token_info,
-        &escrow.fee_token,
-        Some(CardError::InvalidFeeTokenOwner),
-    )?;
-    assert_account_key(
         vault_token_info,
-        &escrow.vault_token,
-        Some(CardError::InvalidVaultTokenOwner),
+        mint_info,
     )?;
-    let mint_info = next_account_info(account_info_iter)?;
-    assert_account_key(mint_info, &escrow.mint, Some(CardError::InvalidMint))?;
     let vault_owner_info = next_account_info(account_info_iter)?;
 
-    let (vault_owner_key, bump) = find_program_authority(program_id);
-    assert_account_key(
+    assert_program_authority(
+        program_id,
         vault_owner_info,
-        &vault_owner_key,
-        Some(CardError::InvalidVaultOwner),
+        escrow.authority_bump,
+        CardError::InvalidVaultOwner,
     )?;
 
     let token_program_info = next_account_info(account_info_iter)?;
     assert_account_key(token_program_info, &spl_token::id(), None)?;
 
-    let vault_signer_seeds = [PREFIX.as_bytes(), program_id.as_ref(), &[bump]];
-
     let is_native = cmp_pubkeys(mint_info.key, &spl_token::native_mint::id());
-    let fee_from_bps = calculate_fee(escrow.amount, escrow.fee_bps as u64)?;
-    let total_fee = fee_from_bps
-        .checked_add(escrow.fixed_fee)
-        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    if !is_native {
+        assert_owned_by(vault_token_info, &spl_token::id())?;
+    }
+    let authority_bump = [escrow.authority_bump];
+    let vault_bump;
+    let vault_signer_seeds: &[&[u8]] = if is_native {
+        vault_bump = [escrow.vault_bump];
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            escrow.reference.as_ref(),
+            Escrow::VAULT_PREFIX.as_bytes(),
+            &vault_bump,
+        ]
+    } else {
+        &[PREFIX.as_bytes(), program_id.as_ref(), &authority_bump]
+    };
 
-    transfer(
+    pay_out_escrow(
+        accounts,
+        &escrow,
         is_native,
         vault_token_info,
         dst_token_info,
         vault_owner_info,
-        escrow.amount,
-        &[&vault_signer_seeds],
+        vault_signer_seeds,
     )?;
     transfer(
         is_native,
         vault_token_info,
         fee_token_info,
         vault_owner_info,
-        total_fee,
-        &[&vault_signer_seeds],
+        escrow.fee.into(),
+        &[vault_signer_seeds],
     )?;
-    msg!("Mark the escrow account as settled...");
-    escrow.is_settled = true;
-    Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
-    Ok(())
+    if is_native {
+        sweep_native_vault(vault_token_info, escrow_info, vault_signer_seeds)?;
+    }
+    verbose_msg!("Mark the escrow account as settled...");
+    mark_settled(
+        escrow_info,
+        dst_token_info.key,
+        Clock::get()?.unix_timestamp,
+    )
 }
 
 //inside: impl Processor {}
-pub fn process_cancel(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
-    msg!("Process cancelation");
+pub fn process_settle_batch(
+    accounts: &[AccountInfo],
+    args: SettleBatchArgs,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose_msg!("Process batch settlement");
     let account_info_iter = &mut accounts.iter();
     let authority_info = next_account_info(account_info_iter)?;
-    assert_signer(authority_info)?;
-    assert_account_key(
-        authority_info,
-        &authority::id(),
-        Some(CardError::InvalidAuthorityId),
+    let fee_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let vault_owner_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    let _config_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, authority_info, accounts, Role::Settler)?;
+    let bump = load_config(program_id, accounts)?.authority_bump;
+    assert_program_authority(
+        program_id,
+        vault_owner_info,
+        bump,
+        CardError::InvalidVaultOwner,
     )?;
+    assert_account_key(token_program_info, &spl_token::id(), None)?;
+    let is_native = cmp_pubkeys(mint_info.key, &spl_token::native_mint::id());
+    let authority_signer_seeds: &[&[u8]] = &[PREFIX.as_bytes(), program_id.as_ref(), &[bump]];
+    let now = Clock::get()?.unix_timestamp;
+
+    // the fees of escrows sharing a token vault are collected in a single transfer
+    let mut vault_fees: Vec<(&AccountInfo, u64)> = Vec::new();
+    for _ in 0..args.count {
+        let escrow_info = next_account_info(account_info_iter)?;
+        let vault_token_info = next_account_info(account_info_iter)?;
+        let dst_token_info = next_account_info(account_info_iter)?;
+        let escrow = load_settleable_escrow(program_id, escrow_info).and_then(|escrow| {
+            assert_settlement_accounts(
+                program_id,
+                accounts,
+                &escrow,
+                dst_token_info,
+                fee_token_info,
+                vault_token_info,
+                mint_info,
+            )?;
+            if !is_native {
+                assert_owned_by(vault_token_info, &spl_token::id())?;
+            }
+            Ok(escrow)
+        });
+        let escrow = match escrow {
+            Ok(escrow) => escrow,
+            Err(error) => {
+                verbose_msg!("Skipping escrow {}: {}", escrow_info.key, error);
+                emit(&CardEvent::EscrowSettlementSkipped {
+                    escrow: *escrow_info.key,
+                    error: u64::from(error),
+                });
+                continue;
+            }
+        };
+
+        let vault_bump = [escrow.vault_bump];
+        let native_vault_signer_seeds: &[&[u8]] = &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            escrow.reference.as_ref(),
+            Escrow::VAULT_PREFIX.as_bytes(),
+            &vault_bump,
+        ];
+        let vault_signer_seeds = if is_native {
+            native_vault_signer_seeds
+        } else {
+            authority_signer_seeds
+        };
+        pay_out_escrow(
+            accounts,
+            &escrow,
+            is_native,
+            vault_token_info,
+            dst_token_info,
+            vault_owner_info,
+            vault_signer_seeds,
+        )?;
+        if is_native {
+            transfer(
+                is_native,
+                vault_token_info,
+                fee_token_info,
+                vault_owner_info,
+                escrow.fee.into(),
+                &[vault_signer_seeds],
+            )?;
+            sweep_native_vault(vault_token_info, escrow_info, vault_signer_seeds)?;
+        } else {
+            match vault_fees
+                .iter_mut()
+                .find(|(vault_info, _)| cmp_pubkeys(vault_info.key, vault_token_info.key))
+            {
+                Some((_, fee)) => {
+                    *fee = fee
+                        .checked_add(escrow.fee.into())
+                        .ok_or::<ProgramError>(CardError::MathOverflow.into())?
+                }
+                None => vault_fees.push((vault_token_info, escrow.fee.into())),
+            }
+        }
+        emit(&CardEvent::EscrowSettled {
+            escrow: *escrow_info.key,
+            amount: escrow.amount.into(),
+            fee: escrow.fee.into(),
+        });
+        mark_settled(escrow_info, dst_token_info.key, now)?;
+    }
+    for (vault_token_info, fee) in vault_fees {
+        transfer(
+            false,
+            vault_token_info,
+            fee_token_info,
+            vault_owner_info,
+            fee,
+            &[authority_signer_seeds],
+        )?;
+    }
+    Ok(())
+}
 
-    let escrow_info = next_account_info(account_info_iter)?;
-    let src_token_info = next_account_info(account_info_iter)?;
-    let vault_token_info = next_account_info(account_info_iter)?;
-    let mut escrow = Escrow::unpack(&escrow_info.data.borrow())?;
+/// Copy of an escrow that is neither settled, canceled nor disputed, the escrow
+/// account taking part in the transfers that follow
+fn load_settleable_escrow(
+    program_id: &Pubkey,
+    escrow_info: &AccountInfo,
+) -> Result<PodEscrow, ProgramError> {
+    assert_owned_by(escrow_info, program_id)?;
+    let escrow = *PodEscrow::load(&escrow_info.data.borrow())?;
 
-    if escrow.is_canceled {
+    if escrow.is_canceled.into() {
         return Err(AccountAlreadyCanceled.into());
     }
-    if escrow.is_settled {
+    if escrow.is_settled.into() {
         return Err(AccountAlreadySettled.into());
     }
+    if escrow.dispute_status()? == DisputeStatus::Disputed {
+        return Err(CardError::EscrowDisputed.into());
+    }
+    Ok(escrow)
+}
+
+/// Mark the escrow settled in place, refunds being taken back from `dst_token`
+/// where it was paid
+fn mark_settled(
+    escrow_info: &AccountInfo,
+    dst_token: &Pubkey,
+    now: UnixTimestamp,
+) -> ProgramResult {
+    let mut data = escrow_info.data.borrow_mut();
+    let escrow = PodEscrow::load_mut(&mut data)?;
+    escrow.dst_token = *dst_token;
+    escrow.is_settled = true.into();
+    escrow.settled_at = now.into();
+    Ok(())
+}
+
+/// Assert the destination, fee, vault and mint accounts are the escrow's
+fn assert_settlement_accounts(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    escrow: &PodEscrow,
+    dst_token_info: &AccountInfo,
+    fee_token_info: &AccountInfo,
+    vault_token_info: &AccountInfo,
+    mint_info: &AccountInfo,
+) -> ProgramResult {
+    // merchants are paid at their current settlement account
+    let dst_token = if escrow.merchant == Pubkey::default() {
+        escrow.dst_token
+    } else {
+        let merchant = load_merchant(program_id, accounts, &escrow.merchant)?;
+        *merchant
+            .settlement(&escrow.mint)
+            .ok_or::<ProgramError>(CardError::InvalidMerchant.into())?
+    };
+    assert_account_key(
+        dst_token_info,
+        &dst_token,
+        Some(CardError::InvalidDstTokenOwner),
+    )?;
+    assert_account_key(
+        fee_token_info,
+        &escrow.fee_token,
+        Some(CardError::InvalidFeeTokenOwner),
+    )?;
+    assert_account_key(
+        vault_token_info,
+        &escrow.vault_token,
+        Some(CardError::InvalidVaultTokenOwner),
+    )?;
+    assert_account_key(mint_info, &escrow.mint, Some(CardError::InvalidMint))
+}
+
+/// Pay the escrow amount out of the vault to the destination, or to the payees
+/// found among `accounts` when it is split
+fn pay_out_escrow<'a>(
+    accounts: &[AccountInfo<'a>],
+    escrow: &PodEscrow,
+    is_native: bool,
+    vault_token_info: &AccountInfo<'a>,
+    dst_token_info: &AccountInfo<'a>,
+    vault_owner_info: &AccountInfo<'a>,
+    vault_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let split = escrow.split()?;
+    if split.payees().is_empty() {
+        return transfer(
+            is_native,
+            vault_token_info,
+            dst_token_info,
+            vault_owner_info,
+            escrow.amount.into(),
+            &[vault_signer_seeds],
+        );
+    }
+    let shares = split
+        .shares(escrow.amount.into())
+        .ok_or::<ProgramError>(CardError::InvalidPayees.into())?;
+    for (payee, share) in split.payees().iter().zip(shares) {
+        let payee_info = accounts
+            .iter()
+            .find(|info| cmp_pubkeys(info.key, &payee.token))
+            .ok_or::<ProgramError>(CardError::InvalidPayeeAccount.into())?;
+        transfer(
+            is_native,
+            vault_token_info,
+            payee_info,
+            vault_owner_info,
+            share,
+            &[vault_signer_seeds],
+        )?;
+    }
+    Ok(())
+}
+
+//inside: impl Processor {}
+pub fn process_cancel(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    verbose_msg!("Process cancelation");
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let escrow_info = next_account_info(account_info_iter)?;
+    let src_token_info = next_account_info(account_info_iter)?;
+    let vault_token_info = next_account_info(account_info_iter)?;
+    let escrow = load_settleable_escrow(program_id, escrow_info)?;
+    assert_authorized(
+        program_id,
+        authority_info,
+        accounts,
+        Role::Canceler,
+        &escrow.reference,
+        escrow.amount.into(),
+        AuthorizationAction::Cancel,
+        Hash::default(),
+    )?;
 
     assert_account_key(
         src_token_info,
@@ -285,63 +696,327 @@ pub fn process_cancel(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramR
     assert_account_key(mint_info, &escrow.mint, Some(CardError::InvalidMint))?;
     let vault_owner_info = next_account_info(account_info_iter)?;
 
-    let (vault_owner_key, bump_seed) = find_program_authority(program_id);
-
-    assert_account_key(
+    assert_program_authority(
+        program_id,
         vault_owner_info,
-        &vault_owner_key,
-        Some(CardError::InvalidVaultTokenOwner),
+        escrow.authority_bump,
+        CardError::InvalidVaultTokenOwner,
     )?;
     let token_program_info = next_account_info(account_info_iter)?;
     assert_account_key(token_program_info, &spl_token::id(), None)?;
-    let fee_from_bps = calculate_fee(escrow.amount, escrow.fee_bps as u64)?;
-    let total_fee = fee_from_bps
-        .checked_add(escrow.fixed_fee)
-        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    let total_fee: u64 = escrow.fee.into();
 
-    let total = escrow
-        .amount
+    let total = u64::from(escrow.amount)
         .checked_add(total_fee)
         .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
-    let vault_signer_seeds = [PREFIX.as_bytes(), program_id.as_ref(), &[bump_seed]];
     let is_native = cmp_pubkeys(mint_info.key, &spl_token::native_mint::id());
+    let authority_bump = [escrow.authority_bump];
+    let vault_bump;
+    let vault_signer_seeds: &[&[u8]] = if is_native {
+        vault_bump = [escrow.vault_bump];
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            escrow.reference.as_ref(),
+            Escrow::VAULT_PREFIX.as_bytes(),
+            &vault_bump,
+        ]
+    } else {
+        &[PREFIX.as_bytes(), program_id.as_ref(), &authority_bump]
+    };
+
+    if escrow.is_hold.into() {
+        release_hold(src_token_info, vault_owner_info, accounts, total)?;
+    } else {
+        transfer(
+            is_native,
+            vault_token_info,
+            src_token_info,
+            vault_owner_info,
+            total,
+            &[vault_signer_seeds],
+        )?;
+    }
+    if is_native {
+        sweep_native_vault(vault_token_info, escrow_info, vault_signer_seeds)?;
+    }
+    if escrow.card != Pubkey::default() {
+        uncharge_card(
+            program_id,
+            accounts,
+            &escrow.card,
+            escrow.amount.into(),
+            escrow.charged_at.into(),
+        )?;
+    }
 
+    verbose_msg!("Mark the escrow account as canceled...");
+    let mut data = escrow_info.data.borrow_mut();
+    PodEscrow::load_mut(&mut data)?.is_canceled = true.into();
+    Ok(())
+}
+
+pub fn process_refund(
+    accounts: &[AccountInfo],
+    args: RefundArgs,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose_msg!("Process refund");
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+
+    let escrow_info = next_account_info(account_info_iter)?;
+    assert_owned_by(escrow_info, program_id)?;
+    let escrow = *PodEscrow::load(&escrow_info.data.borrow())?;
+
+    if !bool::from(escrow.is_settled) {
+        return Err(CardError::AccountNotSettled.into());
+    }
+    if escrow.dispute_status()? == DisputeStatus::Disputed {
+        return Err(CardError::EscrowDisputed.into());
+    }
+    assert_authorized(
+        program_id,
+        authority_info,
+        accounts,
+        Role::Canceler,
+        &escrow.reference,
+        args.amount,
+        AuthorizationAction::Refund,
+        Authorization::hash_args(&args)?,
+    )?;
+    // a split has no single destination to take the refund from
+    if escrow.split.payee_count > 0 {
+        return Err(CardError::InvalidPayees.into());
+    }
+
+    let src_token_info = next_account_info(account_info_iter)?;
+    let dst_token_info = next_account_info(account_info_iter)?;
+    let dst_owner_info = next_account_info(account_info_iter)?;
+    assert_account_key(
+        src_token_info,
+        &escrow.src_token,
+        Some(CardError::InvalidSrcTokenOwner),
+    )?;
+    assert_account_key(
+        dst_token_info,
+        &escrow.dst_token,
+        Some(CardError::InvalidDstTokenOwner),
+    )?;
+    assert_signer(dst_owner_info)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    assert_account_key(mint_info, &escrow.mint, Some(CardError::InvalidMint))?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    assert_account_key(token_program_info, &spl_token::id(), None)?;
+    let _system_program_info = next_account_info(account_info_iter)?;
+    let _config_info = next_account_info(account_info_iter)?;
+
+    if args.refunded_amount != u64::from(escrow.refunded_amount) {
+        return Err(CardError::StaleRefund.into());
+    }
+    let refunded_amount = u64::from(escrow.refunded_amount)
+        .checked_add(args.amount)
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    if refunded_amount > escrow.amount.into() {
+        return Err(CardError::RefundTooLarge.into());
+    }
+    let is_native = cmp_pubkeys(mint_info.key, &spl_token::native_mint::id());
     transfer(
         is_native,
-        vault_token_info,
+        dst_token_info,
         src_token_info,
-        vault_owner_info,
-        total,
-        &[&vault_signer_seeds],
+        dst_owner_info,
+        args.amount,
+        &[],
     )?;
 
-    msg!("Mark the escrow account as canceled...");
-    escrow.is_canceled = true;
-    Escrow::pack(escrow, &mut escrow_info.data.borrow_mut())?;
+    let mut refunded_fee = escrow.refunded_fee.into();
+    if args.refund_fee {
+        let fee_token_info = next_account_info(account_info_iter)?;
+        let fee_owner_info = next_account_info(account_info_iter)?;
+        assert_account_key(
+            fee_token_info,
+            &escrow.fee_token,
+            Some(CardError::InvalidFeeTokenOwner),
+        )?;
+        assert_signer(fee_owner_info)?;
+        // the fee refunded so far follows the amount refunded so far, so that
+        // partial refunds add up to the whole fee
+        let owed_fee = (u64::from(escrow.fee) as u128)
+            .checked_mul(refunded_amount as u128)
+            .and_then(|fee| fee.checked_div(u64::from(escrow.amount) as u128))
+            .ok_or::<ProgramError>(CardError::MathOverflow.into())? as u64;
+        let fee_refund = owed_fee.saturating_sub(refunded_fee);
+        transfer(
+            is_native,
+            fee_token_info,
+            src_token_info,
+            fee_owner_info,
+            fee_refund,
+            &[],
+        )?;
+        refunded_fee = owed_fee.max(refunded_fee);
+    }
+
+    let mut data = escrow_info.data.borrow_mut();
+    let escrow = PodEscrow::load_mut(&mut data)?;
+    escrow.refunded_amount = refunded_amount.into();
+    escrow.refunded_fee = refunded_fee.into();
+    Ok(())
+}
+
+pub fn process_dispute(
+    accounts: &[AccountInfo],
+    args: DisputeArgs,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose_msg!("Process dispute");
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    assert_role(program_id, authority_info, accounts, Role::Canceler)?;
+    let escrow_info = next_account_info(account_info_iter)?;
+    assert_owned_by(escrow_info, program_id)?;
+    let mut data = escrow_info.data.borrow_mut();
+    let escrow = PodEscrow::load_mut(&mut data)?;
+
+    // an escrow is disputed at most once, and only while its funds can still move
+    let now = Clock::get()?.unix_timestamp;
+    let window_open = !bool::from(escrow.is_settled)
+        || now.saturating_sub(escrow.settled_at.into()) <= DISPUTE_WINDOW;
+    if escrow.is_canceled.into() || escrow.dispute_status()? != DisputeStatus::None || !window_open
+    {
+        return Err(CardError::DisputeNotAllowed.into());
+    }
+    // a settled split can't be refunded, so resolving its dispute could never complete
+    if bool::from(escrow.is_settled) && escrow.split.payee_count > 0 {
+        return Err(CardError::DisputeNotAllowed.into());
+    }
+
+    escrow.dispute_status = DisputeStatus::Disputed as u8;
+    escrow.disputed_at = now.into();
+    escrow.dispute_reason = args.reason.into();
     Ok(())
 }
 
+pub fn process_resolve_dispute(
+    accounts: &[AccountInfo],
+    args: ResolveDisputeArgs,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose_msg!("Process dispute resolution");
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    assert_role(program_id, authority_info, accounts, Role::Canceler)?;
+    let escrow_info = next_account_info(account_info_iter)?;
+    assert_owned_by(escrow_info, program_id)?;
+    let (is_settled, refund_args) = {
+        let mut data = escrow_info.data.borrow_mut();
+        let escrow = PodEscrow::load_mut(&mut data)?;
+        if escrow.dispute_status()? != DisputeStatus::Disputed {
+            return Err(CardError::EscrowNotDisputed.into());
+        }
+        escrow.dispute_status = match args.outcome {
+            DisputeOutcome::Release => DisputeStatus::Released,
+            DisputeOutcome::Refund => DisputeStatus::Refunded,
+        } as u8;
+        escrow.resolved_at = Clock::get()?.unix_timestamp.into();
+        let refund_args = RefundArgs {
+            amount: u64::from(escrow.amount).saturating_sub(escrow.refunded_amount.into()),
+            refund_fee: true,
+            refunded_amount: escrow.refunded_amount.into(),
+        };
+        (bool::from(escrow.is_settled), refund_args)
+    };
+
+    // the completing instruction's accounts follow the escrow, and must be about it
+    let completion_accounts = &accounts[2..];
+    let escrow_index = match (args.outcome, is_settled) {
+        (DisputeOutcome::Release, false) => Some(4),
+        (DisputeOutcome::Release, true) => None,
+        (DisputeOutcome::Refund, _) => Some(1),
+    };
+    if let Some(index) = escrow_index {
+        let completion_escrow_info = completion_accounts
+            .get(index)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        assert_account_key(completion_escrow_info, escrow_info.key, None)?;
+    }
+    match (args.outcome, is_settled) {
+        (DisputeOutcome::Release, false) => {
+            assert_not_paused(program_id, accounts, PAUSE_SETTLE)?;
+            process_settlement(completion_accounts, program_id)
+        }
+        (DisputeOutcome::Release, true) => Ok(()),
+        (DisputeOutcome::Refund, false) => process_cancel(completion_accounts, program_id),
+        (DisputeOutcome::Refund, true) => {
+            process_refund(completion_accounts, refund_args, program_id)
+        }
+    }
+}
+
 //inside: impl Processor {}
 pub fn process_close(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let authority_info = next_account_info(account_info_iter)?;
-    assert_signer(authority_info)?;
-    assert_account_key(
-        authority_info,
-        &authority::id(),
-        Some(CardError::InvalidAuthorityId),
-    )?;
+    assert_role(program_id, authority_info, accounts, Role::RentCloser)?;
     let escrow_info = next_account_info(account_info_iter)?;
     assert_owned_by(escrow_info, program_id)?;
 
-    let escrow = Escrow::unpack(&escrow_info.data.borrow())?;
-
-    if !(escrow.is_settled || escrow.is_canceled) {
-        return Err(AccountNotSettledOrCanceled.into());
+    {
+        let data = escrow_info.data.borrow();
+        let escrow = PodEscrow::load(&data)?;
+        if !(bool::from(escrow.is_settled) || bool::from(escrow.is_canceled)) {
+            return Err(AccountNotSettledOrCanceled.into());
+        }
+        if escrow.dispute_status()? == DisputeStatus::Disputed {
+            return Err(CardError::EscrowDisputed.into());
+        }
     }
 
     let fee_payer_info = next_account_info(account_info_iter)?;
-    msg!("Closing the escrow account...");
+    verbose_msg!("Closing the escrow account...");
     empty_account_balance(escrow_info, fee_payer_info)?;
     Ok(())
 }
+
+/// Take the `total` of a canceled hold off the allowance of the program authority
+/// when the owner of the source signed. Without the owner the hold is released all
+/// the same, the allowance standing until the owner revokes it
+fn release_hold<'a>(
+    src_token_info: &AccountInfo<'a>,
+    vault_owner_info: &AccountInfo<'a>,
+    accounts: &[AccountInfo<'a>],
+    total: u64,
+) -> ProgramResult {
+    let src_token: TokenAccount = assert_initialized(src_token_info)?;
+    let owner_info = match accounts
+        .iter()
+        .find(|info| info.is_signer && cmp_pubkeys(info.key, &src_token.owner))
+    {
+        Some(owner_info) => owner_info,
+        None => return Ok(()),
+    };
+    if src_token.delegate != COption::Some(*vault_owner_info.key) {
+        return Ok(());
+    }
+    let allowance = src_token.delegated_amount.saturating_sub(total);
+    if allowance == 0 {
+        spl_token_revoke(src_token_info, owner_info)
+    } else {
+        spl_token_approve(src_token_info, vault_owner_info, owner_info, allowance)
+    }
+}
+
+/// Empty a native vault into the escrow account so that no dust is left behind;
+/// the rent reserve goes back to the fee payer when the escrow is closed
+fn sweep_native_vault<'a>(
+    vault_info: &AccountInfo<'a>,
+    escrow_info: &AccountInfo<'a>,
+    vault_signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let remaining = vault_info.lamports();
+    if remaining > 0 {
+        native_transfer(vault_info, escrow_info, remaining, &[vault_signer_seeds])?;
+    }
+    Ok(())
+}
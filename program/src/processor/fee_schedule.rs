@@ -0,0 +1,113 @@
+//! Fee schedule instruction processing
+
+use crate::{
+    error::CardError,
+    find_fee_schedule_program_address, find_mint_config_program_address,
+    instruction::{InitFeeScheduleArgs, SetFeeScheduleArgs},
+    state::{
+        config::Role,
+        fee_schedule::{FeeRules, FeeSchedule},
+        mint_config::MintConfig,
+    },
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+
+/// Process InitFeeSchedule instruction
+pub fn init(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitFeeScheduleArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let fee_schedule_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+    let _config_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(mint_info, &spl_token::id())?;
+    assert_rules(&args.rules)?;
+    let (mint_config_key, _) = find_mint_config_program_address(program_id, mint_info.key);
+    assert_account_key(
+        mint_config_info,
+        &mint_config_key,
+        Some(CardError::MintNotSupported),
+    )?;
+    assert_owned_by(mint_config_info, program_id)?;
+    let mut mint_config: MintConfig = assert_initialized(mint_config_info)?;
+    if fee_schedule_info.lamports() > 0 && !fee_schedule_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if args.bump != find_fee_schedule_program_address(program_id, mint_info.key).1 {
+        return Err(CardError::InvalidFeeSchedule.into());
+    }
+
+    create_new_account_raw(
+        program_id,
+        fee_schedule_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        FeeSchedule::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mint_info.key.as_ref(),
+            FeeSchedule::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut fee_schedule = FeeSchedule::unpack_unchecked(&fee_schedule_info.data.borrow())?;
+    if fee_schedule.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    fee_schedule.is_initialized = true;
+    fee_schedule.mint = *mint_info.key;
+    fee_schedule.rules = args.rules;
+    FeeSchedule::pack(fee_schedule, &mut fee_schedule_info.data.borrow_mut())?;
+
+    // instructions charging a fee in the mint need the schedule from now on
+    mint_config.has_fee_schedule = true;
+    mint_config.fee_schedule_bump = args.bump;
+    MintConfig::pack(mint_config, &mut mint_config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process SetFeeSchedule instruction
+pub fn set(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetFeeScheduleArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let fee_schedule_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(fee_schedule_info, program_id)?;
+    assert_rules(&args.rules)?;
+    let mut fee_schedule: FeeSchedule = assert_initialized(fee_schedule_info)?;
+    fee_schedule.rules = args.rules;
+    FeeSchedule::pack(fee_schedule, &mut fee_schedule_info.data.borrow_mut())?;
+    Ok(())
+}
+
+fn assert_rules(rules: &FeeRules) -> ProgramResult {
+    if !rules.is_valid() {
+        return Err(CardError::InvalidFeeSchedule.into());
+    }
+    Ok(())
+}
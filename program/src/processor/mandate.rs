@@ -0,0 +1,214 @@
+//! Mandate instruction processing
+
+use crate::{
+    error::CardError,
+    find_mandate_delegate_program_address,
+    instruction::{ChargeMandateArgs, InitMandateArgs},
+    state::{config::Role, mandate::Mandate},
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_option::COption,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use spl_token::state::Account as TokenAccount;
+
+/// Process InitMandate instruction
+pub fn init(program_id: &Pubkey, accounts: &[AccountInfo], args: InitMandateArgs) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let mandate_info = next_account_info(account_info_iter)?;
+    let source_token_info = next_account_info(account_info_iter)?;
+    let _merchant_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    if !args.terms.is_valid() {
+        return Err(CardError::InvalidMandate.into());
+    }
+    assert_owned_by(source_token_info, &spl_token::id())?;
+    let source_token: TokenAccount = assert_initialized(source_token_info)?;
+    assert_token_owned_by(&source_token, owner_info.key)?;
+    let merchant = load_merchant(program_id, accounts, &args.merchant)?;
+    if merchant.settlement(&source_token.mint).is_none() {
+        return Err(CardError::InvalidMerchant.into());
+    }
+    if mandate_info.lamports() > 0 && !mandate_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_new_account_raw(
+        program_id,
+        mandate_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        Mandate::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            owner_info.key.as_ref(),
+            args.merchant.as_ref(),
+            Mandate::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut mandate = Mandate::unpack_unchecked(&mandate_info.data.borrow())?;
+    if mandate.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    mandate.is_initialized = true;
+    mandate.owner = *owner_info.key;
+    mandate.merchant = args.merchant;
+    mandate.mint = source_token.mint;
+    mandate.source_token = *source_token_info.key;
+    mandate.terms = args.terms;
+    mandate.delegate_bump = find_mandate_delegate_program_address(program_id, mandate_info.key).1;
+    Mandate::pack(mandate, &mut mandate_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process ChargeMandate instruction
+pub fn charge(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: ChargeMandateArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let mandate_info = next_account_info(account_info_iter)?;
+    let source_token_info = next_account_info(account_info_iter)?;
+    let settlement_token_info = next_account_info(account_info_iter)?;
+    let delegate_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_owned_by(mandate_info, program_id)?;
+    let mut mandate: Mandate = assert_initialized(mandate_info)?;
+    // the merchant charges its own mandates, the settler any of them
+    if cmp_pubkeys(authority_info.key, &mandate.merchant) {
+        assert_signer(authority_info)?;
+    } else {
+        assert_role(program_id, authority_info, accounts, Role::Settler)?;
+    }
+    assert_account_key(
+        source_token_info,
+        &mandate.source_token,
+        Some(CardError::InvalidSrcTokenOwner),
+    )?;
+    let merchant = load_merchant(program_id, accounts, &mandate.merchant)?;
+    let settlement = merchant
+        .settlement(&mandate.mint)
+        .ok_or::<ProgramError>(CardError::InvalidMerchant.into())?;
+    assert_account_key(
+        settlement_token_info,
+        settlement,
+        Some(CardError::InvalidDstTokenOwner),
+    )?;
+    let delegate_bump = [mandate.delegate_bump];
+    let delegate_seeds: &[&[u8]] = &[
+        PREFIX.as_bytes(),
+        program_id.as_ref(),
+        mandate_info.key.as_ref(),
+        Mandate::DELEGATE_PREFIX.as_bytes(),
+        &delegate_bump,
+    ];
+    let delegate_key = Pubkey::create_program_address(delegate_seeds, program_id)
+        .map_err(|_| CardError::InvalidMandateDelegate)?;
+    assert_account_key(
+        delegate_info,
+        &delegate_key,
+        Some(CardError::InvalidMandateDelegate),
+    )?;
+    assert_account_key(token_program_info, &spl_token::id(), None)?;
+
+    if args.amount == 0 || args.amount > mandate.terms.max_amount {
+        return Err(CardError::MandateAmountExceeded.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if !mandate
+        .is_chargeable(now)
+        .ok_or::<ProgramError>(CardError::MandateInactive.into())?
+    {
+        return Err(CardError::MandateAlreadyCharged.into());
+    }
+    charge_card(
+        program_id,
+        accounts,
+        &mandate.owner,
+        source_token_info.key,
+        args.amount,
+        Spend::Purchase(Some(merchant.params.mcc)),
+    )?;
+
+    spl_token_transfer(
+        source_token_info,
+        settlement_token_info,
+        delegate_info,
+        args.amount,
+        &[delegate_seeds],
+    )?;
+    mandate.charge_count = mandate
+        .charge_count
+        .checked_add(1)
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    mandate.last_charged_at = now;
+    mandate.total_charged = mandate
+        .total_charged
+        .checked_add(args.amount)
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    Mandate::pack(mandate, &mut mandate_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process CancelMandate instruction
+pub fn cancel(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let owner_info = next_account_info(account_info_iter)?;
+    let mandate_info = next_account_info(account_info_iter)?;
+    let receiver_info = next_account_info(account_info_iter)?;
+    let source_token_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    assert_owned_by(mandate_info, program_id)?;
+    let mandate: Mandate = assert_initialized(mandate_info)?;
+    if !cmp_pubkeys(owner_info.key, &mandate.owner) {
+        return Err(CardError::InvalidOwner.into());
+    }
+    assert_account_key(
+        source_token_info,
+        &mandate.source_token,
+        Some(CardError::InvalidSrcTokenOwner),
+    )?;
+    assert_account_key(token_program_info, &spl_token::id(), None)?;
+
+    // the source has a single delegate, which a hold or another mandate may have taken over
+    let delegate_key = Pubkey::create_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mandate_info.key.as_ref(),
+            Mandate::DELEGATE_PREFIX.as_bytes(),
+            &[mandate.delegate_bump],
+        ],
+        program_id,
+    )
+    .map_err(|_| CardError::InvalidMandateDelegate)?;
+    let source_token: TokenAccount = assert_initialized(source_token_info)?;
+    if source_token.delegate == COption::Some(delegate_key) {
+        spl_token_revoke(source_token_info, owner_info)?;
+    }
+    mandate_info.data.borrow_mut().fill(0);
+    empty_account_balance(mandate_info, receiver_info)
+}
@@ -0,0 +1,116 @@
+//! Merchant instruction processing
+
+use crate::{
+    error::CardError,
+    find_merchant_program_address,
+    instruction::{OnboardMerchantArgs, SetMerchantStatusArgs, UpdateMerchantArgs},
+    state::{
+        config::Role,
+        merchant::{Merchant, MerchantParams, MerchantStatus},
+    },
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+
+/// Process OnboardMerchant instruction
+pub fn onboard(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: OnboardMerchantArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let merchant_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_params(&args.params)?;
+    if merchant_info.lamports() > 0 && !merchant_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    // the bump is found once here, later instructions recreating the address from it
+    if args.bump != find_merchant_program_address(program_id, &args.id).1 {
+        return Err(CardError::InvalidMerchant.into());
+    }
+
+    create_new_account_raw(
+        program_id,
+        merchant_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        Merchant::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.id.as_ref(),
+            Merchant::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut merchant = Merchant::unpack_unchecked(&merchant_info.data.borrow())?;
+    if merchant.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    merchant.is_initialized = true;
+    merchant.id = args.id;
+    merchant.status = MerchantStatus::Active;
+    merchant.params = args.params;
+    merchant.bump = args.bump;
+    Merchant::pack(merchant, &mut merchant_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process UpdateMerchant instruction
+pub fn update(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateMerchantArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let merchant_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(merchant_info, program_id)?;
+    assert_params(&args.params)?;
+    let mut merchant: Merchant = assert_initialized(merchant_info)?;
+    merchant.params = args.params;
+    Merchant::pack(merchant, &mut merchant_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process SetMerchantStatus instruction
+pub fn set_status(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetMerchantStatusArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let merchant_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(merchant_info, program_id)?;
+    let mut merchant: Merchant = assert_initialized(merchant_info)?;
+    merchant.status = args.status;
+    Merchant::pack(merchant, &mut merchant_info.data.borrow_mut())?;
+    Ok(())
+}
+
+fn assert_params(params: &MerchantParams) -> ProgramResult {
+    if !params.is_valid() {
+        return Err(CardError::InvalidMerchantConfig.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Migrate instruction processing
+
+use crate::{
+    error::CardError,
+    find_escrow_program_address, find_escrow_vault_program_address, find_program_authority,
+    state::escrow::{PodEscrow, ESCROW_DATA_SIZE, ESCROW_LEGACY_DATA_SIZE},
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+
+/// Process Migrate instruction, the size of the account telling its kind and layout
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer_info = next_account_info(account_info_iter)?;
+    let account_info = next_account_info(account_info_iter)?;
+    let _system_account_info = next_account_info(account_info_iter)?;
+
+    assert_signer(payer_info)?;
+    assert_owned_by(account_info, program_id)?;
+    if account_info.data_len() != ESCROW_LEGACY_DATA_SIZE {
+        return Err(CardError::NotMigratable.into());
+    }
+    grow(payer_info, account_info, ESCROW_DATA_SIZE)?;
+    migrate_escrow(program_id, payer_info, account_info, account_info_iter)
+}
+
+/// Fill in the fields the first escrow layout lacks. The program authority held the
+/// lamports of every native escrow back then, those of an open one move to its own vault
+fn migrate_escrow<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo<'b>,
+    escrow_info: &AccountInfo<'b>,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> ProgramResult {
+    let mut data = escrow_info.try_borrow_mut_data()?;
+    let escrow = PodEscrow::load_mut(&mut data)?;
+    let (escrow_key, _) = find_escrow_program_address(program_id, &escrow.reference);
+    assert_account_key(escrow_info, &escrow_key, Some(CardError::InvalidEscrowKey))?;
+
+    // the first escrows charge the fee they were created with
+    let fee = calculate_fee(escrow.amount.into(), u16::from(escrow.fee_bps) as u64)?
+        .checked_add(escrow.fixed_fee.into())
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    escrow.fee = fee.into();
+    let (authority_key, authority_bump) = find_program_authority(program_id);
+    escrow.authority_bump = authority_bump;
+    if escrow.mint != spl_token::native_mint::id() {
+        return Ok(());
+    }
+
+    let authority_info = next_account_info(account_info_iter)?;
+    let vault_info = next_account_info(account_info_iter)?;
+    assert_account_key(
+        authority_info,
+        &authority_key,
+        Some(CardError::InvalidVaultOwner),
+    )?;
+    let (vault_key, vault_bump) = find_escrow_vault_program_address(program_id, &escrow.reference);
+    assert_account_key(vault_info, &vault_key, Some(CardError::InvalidVaultKey))?;
+
+    let reserve = Rent::get()?
+        .minimum_balance(0)
+        .saturating_sub(vault_info.lamports());
+    if reserve > 0 {
+        native_transfer(payer_info, vault_info, reserve, &[])?;
+    }
+    if !bool::from(escrow.is_settled) && !bool::from(escrow.is_canceled) {
+        let total = u64::from(escrow.amount)
+            .checked_add(fee)
+            .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+        native_transfer(
+            authority_info,
+            vault_info,
+            total,
+            &[&[PREFIX.as_bytes(), program_id.as_ref(), &[authority_bump]]],
+        )?;
+    }
+    escrow.vault_token = vault_key;
+    escrow.vault_bump = vault_bump;
+    Ok(())
+}
+
+/// Grow `account_info` to `data_len` bytes, the payer topping up its rent
+fn grow<'a>(
+    payer_info: &AccountInfo<'a>,
+    account_info: &AccountInfo<'a>,
+    data_len: usize,
+) -> ProgramResult {
+    let missing = Rent::get()?
+        .minimum_balance(data_len)
+        .saturating_sub(account_info.lamports());
+    if missing > 0 {
+        native_transfer(payer_info, account_info, missing, &[])?;
+    }
+    account_info.realloc(data_len, true)
+}
@@ -0,0 +1,99 @@
+//! Mint config instruction processing
+
+use crate::{
+    error::CardError,
+    find_mint_config_program_address,
+    instruction::{InitMintConfigArgs, SetMintConfigArgs},
+    state::{
+        config::Role,
+        mint_config::{MintConfig, MintParams},
+    },
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+
+/// Process InitMintConfig instruction
+pub fn init(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitMintConfigArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(mint_info, &spl_token::id())?;
+    assert_params(&args.params)?;
+    if mint_config_info.lamports() > 0 && !mint_config_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    // the bump is found once here, later instructions recreating the address from it
+    if args.bump != find_mint_config_program_address(program_id, mint_info.key).1 {
+        return Err(CardError::InvalidMintConfig.into());
+    }
+
+    create_new_account_raw(
+        program_id,
+        mint_config_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        MintConfig::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mint_info.key.as_ref(),
+            MintConfig::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut mint_config = MintConfig::unpack_unchecked(&mint_config_info.data.borrow())?;
+    if mint_config.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    mint_config.is_initialized = true;
+    mint_config.mint = *mint_info.key;
+    mint_config.params = args.params;
+    mint_config.bump = args.bump;
+    MintConfig::pack(mint_config, &mut mint_config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process SetMintConfig instruction
+pub fn set(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SetMintConfigArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin_info = next_account_info(account_info_iter)?;
+    let mint_config_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, admin_info, accounts, Role::ConfigAdmin)?;
+    assert_owned_by(mint_config_info, program_id)?;
+    assert_params(&args.params)?;
+    let mut mint_config: MintConfig = assert_initialized(mint_config_info)?;
+    mint_config.params = args.params;
+    MintConfig::pack(mint_config, &mut mint_config_info.data.borrow_mut())?;
+    Ok(())
+}
+
+fn assert_params(params: &MintParams) -> ProgramResult {
+    if params.min_amount > params.max_amount || params.fee_bps > 10000 {
+        return Err(CardError::InvalidMintConfig.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Multisig approval authority instruction processing
+
+use crate::{
+    error::CardError,
+    instruction::{InitMultisigArgs, SetMultisigArgs},
+    state::{
+        config::Role,
+        multisig::{Multisig, MAX_SIGNERS},
+    },
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+};
+
+/// Process InitMultisig instruction
+pub fn init(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: InitMultisigArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let multisig_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    assert_role(program_id, authority_info, accounts, Role::ConfigAdmin)?;
+    if multisig_info.lamports() > 0 && !multisig_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_new_account_raw(
+        program_id,
+        multisig_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        Multisig::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            Multisig::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut multisig = Multisig::unpack_unchecked(&multisig_info.data.borrow())?;
+    if multisig.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    multisig.is_initialized = true;
+    set_signers(&mut multisig, args.threshold, &args.signers)?;
+    Multisig::pack(multisig, &mut multisig_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process SetMultisig instruction
+pub fn set(program_id: &Pubkey, accounts: &[AccountInfo], args: SetMultisigArgs) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let multisig_info = next_account_info(account_info_iter)?;
+
+    assert_multisig_approved(program_id, multisig_info, account_info_iter.as_slice())?;
+    let mut multisig = Multisig::unpack(&multisig_info.data.borrow())?;
+    set_signers(&mut multisig, args.threshold, &args.signers)?;
+    Multisig::pack(multisig, &mut multisig_info.data.borrow_mut())?;
+    Ok(())
+}
+
+fn set_signers(multisig: &mut Multisig, threshold: u8, signers: &[Pubkey]) -> ProgramResult {
+    let distinct = signers
+        .iter()
+        .enumerate()
+        .all(|(i, signer)| !signers[..i].contains(signer));
+    if signers.len() > MAX_SIGNERS
+        || !distinct
+        || threshold == 0
+        || threshold as usize > signers.len()
+    {
+        return Err(CardError::InvalidMultisigConfig.into());
+    }
+    multisig.threshold = threshold;
+    multisig.signer_count = signers.len() as u8;
+    multisig.signers = Default::default();
+    multisig.signers[..signers.len()].copy_from_slice(signers);
+    Ok(())
+}
@@ -0,0 +1,251 @@
+//! Scheduled payout instruction processing
+
+use crate::{
+    create_program_authority,
+    error::CardError,
+    instruction::SchedulePayoutArgs,
+    state::{
+        config::Role,
+        payout::{PayoutStatus, ScheduledPayout},
+    },
+    utils::*,
+    PREFIX,
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use spl_token::state::Account;
+
+/// Process SchedulePayout instruction
+pub fn schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: SchedulePayoutArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let wallet_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let payout_info = next_account_info(account_info_iter)?;
+    let source_token_info = next_account_info(account_info_iter)?;
+    let vault_token_info = next_account_info(account_info_iter)?;
+    let beneficiary_token_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(wallet_info)?;
+    assert_role(
+        program_id,
+        authority_info,
+        accounts,
+        Role::WithdrawalApprover,
+    )?;
+    assert_account_key(token_program_info, &spl_token::id(), None)?;
+    assert_mint_supported(program_id, accounts, mint_info.key, args.amount)?;
+    charge_card(
+        program_id,
+        accounts,
+        wallet_info.key,
+        source_token_info.key,
+        args.amount,
+        Spend::Transfer,
+    )?;
+    if payout_info.lamports() > 0 && !payout_info.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let authority_bump = load_config(program_id, accounts)?.authority_bump;
+    let vault_owner_key = create_program_authority(program_id, authority_bump)
+        .map_err(|_| ProgramError::from(CardError::InvalidVaultOwner))?;
+    for (token_info, owner) in [
+        (source_token_info, wallet_info.key),
+        (vault_token_info, &vault_owner_key),
+    ] {
+        assert_owned_by(token_info, &spl_token::id())?;
+        let token: Account = assert_initialized(token_info)?;
+        assert_token_owned_by(&token, owner)?;
+        if token.mint != *mint_info.key {
+            return Err(CardError::InvalidMint.into());
+        }
+    }
+    assert_owned_by(beneficiary_token_info, &spl_token::id())?;
+    let beneficiary_token: Account = assert_initialized(beneficiary_token_info)?;
+    if beneficiary_token.mint != *mint_info.key {
+        return Err(CardError::InvalidMint.into());
+    }
+
+    create_new_account_raw(
+        program_id,
+        payout_info,
+        rent_info,
+        payer_info,
+        system_account_info,
+        ScheduledPayout::LEN,
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            args.key.as_ref(),
+            ScheduledPayout::PREFIX.as_bytes(),
+            &[args.bump],
+        ],
+    )?;
+    let mut payout = ScheduledPayout::unpack_unchecked(&payout_info.data.borrow())?;
+    if payout.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    transfer(
+        false,
+        source_token_info,
+        vault_token_info,
+        wallet_info,
+        args.amount,
+        &[],
+    )?;
+    payout.is_initialized = true;
+    payout.status = PayoutStatus::Pending;
+    payout.key = args.key;
+    payout.mint = *mint_info.key;
+    payout.amount = args.amount;
+    payout.source_token = *source_token_info.key;
+    payout.vault_token = *vault_token_info.key;
+    payout.beneficiary_token = *beneficiary_token_info.key;
+    payout.unlock_at = args.unlock_at;
+    payout.authority_bump = authority_bump;
+    payout.rent_payer = *payer_info.key;
+    ScheduledPayout::pack(payout, &mut payout_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Process ExecutePayout instruction
+pub fn execute(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payout_info = next_account_info(account_info_iter)?;
+    let vault_token_info = next_account_info(account_info_iter)?;
+    let beneficiary_token_info = next_account_info(account_info_iter)?;
+
+    let mut payout = pending_payout(program_id, payout_info)?;
+    if Clock::get()?.unix_timestamp < payout.unlock_at {
+        return Err(CardError::PayoutLocked.into());
+    }
+    assert_account_key(
+        beneficiary_token_info,
+        &payout.beneficiary_token,
+        Some(CardError::InvalidDstTokenOwner),
+    )?;
+    pay_out(
+        program_id,
+        account_info_iter,
+        &payout,
+        vault_token_info,
+        beneficiary_token_info,
+    )?;
+    payout.status = PayoutStatus::Executed;
+    close(payout_info, payout, account_info_iter)
+}
+
+/// Process CancelPayout instruction
+pub fn cancel(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let authority_info = next_account_info(account_info_iter)?;
+    let payout_info = next_account_info(account_info_iter)?;
+    let vault_token_info = next_account_info(account_info_iter)?;
+    let source_token_info = next_account_info(account_info_iter)?;
+
+    assert_role(
+        program_id,
+        authority_info,
+        accounts,
+        Role::WithdrawalApprover,
+    )?;
+    let mut payout = pending_payout(program_id, payout_info)?;
+    if Clock::get()?.unix_timestamp >= payout.unlock_at {
+        return Err(CardError::PayoutUnlocked.into());
+    }
+    assert_account_key(
+        source_token_info,
+        &payout.source_token,
+        Some(CardError::InvalidSrcTokenOwner),
+    )?;
+    pay_out(
+        program_id,
+        account_info_iter,
+        &payout,
+        vault_token_info,
+        source_token_info,
+    )?;
+    payout.status = PayoutStatus::Canceled;
+    close(payout_info, payout, account_info_iter)
+}
+
+/// Record the final status of `payout` and return its rent to the rent payer,
+/// the next account, closing it
+fn close<'a, 'b: 'a>(
+    payout_info: &AccountInfo<'b>,
+    payout: ScheduledPayout,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> ProgramResult {
+    let rent_payer_info = next_account_info(account_info_iter)?;
+    let rent_payer = payout.rent_payer;
+    ScheduledPayout::pack(payout, &mut payout_info.data.borrow_mut())?;
+    assert_account_key(rent_payer_info, &rent_payer, None)?;
+    empty_account_balance(payout_info, rent_payer_info)
+}
+
+fn pending_payout(
+    program_id: &Pubkey,
+    payout_info: &AccountInfo,
+) -> Result<ScheduledPayout, ProgramError> {
+    assert_owned_by(payout_info, program_id)?;
+    let payout: ScheduledPayout = assert_initialized(payout_info)?;
+    if payout.status != PayoutStatus::Pending {
+        return Err(CardError::PayoutNotPending.into());
+    }
+    Ok(payout)
+}
+
+/// Move the payout amount out of the vault to `destination_info`, the PDA and
+/// token program accounts being next
+fn pay_out<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    payout: &ScheduledPayout,
+    vault_token_info: &AccountInfo<'b>,
+    destination_info: &AccountInfo<'b>,
+) -> ProgramResult {
+    let vault_owner_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    assert_account_key(
+        vault_token_info,
+        &payout.vault_token,
+        Some(CardError::InvalidVaultTokenOwner),
+    )?;
+    assert_program_authority(
+        program_id,
+        vault_owner_info,
+        payout.authority_bump,
+        CardError::InvalidVaultOwner,
+    )?;
+    assert_account_key(token_program_info, &spl_token::id(), None)?;
+    transfer(
+        false,
+        vault_token_info,
+        destination_info,
+        vault_owner_info,
+        payout.amount,
+        &[&[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            &[payout.authority_bump],
+        ]],
+    )
+}
@@ -1,11 +1,13 @@
 //! Init pass instruction processing
 
 use crate::{
+    collections::fee,
     error::CardError,
+    find_wrap_program_address,
     instruction::WithdrawArgs,
-    state::{withdraw::Withdraw, FLAG_ACCOUNT_SIZE},
+    state::{config::Role, fee_schedule::FeeOperation, withdraw::Withdraw, FLAG_ACCOUNT_SIZE},
     utils::*,
-    PREFIX,
+    PREFIX, WRAP_PREFIX,
 };
 
 use solana_program::{
@@ -14,6 +16,7 @@ use solana_program::{
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
+    system_program,
 };
 
 use spl_token::state::Account;
@@ -31,23 +34,80 @@ pub fn init(program_id: &Pubkey, accounts: &[AccountInfo], args: WithdrawArgs) -
     let mint_info = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
     let system_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter);
 
     assert_signer(wallet_info)?;
-    assert_signer(authority_info)?;
+    assert_role(
+        program_id,
+        authority_info,
+        accounts,
+        Role::WithdrawalApprover,
+    )?;
+    let mint_config = assert_mint_supported(program_id, accounts, mint_info.key, args.amount)?;
+    charge_card(
+        program_id,
+        accounts,
+        wallet_info.key,
+        source_token_info.key,
+        args.amount,
+        Spend::Transfer,
+    )?;
+    assert_account_key(
+        collection_fee_token_info,
+        &mint_config.params.fee_collection,
+        Some(CardError::InvalidCollectionAccount),
+    )?;
 
     if withdraw_info.lamports() > 0 && !withdraw_info.data_is_empty() {
         return Err(ProgramError::AccountAlreadyInitialized);
     }
-    assert_owned_by(source_token_info, &spl_token::id())?;
-    let source_token: Account = assert_initialized(source_token_info)?;
-    if source_token.mint != *mint_info.key {
-        return Err(CardError::InvalidMint.into());
+    if let Some(associated_token_program_info) = next_associated_token_program(account_info_iter) {
+        let token_program_info = token_program_info.clone()?;
+        let destination_owner_info = next_account_info(account_info_iter)?;
+        let fee_collection_info = next_account_info(account_info_iter)?;
+        assert_account_key(
+            fee_collection_info,
+            &fee::id(),
+            Some(CardError::InvalidFeeTokenOwner),
+        )?;
+        create_associated_token_account(
+            payer_info,
+            destination_token_info,
+            destination_owner_info,
+            mint_info,
+            system_account_info,
+            token_program_info,
+            associated_token_program_info,
+        )?;
+        create_associated_token_account(
+            payer_info,
+            collection_fee_token_info,
+            fee_collection_info,
+            mint_info,
+            system_account_info,
+            token_program_info,
+            associated_token_program_info,
+        )?;
     }
-    assert_owned_by(destination_token_info, &spl_token::id())?;
-    let destination_token: Account = assert_initialized(destination_token_info)?;
-    assert_token_owned_by(&source_token, wallet_info.key)?;
-    if destination_token.mint != *mint_info.key {
-        return Err(CardError::InvalidMint.into());
+    // native SOL can be sent from and paid out to plain wallets
+    let is_native = cmp_pubkeys(mint_info.key, &spl_token::native_mint::id());
+    let wrap_source = is_native && cmp_pubkeys(source_token_info.key, wallet_info.key);
+    let unwrap_destination =
+        is_native && cmp_pubkeys(destination_token_info.owner, &system_program::id());
+    if !wrap_source {
+        assert_owned_by(source_token_info, &spl_token::id())?;
+        let source_token: Account = assert_initialized(source_token_info)?;
+        if source_token.mint != *mint_info.key {
+            return Err(CardError::InvalidMint.into());
+        }
+        assert_token_owned_by(&source_token, wallet_info.key)?;
+    }
+    if !unwrap_destination {
+        assert_owned_by(destination_token_info, &spl_token::id())?;
+        let destination_token: Account = assert_initialized(destination_token_info)?;
+        if destination_token.mint != *mint_info.key {
+            return Err(CardError::InvalidMint.into());
+        }
     }
     assert_owned_by(collection_fee_token_info, &spl_token::id())?;
     let collection_fee_token: Account = assert_initialized(collection_fee_token_info)?;
@@ -56,28 +116,140 @@ pub fn init(program_id: &Pubkey, accounts: &[AccountInfo], args: WithdrawArgs) -
     }
 
     let fee_from_bps = calculate_fee(args.amount, args.fee_bps as u64)?;
-    let fee = fee_from_bps
+    let requested_fee = fee_from_bps
         .checked_add(args.fixed_fee)
         .ok_or::<ProgramError>(CardError::AmountOverflow.into())?;
-    if args.amount > 0 {
-        transfer(
-            false,
-            source_token_info,
-            destination_token_info,
-            wallet_info,
-            args.amount,
-            &[],
+    let fee = scheduled_fee(
+        load_fee_schedule(program_id, accounts, &mint_config)?.as_ref(),
+        &mint_config,
+        None,
+        FeeOperation::Withdrawal,
+        args.amount,
+        requested_fee,
+    )?;
+
+    if wrap_source || unwrap_destination {
+        let token_program_info = token_program_info?;
+        let program_authority_info = next_account_info(account_info_iter)?;
+        let wrap_info = next_account_info(account_info_iter)?;
+
+        assert_account_key(token_program_info, &spl_token::id(), None)?;
+
+        let authority_bump = load_config(program_id, accounts)?.authority_bump;
+        assert_program_authority(
+            program_id,
+            program_authority_info,
+            authority_bump,
+            CardError::InvalidVaultOwner,
         )?;
-    }
-    if fee > 0 {
-        transfer(
-            false,
-            source_token_info,
-            collection_fee_token_info,
-            wallet_info,
-            fee,
-            &[],
+        let (wrap_key, wrap_bump) = find_wrap_program_address(program_id, &args.key);
+        assert_account_key(wrap_info, &wrap_key, Some(CardError::InvalidWrapKey))?;
+        let authority_signer_seeds = [PREFIX.as_bytes(), program_id.as_ref(), &[authority_bump]];
+
+        create_wrapped_native_account(
+            wrap_info,
+            mint_info,
+            program_authority_info,
+            rent_info,
+            payer_info,
+            system_account_info,
+            &[
+                PREFIX.as_bytes(),
+                program_id.as_ref(),
+                args.key.as_ref(),
+                WRAP_PREFIX.as_bytes(),
+                &[wrap_bump],
+            ],
+        )?;
+
+        if wrap_source {
+            // lamports going to a wallet don't need wrapping
+            let wrapped = if unwrap_destination {
+                fee
+            } else {
+                args.amount
+                    .checked_add(fee)
+                    .ok_or::<ProgramError>(CardError::AmountOverflow.into())?
+            };
+            wrap_native(wallet_info, wrap_info, wrapped)?;
+        }
+
+        if args.amount > 0 {
+            if wrap_source && unwrap_destination {
+                native_transfer(wallet_info, destination_token_info, args.amount, &[])?;
+            } else if wrap_source {
+                transfer(
+                    false,
+                    wrap_info,
+                    destination_token_info,
+                    program_authority_info,
+                    args.amount,
+                    &[&authority_signer_seeds],
+                )?;
+            } else {
+                transfer(
+                    false,
+                    source_token_info,
+                    wrap_info,
+                    wallet_info,
+                    args.amount,
+                    &[],
+                )?;
+            }
+        }
+        if fee > 0 {
+            if wrap_source {
+                transfer(
+                    false,
+                    wrap_info,
+                    collection_fee_token_info,
+                    program_authority_info,
+                    fee,
+                    &[&authority_signer_seeds],
+                )?;
+            } else {
+                transfer(
+                    false,
+                    source_token_info,
+                    collection_fee_token_info,
+                    wallet_info,
+                    fee,
+                    &[],
+                )?;
+            }
+        }
+
+        // the payer gets its rent back, along with the unwrapped amount it forwards
+        close_token_account(
+            wrap_info,
+            payer_info,
+            program_authority_info,
+            &[&authority_signer_seeds],
         )?;
+        if unwrap_destination && !wrap_source && args.amount > 0 {
+            native_transfer(payer_info, destination_token_info, args.amount, &[])?;
+        }
+    } else {
+        if args.amount > 0 {
+            transfer(
+                false,
+                source_token_info,
+                destination_token_info,
+                wallet_info,
+                args.amount,
+                &[],
+            )?;
+        }
+        if fee > 0 {
+            transfer(
+                false,
+                source_token_info,
+                collection_fee_token_info,
+                wallet_info,
+                fee,
+                &[],
+            )?;
+        }
     }
 
     create_new_account_raw(
@@ -1,7 +1,14 @@
-pub mod withdraw;
+pub mod card;
+pub mod config;
 pub mod deposit;
 pub mod escrow;
+pub mod fee_schedule;
+pub mod mandate;
+pub mod merchant;
+pub mod mint_config;
+pub mod multisig;
+pub mod payout;
+pub mod pod;
+pub mod withdraw;
 
-
-
-pub const FLAG_ACCOUNT_SIZE: usize = 1;
\ No newline at end of file
+pub const FLAG_ACCOUNT_SIZE: usize = 1;
@@ -0,0 +1,200 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    clock::UnixTimestamp,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+pub const CARD_DATA_SIZE: usize = 159;
+pub const MAX_MCC_RANGES: usize = 8;
+const SECONDS_PER_DAY: UnixTimestamp = 24 * 60 * 60;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub enum CardStatus {
+    #[default]
+    Active,
+    Frozen,
+}
+
+/// Who acted on a card, the holder can't undo what the issuer did
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, BorshSerialize, BorshDeserialize, Default)]
+pub enum CardAuthority {
+    #[default]
+    None,
+    Holder,
+    Issuer,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub enum CategoryMode {
+    /// Any merchant category may be paid
+    #[default]
+    Any,
+    /// Only the listed merchant categories may be paid
+    Allow,
+    /// The listed merchant categories may not be paid
+    Deny,
+}
+
+/// Inclusive range of merchant category codes
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct MccRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+/// Merchant categories a card may be spent at
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct MerchantCategories {
+    pub mode: CategoryMode,
+    pub range_count: u8,
+    pub ranges: [MccRange; MAX_MCC_RANGES],
+}
+
+impl MerchantCategories {
+    pub fn ranges(&self) -> &[MccRange] {
+        &self.ranges[..(self.range_count as usize).min(MAX_MCC_RANGES)]
+    }
+
+    /// Whether a merchant of category `mcc` may be paid
+    pub fn allows(&self, mcc: u16) -> bool {
+        let listed = self
+            .ranges()
+            .iter()
+            .any(|range| range.start <= mcc && mcc <= range.end);
+        match self.mode {
+            CategoryMode::Any => true,
+            CategoryMode::Allow => listed,
+            CategoryMode::Deny => !listed,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.range_count as usize <= MAX_MCC_RANGES
+            && self.ranges().iter().all(|range| range.start <= range.end)
+    }
+}
+
+/// Spending limits of a card, `u64::MAX` for no limit
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct CardLimits {
+    pub per_transaction: u64,
+    pub daily: u64,
+    pub monthly: u64,
+}
+
+/// Card settings the admin may update
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct CardParams {
+    /// Token account the card is spent from, for native SOL the owner's wallet
+    pub funding_token: Pubkey,
+    pub limits: CardLimits,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Card {
+    pub is_initialized: bool,
+    pub owner: Pubkey,
+    pub status: CardStatus,
+    pub params: CardParams,
+    /// Day `daily_spent` counts, in days since the Unix epoch
+    pub day: i64,
+    pub daily_spent: u64,
+    /// Month `monthly_spent` counts, in months since January 1970
+    pub month: i64,
+    pub monthly_spent: u64,
+    pub frozen_by: CardAuthority,
+    pub categories: MerchantCategories,
+    /// The holder can't change categories set by the issuer
+    pub categories_set_by: CardAuthority,
+    /// Canonical bump of the card address
+    pub bump: u8,
+}
+
+impl Card {
+    pub const PREFIX: &'static str = "card";
+
+    /// Reset the counters of the days and months that are over at `now`
+    pub fn roll(&mut self, now: UnixTimestamp) {
+        let day = now.div_euclid(SECONDS_PER_DAY);
+        if day != self.day {
+            self.day = day;
+            self.daily_spent = 0;
+        }
+        let month = month_of_day(day);
+        if month != self.month {
+            self.month = month;
+            self.monthly_spent = 0;
+        }
+    }
+
+    /// Take `amount` charged at `charged_at` back off the counters still counting it at `now`
+    pub fn uncharge(&mut self, amount: u64, charged_at: UnixTimestamp, now: UnixTimestamp) {
+        self.roll(now);
+        let day = charged_at.div_euclid(SECONDS_PER_DAY);
+        if day == self.day {
+            self.daily_spent = self.daily_spent.saturating_sub(amount);
+        }
+        if month_of_day(day) == self.month {
+            self.monthly_spent = self.monthly_spent.saturating_sub(amount);
+        }
+    }
+}
+
+/// Calendar month of `day`, counted in months since January 1970
+fn month_of_day(day: i64) -> i64 {
+    // civil_from_days, from http://howardhinnant.github.io/date_algorithms.html
+    let z = day + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year - 1970) * 12 + month - 1
+}
+
+impl IsInitialized for Card {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Card {}
+
+impl Pack for Card {
+    const LEN: usize = CARD_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
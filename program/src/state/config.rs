@@ -0,0 +1,123 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use crate::error::CardError;
+
+pub const CONFIG_DATA_SIZE: usize = 203;
+
+/// Bits of `Config::paused_flags`, canceling can't be paused so users can always be refunded
+pub const PAUSE_INIT_DEPOSIT: u64 = 1 << 0;
+pub const PAUSE_INIT_WITHDRAWAL: u64 = 1 << 1;
+pub const PAUSE_INIT_ESCROW: u64 = 1 << 2;
+pub const PAUSE_SETTLE: u64 = 1 << 3;
+pub const PAUSE_CLOSE: u64 = 1 << 4;
+pub const PAUSE_CHARGE_MANDATE: u64 = 1 << 5;
+pub const PAUSE_ALL: u64 = PAUSE_INIT_DEPOSIT
+    | PAUSE_INIT_WITHDRAWAL
+    | PAUSE_INIT_ESCROW
+    | PAUSE_SETTLE
+    | PAUSE_CLOSE
+    | PAUSE_CHARGE_MANDATE;
+
+/// Keys holding each role, a key may be the multisig approval authority
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Roles {
+    pub settler: Pubkey,
+    pub canceler: Pubkey,
+    pub deposit_approver: Pubkey,
+    pub withdrawal_approver: Pubkey,
+    pub rent_closer: Pubkey,
+    pub config_admin: Pubkey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    Settler,
+    Canceler,
+    DepositApprover,
+    WithdrawalApprover,
+    RentCloser,
+    ConfigAdmin,
+}
+
+impl Role {
+    /// Error returned when the role didn't approve the instruction
+    pub fn missing_error(self) -> CardError {
+        match self {
+            Role::Settler => CardError::MissingSettlerRole,
+            Role::Canceler => CardError::MissingCancelerRole,
+            Role::DepositApprover => CardError::MissingDepositApproverRole,
+            Role::WithdrawalApprover => CardError::MissingWithdrawalApproverRole,
+            Role::RentCloser => CardError::MissingRentCloserRole,
+            Role::ConfigAdmin => CardError::MissingConfigAdminRole,
+        }
+    }
+}
+
+impl Roles {
+    pub fn key(&self, role: Role) -> &Pubkey {
+        match role {
+            Role::Settler => &self.settler,
+            Role::Canceler => &self.canceler,
+            Role::DepositApprover => &self.deposit_approver,
+            Role::WithdrawalApprover => &self.withdrawal_approver,
+            Role::RentCloser => &self.rent_closer,
+            Role::ConfigAdmin => &self.config_admin,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Config {
+    pub is_initialized: bool,
+    pub roles: Roles,
+    pub paused_flags: u64,
+    /// Canonical bump of the config address
+    pub bump: u8,
+    /// Canonical bump of the program authority
+    pub authority_bump: u8,
+}
+
+impl Config {
+    pub const PREFIX: &'static str = "config";
+
+    pub fn is_paused(&self, flag: u64) -> bool {
+        self.paused_flags & flag != 0
+    }
+}
+
+impl IsInitialized for Config {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Config {}
+
+impl Pack for Config {
+    const LEN: usize = CONFIG_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
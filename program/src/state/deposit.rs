@@ -8,6 +8,10 @@ use solana_program::{
 
 use super::FLAG_ACCOUNT_SIZE;
 
+/// Deposits of a batch fitting a legacy transaction signed by the source owner,
+/// the approver and the fee payer
+pub const MAX_BATCH_DEPOSITS: usize = 6;
+
 #[repr(C)]
 #[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
 pub struct Deposit {
@@ -35,8 +39,7 @@ impl Pack for Deposit {
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() != Self::LEN
-        {
+        if src.len() != Self::LEN {
             msg!("Failed to deserialize");
             return Err(ProgramError::InvalidAccountData);
         }
@@ -46,4 +49,3 @@ impl Pack for Deposit {
         Ok(result)
     }
 }
-
@@ -1,12 +1,116 @@
+use std::convert::TryFrom;
+
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{bytes_of, try_from_bytes, try_from_bytes_mut, Pod, Zeroable};
 use solana_program::{
-    borsh0_10::try_from_slice_unchecked,
+    clock::UnixTimestamp,
     msg,
     program_error::ProgramError,
-    program_pack::{IsInitialized, Pack, Sealed}, pubkey::Pubkey,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
 };
 
-pub const ESCROW_DATA_SIZE: usize = 213;
+use super::pod::{PodBool, PodI64, PodU16, PodU64};
+
+pub const ESCROW_DATA_SIZE: usize = 505;
+/// Size of the escrow layout of the first release
+pub const ESCROW_LEGACY_DATA_SIZE: usize = 213;
+pub const MAX_PAYEES: usize = 4;
+/// Settled escrows can be disputed for 120 days, as on card rails
+pub const DISPUTE_WINDOW: UnixTimestamp = 120 * 24 * 60 * 60;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub enum DisputeStatus {
+    #[default]
+    None,
+    Disputed,
+    /// Resolved in favour of the merchant
+    Released,
+    /// Resolved in favour of the payer
+    Refunded,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub enum ShareKind {
+    /// Basis points of what is left once the absolute shares are paid
+    #[default]
+    Bps,
+    /// Absolute amount
+    Amount,
+}
+
+/// Token account paid a share of the escrow amount, for native SOL a wallet
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Payee {
+    pub token: Pubkey,
+    pub kind: ShareKind,
+    pub share: u64,
+}
+
+/// Payees the escrow amount is split between on settlement
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Split {
+    pub payee_count: u8,
+    pub payees: [Payee; MAX_PAYEES],
+    /// Index of the payee receiving the rounding dust
+    pub dust_payee: u8,
+}
+
+impl Split {
+    pub fn payees(&self) -> &[Payee] {
+        &self.payees[..(self.payee_count as usize).min(MAX_PAYEES)]
+    }
+
+    /// Amount each payee is paid out of `amount`, absolute shares first
+    pub fn shares(&self, amount: u64) -> Option<[u64; MAX_PAYEES]> {
+        let payees = self.payees();
+        let absolute = payees
+            .iter()
+            .filter(|payee| payee.kind == ShareKind::Amount)
+            .try_fold(0u64, |sum, payee| sum.checked_add(payee.share))?;
+        let rest = amount.checked_sub(absolute)?;
+        let mut shares = [0u64; MAX_PAYEES];
+        let mut paid = 0u64;
+        for (share, payee) in shares.iter_mut().zip(payees) {
+            *share = match payee.kind {
+                ShareKind::Amount => payee.share,
+                ShareKind::Bps => rest.checked_mul(payee.share)?.checked_div(10000)?,
+            };
+            paid = paid.checked_add(*share)?;
+        }
+        let dust = shares.get_mut(self.dust_payee as usize)?;
+        *dust = dust.checked_add(amount.checked_sub(paid)?)?;
+        Some(shares)
+    }
+
+    /// Whether the shares add up to exactly `amount`
+    pub fn is_valid(&self, amount: u64) -> bool {
+        let payees = self.payees();
+        let bps: u64 = payees
+            .iter()
+            .filter(|payee| payee.kind == ShareKind::Bps)
+            .map(|payee| payee.share.min(u16::MAX as u64))
+            .sum();
+        let has_bps = payees.iter().any(|payee| payee.kind == ShareKind::Bps);
+        let absolute = payees
+            .iter()
+            .filter(|payee| payee.kind == ShareKind::Amount)
+            .try_fold(0u64, |sum, payee| sum.checked_add(payee.share));
+        self.payee_count as usize <= MAX_PAYEES
+            && !payees.is_empty()
+            && (self.dust_payee as usize) < payees.len()
+            && if has_bps {
+                bps == 10000
+            } else {
+                absolute == Some(amount)
+            }
+            && self.shares(amount).is_some()
+    }
+}
 
 #[repr(C)]
 #[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
@@ -23,10 +127,37 @@ pub struct Escrow {
     pub fee_token: Pubkey,
     pub mint: Pubkey,
     pub reference: Pubkey,
+    /// Fee charged on settlement, resolved against the fee schedule on init
+    pub fee: u64,
+    /// Id of the merchant paid on settlement, the default key when paying the deposit collection
+    pub merchant: Pubkey,
+    /// Payees paid on settlement instead of the destination, none when paying the destination
+    pub split: Split,
+    /// Amount returned to the source since settlement
+    pub refunded_amount: u64,
+    /// Fee returned to the source since settlement
+    pub refunded_fee: u64,
+    pub dispute_status: DisputeStatus,
+    pub settled_at: UnixTimestamp,
+    pub disputed_at: UnixTimestamp,
+    pub dispute_reason: u16,
+    pub resolved_at: UnixTimestamp,
+    /// Whether the funds stay in the source, the program authority being its delegate
+    /// for the amount and fee, the vault then being the source
+    pub is_hold: bool,
+    /// Canonical bump of the program authority
+    pub authority_bump: u8,
+    /// Canonical bump of the native SOL vault, zero for an SPL token vault
+    pub vault_bump: u8,
+    /// Card the escrow amount was counted against, the default key when none was
+    pub card: Pubkey,
+    /// When the card was charged
+    pub charged_at: UnixTimestamp,
 }
 
 impl Escrow {
     pub const PREFIX: &'static str = "escrow";
+    pub const VAULT_PREFIX: &'static str = "vault";
 }
 
 impl IsInitialized for Escrow {
@@ -41,20 +172,244 @@ impl Pack for Escrow {
     const LEN: usize = ESCROW_DATA_SIZE;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut slice = dst;
-        self.serialize(&mut slice).unwrap()
+        dst.copy_from_slice(bytes_of(&PodEscrow::from(self)))
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() != Self::LEN
-        {
-            msg!("Failed to deserialize");
-            return Err(ProgramError::InvalidAccountData);
+        Escrow::try_from(PodEscrow::load_unchecked(src)?)
+    }
+}
+
+impl TryFrom<u8> for DisputeStatus {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(DisputeStatus::None),
+            1 => Ok(DisputeStatus::Disputed),
+            2 => Ok(DisputeStatus::Released),
+            3 => Ok(DisputeStatus::Refunded),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+impl TryFrom<u8> for ShareKind {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ShareKind::Bps),
+            1 => Ok(ShareKind::Amount),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+/// `Payee` laid out in place
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+pub struct PodPayee {
+    pub token: Pubkey,
+    pub kind: u8,
+    pub share: PodU64,
+}
+
+/// `Split` laid out in place
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+pub struct PodSplit {
+    pub payee_count: u8,
+    pub payees: [PodPayee; MAX_PAYEES],
+    pub dust_payee: u8,
+}
+
+impl From<&Split> for PodSplit {
+    fn from(split: &Split) -> Self {
+        let mut payees = [PodPayee::default(); MAX_PAYEES];
+        for (pod, payee) in payees.iter_mut().zip(split.payees.iter()) {
+            *pod = PodPayee {
+                token: payee.token,
+                kind: payee.kind as u8,
+                share: payee.share.into(),
+            };
+        }
+        PodSplit {
+            payee_count: split.payee_count,
+            payees,
+            dust_payee: split.dust_payee,
+        }
+    }
+}
+
+impl TryFrom<&PodSplit> for Split {
+    type Error = ProgramError;
+
+    fn try_from(pod: &PodSplit) -> Result<Self, Self::Error> {
+        let mut payees = [Payee::default(); MAX_PAYEES];
+        for (payee, pod) in payees.iter_mut().zip(pod.payees.iter()) {
+            *payee = Payee {
+                token: pod.token,
+                kind: ShareKind::try_from(pod.kind)?,
+                share: pod.share.into(),
+            };
+        }
+        Ok(Split {
+            payee_count: pod.payee_count,
+            payees,
+            dust_payee: pod.dust_payee,
+        })
+    }
+}
+
+/// Zero-copy view of the data of an escrow account, laid out byte for byte like
+/// the Borsh encoding of `Escrow`, so processors read and update fields in place
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+pub struct PodEscrow {
+    pub is_initialized: PodBool,
+    pub is_settled: PodBool,
+    pub is_canceled: PodBool,
+    pub amount: PodU64,
+    pub fee_bps: PodU16,
+    pub fixed_fee: PodU64,
+    pub src_token: Pubkey,
+    pub dst_token: Pubkey,
+    pub vault_token: Pubkey,
+    pub fee_token: Pubkey,
+    pub mint: Pubkey,
+    pub reference: Pubkey,
+    pub fee: PodU64,
+    pub merchant: Pubkey,
+    pub split: PodSplit,
+    pub refunded_amount: PodU64,
+    pub refunded_fee: PodU64,
+    pub dispute_status: u8,
+    pub settled_at: PodI64,
+    pub disputed_at: PodI64,
+    pub dispute_reason: PodU16,
+    pub resolved_at: PodI64,
+    pub is_hold: PodBool,
+    pub authority_bump: u8,
+    pub vault_bump: u8,
+    pub card: Pubkey,
+    pub charged_at: PodI64,
+}
+
+impl PodEscrow {
+    /// View of initialized escrow `data`
+    pub fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        let escrow = Self::load_unchecked(data)?;
+        if !escrow.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(escrow)
+    }
+
+    /// Mutable view of initialized escrow `data`
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        let escrow = Self::load_mut_unchecked(data)?;
+        if !escrow.is_initialized() {
+            return Err(ProgramError::UninitializedAccount);
         }
+        Ok(escrow)
+    }
 
-        let result: Self = try_from_slice_unchecked(src)?;
+    pub fn load_unchecked(data: &[u8]) -> Result<&Self, ProgramError> {
+        try_from_bytes(data).map_err(|_| {
+            msg!("Failed to deserialize");
+            ProgramError::InvalidAccountData
+        })
+    }
 
-        Ok(result)
+    pub fn load_mut_unchecked(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        try_from_bytes_mut(data).map_err(|_| {
+            msg!("Failed to deserialize");
+            ProgramError::InvalidAccountData
+        })
+    }
+
+    pub fn dispute_status(&self) -> Result<DisputeStatus, ProgramError> {
+        DisputeStatus::try_from(self.dispute_status)
+    }
+
+    pub fn split(&self) -> Result<Split, ProgramError> {
+        Split::try_from(&self.split)
     }
 }
 
+impl IsInitialized for PodEscrow {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized.into()
+    }
+}
+
+impl From<&Escrow> for PodEscrow {
+    fn from(escrow: &Escrow) -> Self {
+        PodEscrow {
+            is_initialized: escrow.is_initialized.into(),
+            is_settled: escrow.is_settled.into(),
+            is_canceled: escrow.is_canceled.into(),
+            amount: escrow.amount.into(),
+            fee_bps: escrow.fee_bps.into(),
+            fixed_fee: escrow.fixed_fee.into(),
+            src_token: escrow.src_token,
+            dst_token: escrow.dst_token,
+            vault_token: escrow.vault_token,
+            fee_token: escrow.fee_token,
+            mint: escrow.mint,
+            reference: escrow.reference,
+            fee: escrow.fee.into(),
+            merchant: escrow.merchant,
+            split: PodSplit::from(&escrow.split),
+            refunded_amount: escrow.refunded_amount.into(),
+            refunded_fee: escrow.refunded_fee.into(),
+            dispute_status: escrow.dispute_status as u8,
+            settled_at: escrow.settled_at.into(),
+            disputed_at: escrow.disputed_at.into(),
+            dispute_reason: escrow.dispute_reason.into(),
+            resolved_at: escrow.resolved_at.into(),
+            is_hold: escrow.is_hold.into(),
+            authority_bump: escrow.authority_bump,
+            vault_bump: escrow.vault_bump,
+            card: escrow.card,
+            charged_at: escrow.charged_at.into(),
+        }
+    }
+}
+
+impl TryFrom<&PodEscrow> for Escrow {
+    type Error = ProgramError;
+
+    fn try_from(pod: &PodEscrow) -> Result<Self, Self::Error> {
+        Ok(Escrow {
+            is_initialized: pod.is_initialized.into(),
+            is_settled: pod.is_settled.into(),
+            is_canceled: pod.is_canceled.into(),
+            amount: pod.amount.into(),
+            fee_bps: pod.fee_bps.into(),
+            fixed_fee: pod.fixed_fee.into(),
+            src_token: pod.src_token,
+            dst_token: pod.dst_token,
+            vault_token: pod.vault_token,
+            fee_token: pod.fee_token,
+            mint: pod.mint,
+            reference: pod.reference,
+            fee: pod.fee.into(),
+            merchant: pod.merchant,
+            split: pod.split()?,
+            refunded_amount: pod.refunded_amount.into(),
+            refunded_fee: pod.refunded_fee.into(),
+            dispute_status: pod.dispute_status()?,
+            settled_at: pod.settled_at.into(),
+            disputed_at: pod.disputed_at.into(),
+            dispute_reason: pod.dispute_reason.into(),
+            resolved_at: pod.resolved_at.into(),
+            is_hold: pod.is_hold.into(),
+            authority_bump: pod.authority_bump,
+            vault_bump: pod.vault_bump,
+            card: pod.card,
+            charged_at: pod.charged_at.into(),
+        })
+    }
+}
@@ -0,0 +1,134 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use crate::{error::CardError, utils::calculate_fee};
+
+pub const MAX_FEE_TIERS: usize = 4;
+pub const FEE_SCHEDULE_DATA_SIZE: usize = 229;
+
+/// Fee bps charged from `min_amount` on
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct FeeTier {
+    pub min_amount: u64,
+    pub fee_bps: u16,
+}
+
+/// Fee of one operation type, tiers are sorted by ascending `min_amount`
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct FeeRule {
+    pub tier_count: u8,
+    pub tiers: [FeeTier; MAX_FEE_TIERS],
+    pub fixed_fee: u64,
+    pub min_fee: u64,
+    pub max_fee: u64,
+}
+
+impl FeeRule {
+    pub fn tiers(&self) -> &[FeeTier] {
+        &self.tiers[..(self.tier_count as usize).min(MAX_FEE_TIERS)]
+    }
+
+    /// Fee of the highest tier `amount` reaches plus the fixed fee, within the min and max fee
+    pub fn calculate_fee(&self, amount: u64) -> Result<u64, ProgramError> {
+        let fee_bps = self
+            .tiers()
+            .iter()
+            .rev()
+            .find(|tier| amount >= tier.min_amount)
+            .map_or(0, |tier| tier.fee_bps);
+        let fee = calculate_fee(amount, fee_bps as u64)?
+            .checked_add(self.fixed_fee)
+            .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+        Ok(fee.clamp(self.min_fee, self.max_fee))
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.tier_count as usize <= MAX_FEE_TIERS
+            && self.min_fee <= self.max_fee
+            && self.tiers().iter().all(|tier| tier.fee_bps <= 10000)
+            && self
+                .tiers()
+                .windows(2)
+                .all(|pair| pair[0].min_amount < pair[1].min_amount)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeeOperation {
+    Deposit,
+    Withdrawal,
+    Escrow,
+}
+
+/// Fee rules of a mint
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct FeeRules {
+    /// Whether callers may ask for a fee lower than the scheduled one
+    pub allow_override: bool,
+    pub deposit: FeeRule,
+    pub withdrawal: FeeRule,
+    pub escrow: FeeRule,
+}
+
+impl FeeRules {
+    pub fn rule(&self, operation: FeeOperation) -> &FeeRule {
+        match operation {
+            FeeOperation::Deposit => &self.deposit,
+            FeeOperation::Withdrawal => &self.withdrawal,
+            FeeOperation::Escrow => &self.escrow,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.deposit.is_valid() && self.withdrawal.is_valid() && self.escrow.is_valid()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct FeeSchedule {
+    pub is_initialized: bool,
+    pub mint: Pubkey,
+    pub rules: FeeRules,
+}
+
+impl FeeSchedule {
+    pub const PREFIX: &'static str = "fees";
+}
+
+impl IsInitialized for FeeSchedule {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for FeeSchedule {}
+
+impl Pack for FeeSchedule {
+    const LEN: usize = FEE_SCHEDULE_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
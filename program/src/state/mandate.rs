@@ -0,0 +1,95 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    clock::UnixTimestamp,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+pub const MANDATE_DATA_SIZE: usize = 186;
+
+/// Terms of a mandate, as signed by its owner
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct MandateTerms {
+    /// Most charged in a single period
+    pub max_amount: u64,
+    /// Length of a period in seconds
+    pub period: i64,
+    pub start: UnixTimestamp,
+    /// No more charges from then on, 0 for no end
+    pub end: UnixTimestamp,
+}
+
+impl MandateTerms {
+    pub fn is_valid(&self) -> bool {
+        self.max_amount > 0 && self.period > 0 && (self.end == 0 || self.end > self.start)
+    }
+
+    /// Period `now` falls in, counted from the start, none outside of the mandate
+    pub fn period_at(&self, now: UnixTimestamp) -> Option<i64> {
+        if now < self.start || (self.end != 0 && now >= self.end) {
+            return None;
+        }
+        (now - self.start).checked_div(self.period)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Mandate {
+    pub is_initialized: bool,
+    pub owner: Pubkey,
+    /// Id of the merchant charging the mandate
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    /// Token account charged, delegated to the mandate delegate by the owner
+    pub source_token: Pubkey,
+    pub terms: MandateTerms,
+    pub charge_count: u64,
+    pub last_charged_at: UnixTimestamp,
+    pub total_charged: u64,
+    /// Canonical bump of the mandate delegate
+    pub delegate_bump: u8,
+}
+
+impl Mandate {
+    pub const PREFIX: &'static str = "mandate";
+    pub const DELEGATE_PREFIX: &'static str = "delegate";
+
+    /// Whether the mandate may be charged at `now`, once per period
+    pub fn is_chargeable(&self, now: UnixTimestamp) -> Option<bool> {
+        let period = self.terms.period_at(now)?;
+        Some(self.charge_count == 0 || self.terms.period_at(self.last_charged_at) != Some(period))
+    }
+}
+
+impl IsInitialized for Mandate {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Mandate {}
+
+impl Pack for Mandate {
+    const LEN: usize = MANDATE_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,110 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+pub const MAX_MERCHANT_MINTS: usize = 4;
+pub const MERCHANT_DATA_SIZE: usize = 305;
+
+/// Token account a merchant is paid in for a mint, for native SOL a wallet
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Settlement {
+    pub mint: Pubkey,
+    pub token: Pubkey,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub enum MerchantStatus {
+    #[default]
+    Active,
+    Suspended,
+}
+
+/// Merchant settings the admin may update
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct MerchantParams {
+    pub settlement_count: u8,
+    pub settlements: [Settlement; MAX_MERCHANT_MINTS],
+    /// Whether escrows of the merchant are charged the fee below rather than the mint's
+    pub has_fee_override: bool,
+    pub fee_bps: u16,
+    pub fixed_fee: u64,
+    /// Merchant category code mandates of the merchant are charged under
+    pub mcc: u16,
+}
+
+impl MerchantParams {
+    pub fn settlements(&self) -> &[Settlement] {
+        &self.settlements[..(self.settlement_count as usize).min(MAX_MERCHANT_MINTS)]
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let settlements = self.settlements();
+        self.settlement_count as usize <= MAX_MERCHANT_MINTS
+            && self.fee_bps <= 10000
+            && settlements.iter().enumerate().all(|(i, settlement)| {
+                settlements[..i]
+                    .iter()
+                    .all(|other| other.mint != settlement.mint)
+            })
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Merchant {
+    pub is_initialized: bool,
+    pub id: Pubkey,
+    pub status: MerchantStatus,
+    pub params: MerchantParams,
+    /// Canonical bump of the merchant address
+    pub bump: u8,
+}
+
+impl Merchant {
+    pub const PREFIX: &'static str = "merchant";
+
+    /// Account the merchant is paid in for `mint`
+    pub fn settlement(&self, mint: &Pubkey) -> Option<&Pubkey> {
+        self.params
+            .settlements()
+            .iter()
+            .find(|settlement| settlement.mint == *mint)
+            .map(|settlement| &settlement.token)
+    }
+}
+
+impl IsInitialized for Merchant {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Merchant {}
+
+impl Pack for Merchant {
+    const LEN: usize = MERCHANT_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,72 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+pub const MINT_CONFIG_DATA_SIZE: usize = 127;
+
+/// Parameters of a supported mint
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct MintParams {
+    pub enabled: bool,
+    pub min_amount: u64,
+    pub max_amount: u64,
+    /// Fee charged while the mint has no fee schedule
+    pub fee_bps: u16,
+    pub fixed_fee: u64,
+    /// Account receiving the deposited and settled amounts, for native SOL escrows the deposit wallet
+    pub deposit_collection: Pubkey,
+    /// Account receiving the fees, for native SOL escrows the fee wallet
+    pub fee_collection: Pubkey,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct MintConfig {
+    pub is_initialized: bool,
+    pub mint: Pubkey,
+    pub params: MintParams,
+    /// Whether the mint has a fee schedule, which instructions charging a fee then need
+    pub has_fee_schedule: bool,
+    /// Bump of the fee schedule address, zero without a schedule
+    pub fee_schedule_bump: u8,
+    /// Canonical bump of the mint config address
+    pub bump: u8,
+}
+
+impl MintConfig {
+    pub const PREFIX: &'static str = "mint";
+}
+
+impl IsInitialized for MintConfig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for MintConfig {}
+
+impl Pack for MintConfig {
+    const LEN: usize = MINT_CONFIG_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
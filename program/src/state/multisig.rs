@@ -0,0 +1,58 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+pub const MAX_SIGNERS: usize = 11;
+pub const MULTISIG_DATA_SIZE: usize = 355;
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct Multisig {
+    pub is_initialized: bool,
+    /// Number of signers required
+    pub threshold: u8,
+    /// Number of valid signers
+    pub signer_count: u8,
+    pub signers: [Pubkey; MAX_SIGNERS],
+}
+
+impl Multisig {
+    pub const PREFIX: &'static str = "multisig";
+
+    pub fn signers(&self) -> &[Pubkey] {
+        &self.signers[..(self.signer_count as usize).min(MAX_SIGNERS)]
+    }
+}
+
+impl IsInitialized for Multisig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for Multisig {}
+
+impl Pack for Multisig {
+    const LEN: usize = MULTISIG_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
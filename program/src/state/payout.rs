@@ -0,0 +1,72 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    borsh0_10::try_from_slice_unchecked,
+    clock::UnixTimestamp,
+    msg,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+pub const SCHEDULED_PAYOUT_DATA_SIZE: usize = 211;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub enum PayoutStatus {
+    #[default]
+    Pending,
+    Executed,
+    Canceled,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Default)]
+pub struct ScheduledPayout {
+    pub is_initialized: bool,
+    pub status: PayoutStatus,
+    pub key: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Token account funding the payout, refunded when it is canceled
+    pub source_token: Pubkey,
+    /// Token account of the program authority holding the amount until unlock
+    pub vault_token: Pubkey,
+    pub beneficiary_token: Pubkey,
+    pub unlock_at: UnixTimestamp,
+    /// Canonical bump of the program authority
+    pub authority_bump: u8,
+    /// Paid the rent of the account and gets it back once the payout is executed or canceled
+    pub rent_payer: Pubkey,
+}
+
+impl ScheduledPayout {
+    pub const PREFIX: &'static str = "payout";
+}
+
+impl IsInitialized for ScheduledPayout {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Sealed for ScheduledPayout {}
+
+impl Pack for ScheduledPayout {
+    const LEN: usize = SCHEDULED_PAYOUT_DATA_SIZE;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            msg!("Failed to deserialize");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let result: Self = try_from_slice_unchecked(src)?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,44 @@
+//! Plain old data types of alignment 1, laid out like their Borsh encoding so
+//! that account data can be viewed and updated in place
+
+use bytemuck::{Pod, Zeroable};
+
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+pub struct PodBool(u8);
+
+impl From<bool> for PodBool {
+    fn from(value: bool) -> Self {
+        PodBool(value as u8)
+    }
+}
+
+impl From<PodBool> for bool {
+    fn from(value: PodBool) -> Self {
+        value.0 != 0
+    }
+}
+
+macro_rules! pod_int {
+    ($name:ident, $int:ty, $size:expr) => {
+        #[repr(transparent)]
+        #[derive(Debug, Clone, Copy, PartialEq, Default, Pod, Zeroable)]
+        pub struct $name([u8; $size]);
+
+        impl From<$int> for $name {
+            fn from(value: $int) -> Self {
+                $name(value.to_le_bytes())
+            }
+        }
+
+        impl From<$name> for $int {
+            fn from(value: $name) -> Self {
+                <$int>::from_le_bytes(value.0)
+            }
+        }
+    };
+}
+
+pod_int!(PodU16, u16, 2);
+pod_int!(PodU64, u64, 8);
+pod_int!(PodI64, i64, 8);
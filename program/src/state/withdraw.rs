@@ -35,8 +35,7 @@ impl Pack for Withdraw {
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() != Self::LEN
-        {
+        if src.len() != Self::LEN {
             msg!("Failed to deserialize");
             return Err(ProgramError::InvalidAccountData);
         }
@@ -46,4 +45,3 @@ impl Pack for Withdraw {
         Ok(result)
     }
 }
-
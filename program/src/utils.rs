@@ -2,18 +2,45 @@
 
 use std::convert::TryInto;
 
-use crate::error::CardError;
+use crate::{
+    create_card_program_address, create_config_program_address,
+    create_fee_schedule_program_address, create_merchant_program_address,
+    create_mint_config_program_address, create_program_authority,
+    error::CardError,
+    find_multisig_program_address, find_program_data_address,
+    instruction::{Authorization, AuthorizationAction},
+    state::{
+        card::{Card, CardStatus, CategoryMode},
+        config::{Config, Role},
+        fee_schedule::{FeeOperation, FeeSchedule},
+        merchant::{Merchant, MerchantStatus},
+        mint_config::MintConfig,
+        multisig::Multisig,
+    },
+};
+use borsh::BorshDeserialize;
 use solana_program::{
     account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::{Clock, UnixTimestamp},
+    ed25519_program,
     entrypoint::ProgramResult,
-    msg,
+    hash::Hash,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
     program_memory::sol_memcmp,
     program_pack::{IsInitialized, Pack},
     pubkey::{Pubkey, PUBKEY_BYTES},
     system_instruction,
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::{
+        self,
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        rent::Rent,
+        Sysvar,
+    },
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
 };
 use spl_token::state::Account;
 
@@ -35,6 +62,422 @@ pub fn assert_signer(account: &AccountInfo) -> ProgramResult {
     Err(ProgramError::MissingRequiredSignature)
 }
 
+/// Assert the authority is the upgrade authority recorded in the program data account
+pub fn assert_upgrade_authority(
+    program_id: &Pubkey,
+    program_data_info: &AccountInfo,
+    authority_info: &AccountInfo,
+) -> ProgramResult {
+    assert_account_key(
+        program_data_info,
+        &find_program_data_address(program_id),
+        Some(CardError::InvalidAuthorityId),
+    )?;
+    assert_owned_by(program_data_info, &bpf_loader_upgradeable::id())?;
+    // bincode encoding of `UpgradeableLoaderState::ProgramData`: the variant index,
+    // the slot of the last deployment then the optional upgrade authority
+    let data = program_data_info.try_borrow_data()?;
+    let metadata = data
+        .get(..UpgradeableLoaderState::size_of_programdata_metadata())
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let (variant, authority) = (&metadata[..4], &metadata[12..]);
+    if variant != 3u32.to_le_bytes() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if authority[0] != 1
+        || sol_memcmp(&authority[1..], authority_info.key.as_ref(), PUBKEY_BYTES) != 0
+    {
+        return Err(CardError::InvalidAuthorityId.into());
+    }
+    Ok(())
+}
+
+/// Load the config, passed anywhere among `accounts`
+pub fn load_config(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<Config, ProgramError> {
+    // the config is the program account recreated from the canonical bump it stores
+    accounts
+        .iter()
+        .filter(|info| cmp_pubkeys(info.owner, program_id) && info.data_len() == Config::LEN)
+        .find_map(|info| {
+            let config = Config::unpack(&info.data.borrow()).ok()?;
+            let config_key = create_config_program_address(program_id, config.bump).ok()?;
+            cmp_pubkeys(info.key, &config_key).then_some(config)
+        })
+        .ok_or::<ProgramError>(CardError::InvalidConfig.into())
+}
+
+/// Load the config of `mint`, passed anywhere among `accounts`, failing unless the
+/// mint is enabled and `amount` within its limits
+pub fn assert_mint_supported(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: &Pubkey,
+    amount: u64,
+) -> Result<MintConfig, ProgramError> {
+    // the mint config is the program account of the mint recreated from the canonical
+    // bump it stores, a mint never listed has none
+    let mint_config = accounts
+        .iter()
+        .filter(|info| cmp_pubkeys(info.owner, program_id) && info.data_len() == MintConfig::LEN)
+        .find_map(|info| {
+            let mint_config = MintConfig::unpack(&info.data.borrow()).ok()?;
+            if !cmp_pubkeys(&mint_config.mint, mint) {
+                return None;
+            }
+            let mint_config_key =
+                create_mint_config_program_address(program_id, mint, mint_config.bump).ok()?;
+            cmp_pubkeys(info.key, &mint_config_key).then_some(mint_config)
+        })
+        .ok_or::<ProgramError>(CardError::MintNotSupported.into())?;
+    if !mint_config.params.enabled {
+        return Err(CardError::MintNotSupported.into());
+    }
+    if amount < mint_config.params.min_amount || amount > mint_config.params.max_amount {
+        return Err(CardError::AmountOutOfRange.into());
+    }
+    Ok(mint_config)
+}
+
+/// Load the fee schedule of the mint of `mint_config`, passed anywhere among
+/// `accounts`, none when the mint has no schedule
+pub fn load_fee_schedule(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint_config: &MintConfig,
+) -> Result<Option<FeeSchedule>, ProgramError> {
+    if !mint_config.has_fee_schedule {
+        return Ok(None);
+    }
+    let fee_schedule_key = create_fee_schedule_program_address(
+        program_id,
+        &mint_config.mint,
+        mint_config.fee_schedule_bump,
+    )
+    .map_err(|_| CardError::InvalidFeeSchedule)?;
+    let fee_schedule_info = accounts
+        .iter()
+        .find(|info| cmp_pubkeys(info.key, &fee_schedule_key))
+        .ok_or::<ProgramError>(CardError::InvalidFeeSchedule.into())?;
+    assert_owned_by(fee_schedule_info, program_id)?;
+    Ok(Some(assert_initialized(fee_schedule_info)?))
+}
+
+/// Fee of `operation` on `amount` from the mint's fee schedule, or from the
+/// mint's default fee when it has none, unless the merchant overrides it. The
+/// caller's `requested` fee is charged instead when lower and the schedule
+/// allows it
+pub fn scheduled_fee(
+    fee_schedule: Option<&FeeSchedule>,
+    mint_config: &MintConfig,
+    merchant: Option<&Merchant>,
+    operation: FeeOperation,
+    amount: u64,
+    requested: u64,
+) -> Result<u64, ProgramError> {
+    let (fee, allow_override) = match fee_schedule {
+        Some(fee_schedule) => (
+            fee_schedule.rules.rule(operation).calculate_fee(amount)?,
+            fee_schedule.rules.allow_override,
+        ),
+        None => (
+            calculate_fee(amount, mint_config.params.fee_bps as u64)?
+                .checked_add(mint_config.params.fixed_fee)
+                .ok_or::<ProgramError>(CardError::MathOverflow.into())?,
+            false,
+        ),
+    };
+    let fee = match merchant {
+        Some(merchant) if merchant.params.has_fee_override => {
+            calculate_fee(amount, merchant.params.fee_bps as u64)?
+                .checked_add(merchant.params.fixed_fee)
+                .ok_or::<ProgramError>(CardError::MathOverflow.into())?
+        }
+        _ => fee,
+    };
+    if allow_override {
+        Ok(fee.min(requested))
+    } else {
+        Ok(fee)
+    }
+}
+
+/// Load the active merchant `id`, passed anywhere among `accounts`
+pub fn load_merchant(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    id: &Pubkey,
+) -> Result<Merchant, ProgramError> {
+    // the merchant is the program account of `id` recreated from the canonical bump it stores
+    let merchant = accounts
+        .iter()
+        .filter(|info| cmp_pubkeys(info.owner, program_id) && info.data_len() == Merchant::LEN)
+        .find_map(|info| {
+            let merchant = Merchant::unpack(&info.data.borrow()).ok()?;
+            if !cmp_pubkeys(&merchant.id, id) {
+                return None;
+            }
+            let merchant_key =
+                create_merchant_program_address(program_id, id, merchant.bump).ok()?;
+            cmp_pubkeys(info.key, &merchant_key).then_some(merchant)
+        })
+        .ok_or::<ProgramError>(CardError::InvalidMerchant.into())?;
+    if merchant.status == MerchantStatus::Suspended {
+        return Err(CardError::MerchantSuspended.into());
+    }
+    Ok(merchant)
+}
+
+/// What a card is charged for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spend {
+    /// Funds moved without paying a merchant, not checked against categories
+    Transfer,
+    /// Payment to a merchant of the category, `None` when no merchant is named
+    Purchase(Option<u16>),
+}
+
+/// Count `amount` spent from `source_token` against the card of `wallet`, passed
+/// anywhere among `accounts`, when the wallet has a card; a card that isn't passed
+/// or doesn't exist sets no limits. Purchases are checked against the categories
+/// of the card, which only allows unnamed merchants when it doesn't restrict
+/// categories. Returns the key of the card charged, if any
+pub fn charge_card(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wallet: &Pubkey,
+    source_token: &Pubkey,
+    amount: u64,
+    spend: Spend,
+) -> Result<Option<Pubkey>, ProgramError> {
+    // the card is the program account of `wallet` recreated from the canonical bump it stores
+    let (card_info, mut card) = match accounts
+        .iter()
+        .filter(|info| cmp_pubkeys(info.owner, program_id) && info.data_len() == Card::LEN)
+        .find_map(|info| {
+            let card = Card::unpack(&info.data.borrow()).ok()?;
+            if !cmp_pubkeys(&card.owner, wallet) {
+                return None;
+            }
+            let card_key = create_card_program_address(program_id, wallet, card.bump).ok()?;
+            cmp_pubkeys(info.key, &card_key).then_some((info, card))
+        }) {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+    if card.status == CardStatus::Frozen {
+        return Err(CardError::CardFrozen.into());
+    }
+    if !cmp_pubkeys(source_token, &card.params.funding_token) {
+        return Err(CardError::InvalidCardFundingAccount.into());
+    }
+    let allowed = match spend {
+        Spend::Transfer => true,
+        Spend::Purchase(Some(mcc)) => card.categories.allows(mcc),
+        Spend::Purchase(None) => card.categories.mode == CategoryMode::Any,
+    };
+    if !allowed {
+        return Err(CardError::MerchantCategoryBlocked.into());
+    }
+
+    let limits = card.params.limits;
+    card.roll(Clock::get()?.unix_timestamp);
+    if amount > limits.per_transaction {
+        return Err(CardError::TransactionLimitExceeded.into());
+    }
+    card.daily_spent = card
+        .daily_spent
+        .checked_add(amount)
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    if card.daily_spent > limits.daily {
+        return Err(CardError::DailyLimitExceeded.into());
+    }
+    card.monthly_spent = card
+        .monthly_spent
+        .checked_add(amount)
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())?;
+    if card.monthly_spent > limits.monthly {
+        return Err(CardError::MonthlyLimitExceeded.into());
+    }
+    Card::pack(card, &mut card_info.data.borrow_mut())?;
+    Ok(Some(*card_info.key))
+}
+
+/// Give `amount` charged at `charged_at` back to the counters of `card_key`, passed
+/// anywhere among `accounts`, unless the card was closed since
+pub fn uncharge_card(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    card_key: &Pubkey,
+    amount: u64,
+    charged_at: UnixTimestamp,
+) -> ProgramResult {
+    let card_info = accounts
+        .iter()
+        .find(|info| cmp_pubkeys(info.key, card_key))
+        .ok_or::<ProgramError>(CardError::InvalidCard.into())?;
+    if card_info.owner != program_id || card_info.data_is_empty() {
+        return Ok(());
+    }
+    let mut card = Card::unpack_unchecked(&card_info.data.borrow())?;
+    if !card.is_initialized() {
+        return Ok(());
+    }
+    card.uncharge(amount, charged_at, Clock::get()?.unix_timestamp);
+    Card::pack(card, &mut card_info.data.borrow_mut())
+}
+
+/// Assert none of the `flag` instructions are paused
+pub fn assert_not_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    flag: u64,
+) -> ProgramResult {
+    if load_config(program_id, accounts)?.is_paused(flag) {
+        return Err(CardError::ProgramPaused.into());
+    }
+    Ok(())
+}
+
+/// Assert `authority_info` holds `role` and approved the instruction, either by
+/// signing it or, for a role held by the multisig, through enough of its members
+pub fn assert_role(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo,
+    accounts: &[AccountInfo],
+    role: Role,
+) -> ProgramResult {
+    if !role_approved(program_id, authority_info, accounts, role)? {
+        return Err(role.missing_error().into());
+    }
+    Ok(())
+}
+
+/// Assert `authority_info` holds `role` and approved `action` on the escrow
+/// `reference` for `amount` with arguments hashing to `args_hash`, either like
+/// `assert_role` or with an Ed25519 signature over an `Authorization` verified
+/// by the instruction right before this one
+#[allow(clippy::too_many_arguments)]
+pub fn assert_authorized(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo,
+    accounts: &[AccountInfo],
+    role: Role,
+    reference: &Pubkey,
+    amount: u64,
+    action: AuthorizationAction,
+    args_hash: Hash,
+) -> ProgramResult {
+    if role_approved(program_id, authority_info, accounts, role)? {
+        return Ok(());
+    }
+    let instructions_info = accounts
+        .iter()
+        .find(|info| cmp_pubkeys(info.key, &sysvar::instructions::id()))
+        .ok_or::<ProgramError>(role.missing_error().into())?;
+    let current_index = load_current_index_checked(instructions_info)?;
+    let verify_index = current_index
+        .checked_sub(1)
+        .ok_or::<ProgramError>(CardError::InvalidAuthorization.into())?;
+    let verify_instruction = load_instruction_at_checked(verify_index as usize, instructions_info)?;
+    if !cmp_pubkeys(&verify_instruction.program_id, &ed25519_program::id()) {
+        return Err(CardError::InvalidAuthorization.into());
+    }
+    let message = ed25519_signed_message(&verify_instruction.data, authority_info.key)?
+        .strip_prefix(Authorization::DOMAIN)
+        .ok_or::<ProgramError>(CardError::InvalidAuthorization.into())?;
+    let authorization = Authorization::try_from_slice(message)
+        .map_err(|_| ProgramError::from(CardError::InvalidAuthorization))?;
+    if authorization.program_id != *program_id
+        || authorization.reference != *reference
+        || authorization.amount != amount
+        || authorization.action != action
+        || authorization.args_hash != args_hash
+    {
+        return Err(CardError::InvalidAuthorization.into());
+    }
+    if Clock::get()?.unix_timestamp > authorization.expiry {
+        return Err(CardError::AuthorizationExpired.into());
+    }
+    Ok(())
+}
+
+/// Whether the holder of `role` signed, only the multisig members are left to
+/// check when the role is held by the multisig
+fn role_approved(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo,
+    accounts: &[AccountInfo],
+    role: Role,
+) -> Result<bool, ProgramError> {
+    let config = load_config(program_id, accounts)?;
+    if !cmp_pubkeys(authority_info.key, config.roles.key(role)) {
+        return Err(role.missing_error().into());
+    }
+    if cmp_pubkeys(authority_info.owner, program_id) {
+        assert_multisig_approved(program_id, authority_info, accounts)?;
+        return Ok(true);
+    }
+    Ok(authority_info.is_signer)
+}
+
+/// Assert at least `threshold` distinct members of the multisig signed
+pub fn assert_multisig_approved(
+    program_id: &Pubkey,
+    multisig_info: &AccountInfo,
+    signer_infos: &[AccountInfo],
+) -> ProgramResult {
+    let (multisig_key, _) = find_multisig_program_address(program_id);
+    assert_account_key(
+        multisig_info,
+        &multisig_key,
+        Some(CardError::InvalidAuthorityId),
+    )?;
+    assert_owned_by(multisig_info, program_id)?;
+    let multisig: Multisig = assert_initialized(multisig_info)?;
+    let mut approvals: Vec<&Pubkey> = Vec::with_capacity(multisig.signers().len());
+    for signer_info in signer_infos.iter().filter(|info| info.is_signer) {
+        if multisig.signers().contains(signer_info.key) && !approvals.contains(&signer_info.key) {
+            approvals.push(signer_info.key);
+        }
+    }
+    if approvals.len() < multisig.threshold as usize {
+        return Err(CardError::NotEnoughMultisigSigners.into());
+    }
+    Ok(())
+}
+
+/// Message of the single signature by `signer` the Ed25519 program instruction
+/// `data` verifies, with the key and message held by the instruction itself
+fn ed25519_signed_message<'a>(data: &'a [u8], signer: &Pubkey) -> Result<&'a [u8], ProgramError> {
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_SIZE: usize = 14;
+    const CURRENT_INSTRUCTION: usize = u16::MAX as usize;
+
+    if data.len() < OFFSETS_START + OFFSETS_SIZE || data[0] != 1 {
+        return Err(CardError::InvalidAuthorization.into());
+    }
+    let read_u16 = |at: usize| {
+        u16::from_le_bytes([data[OFFSETS_START + at], data[OFFSETS_START + at + 1]]) as usize
+    };
+    let signature_instruction_index = read_u16(2);
+    let public_key_offset = read_u16(4);
+    let public_key_instruction_index = read_u16(6);
+    let message_offset = read_u16(8);
+    let message_size = read_u16(10);
+    let message_instruction_index = read_u16(12);
+    if signature_instruction_index != CURRENT_INSTRUCTION
+        || public_key_instruction_index != CURRENT_INSTRUCTION
+        || message_instruction_index != CURRENT_INSTRUCTION
+    {
+        return Err(CardError::InvalidAuthorization.into());
+    }
+    if data.get(public_key_offset..public_key_offset + PUBKEY_BYTES) != Some(signer.as_ref()) {
+        return Err(CardError::InvalidAuthorization.into());
+    }
+    data.get(message_offset..message_offset + message_size)
+        .ok_or_else(|| CardError::InvalidAuthorization.into())
+}
+
 /// Assert owned by
 pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
     if account.owner != owner {
@@ -69,6 +512,17 @@ pub fn assert_account_key(
     }
 }
 
+/// Assert `account_info` is the program authority of its canonical `bump`, stored on init
+pub fn assert_program_authority(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+    bump: u8,
+    error: CardError,
+) -> ProgramResult {
+    let key = create_program_authority(program_id, bump).map_err(|_| ProgramError::from(error))?;
+    assert_account_key(account_info, &key, Some(error))
+}
+
 /// Assert account rent exempt
 pub fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramResult {
     if !rent.is_exempt(account_info.lamports(), account_info.data_len()) {
@@ -111,7 +565,12 @@ pub fn transfer<'a>(
     signers_seeds: &[&[&[u8]]],
 ) -> Result<(), ProgramError> {
     if is_native {
-        native_transfer(source_account_info, destination_account_info, amount, signers_seeds)
+        native_transfer(
+            source_account_info,
+            destination_account_info,
+            amount,
+            signers_seeds,
+        )
     } else {
         spl_token_transfer(
             source_account_info,
@@ -123,6 +582,32 @@ pub fn transfer<'a>(
     }
 }
 
+/// SPL approve instruction.
+pub fn spl_token_approve<'a>(
+    source: &AccountInfo<'a>,
+    delegate: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let ix = spl_token::instruction::approve(
+        &spl_token::id(),
+        source.key,
+        delegate.key,
+        owner.key,
+        &[],
+        amount,
+    )?;
+
+    invoke(&ix, &[source.clone(), delegate.clone(), owner.clone()])
+}
+
+/// SPL revoke instruction.
+pub fn spl_token_revoke<'a>(source: &AccountInfo<'a>, owner: &AccountInfo<'a>) -> ProgramResult {
+    let ix = spl_token::instruction::revoke(&spl_token::id(), source.key, owner.key, &[])?;
+
+    invoke(&ix, &[source.clone(), owner.clone()])
+}
+
 /// SPL transfer instruction.
 pub fn spl_token_transfer<'a>(
     source: &AccountInfo<'a>,
@@ -156,24 +641,140 @@ pub fn native_transfer<'a>(
 ) -> Result<(), ProgramError> {
     invoke_signed(
         // for native SOL transfer user_wallet key == user_token_account key
-        &system_instruction::transfer(&source.key, &destination.key, amount),
+        &system_instruction::transfer(source.key, destination.key, amount),
         &[source.clone(), destination.clone()],
         signers_seeds,
     )
 }
 
+/// Create and initialize the temporary wrapped SOL account owned by `owner`
+pub fn create_wrapped_native_account<'a>(
+    wrap_info: &AccountInfo<'a>,
+    native_mint_info: &AccountInfo<'a>,
+    owner_info: &AccountInfo<'a>,
+    rent_sysvar_info: &AccountInfo<'a>,
+    payer_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    create_new_account_raw(
+        &spl_token::id(),
+        wrap_info,
+        rent_sysvar_info,
+        payer_info,
+        system_program_info,
+        Account::LEN,
+        signer_seeds,
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account3(
+            &spl_token::id(),
+            wrap_info.key,
+            native_mint_info.key,
+            owner_info.key,
+        )?,
+        &[wrap_info.clone(), native_mint_info.clone()],
+    )
+}
+
+/// Wrap lamports from the wallet into the wrapped SOL account
+pub fn wrap_native<'a>(
+    wallet_info: &AccountInfo<'a>,
+    wrap_info: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    native_transfer(wallet_info, wrap_info, amount, &[])?;
+    invoke(
+        &spl_token::instruction::sync_native(&spl_token::id(), wrap_info.key)?,
+        std::slice::from_ref(wrap_info),
+    )
+}
+
+/// Close a token account, for wrapped SOL this unwraps the whole balance
+pub fn close_token_account<'a>(
+    account_info: &AccountInfo<'a>,
+    destination_info: &AccountInfo<'a>,
+    owner_info: &AccountInfo<'a>,
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &spl_token::instruction::close_account(
+            &spl_token::id(),
+            account_info.key,
+            destination_info.key,
+            owner_info.key,
+            &[],
+        )?,
+        &[
+            account_info.clone(),
+            destination_info.clone(),
+            owner_info.clone(),
+        ],
+        signers_seeds,
+    )
+}
+
+/// Take the associated token program from the remaining accounts, callers pass it
+/// to opt in to having associated token accounts created on demand
+pub fn next_associated_token_program<'a, 'b>(
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Option<&'a AccountInfo<'b>> {
+    match account_info_iter.as_slice().first() {
+        Some(info) if cmp_pubkeys(info.key, &spl_associated_token_account::id()) => {
+            account_info_iter.next()
+        }
+        _ => None,
+    }
+}
+
+/// Create the associated token account of `owner` for `mint` unless it already exists
+#[allow(clippy::too_many_arguments)]
+pub fn create_associated_token_account<'a>(
+    payer_info: &AccountInfo<'a>,
+    associated_token_info: &AccountInfo<'a>,
+    owner_info: &AccountInfo<'a>,
+    mint_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    token_program_info: &AccountInfo<'a>,
+    associated_token_program_info: &AccountInfo<'a>,
+) -> ProgramResult {
+    let associated_token_key = get_associated_token_address(owner_info.key, mint_info.key);
+    assert_account_key(
+        associated_token_info,
+        &associated_token_key,
+        Some(CardError::InvalidAssociatedTokenAccount),
+    )?;
+    invoke(
+        &create_associated_token_account_idempotent(
+            payer_info.key,
+            owner_info.key,
+            mint_info.key,
+            &spl_token::id(),
+        ),
+        &[
+            payer_info.clone(),
+            associated_token_info.clone(),
+            owner_info.clone(),
+            mint_info.clone(),
+            system_program_info.clone(),
+            token_program_info.clone(),
+            associated_token_program_info.clone(),
+        ],
+    )
+}
+
 pub fn calculate_fee(amount: u64, fee_basis_points: u64) -> Result<u64, ProgramError> {
-    Ok(amount
+    amount
         .checked_mul(fee_basis_points)
         .ok_or::<ProgramError>(CardError::MathOverflow.into())?
         .checked_div(10000)
-        .ok_or::<ProgramError>(CardError::MathOverflow.into())?)
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())
 }
 
 pub fn calculate_amount_with_fee(amount: u64, fee_basis_points: u64) -> Result<u64, ProgramError> {
-    Ok(amount
+    amount
         .checked_add(calculate_fee(amount, fee_basis_points)?)
-        .ok_or::<ProgramError>(CardError::MathOverflow.into())?)
+        .ok_or::<ProgramError>(CardError::MathOverflow.into())
 }
 
 pub fn create_new_account_raw<'a>(
@@ -189,9 +790,9 @@ pub fn create_new_account_raw<'a>(
     let required_lamports = rent.minimum_balance(size);
 
     if required_lamports > 0 {
-        msg!("Transfer {} lamports to the new account", required_lamports);
+        verbose_msg!("Transfer {} lamports to the new account", required_lamports);
         invoke(
-            &system_instruction::transfer(&payer_info.key, new_account_info.key, required_lamports),
+            &system_instruction::transfer(payer_info.key, new_account_info.key, required_lamports),
             &[
                 payer_info.clone(),
                 new_account_info.clone(),
@@ -202,18 +803,18 @@ pub fn create_new_account_raw<'a>(
 
     let accounts = &[new_account_info.clone(), system_program_info.clone()];
 
-    msg!("Allocate space for the account {}", new_account_info.key);
+    verbose_msg!("Allocate space for the account {}", new_account_info.key);
     invoke_signed(
         &system_instruction::allocate(new_account_info.key, size.try_into().unwrap()),
         accounts,
-        &[&signer_seeds],
+        &[signer_seeds],
     )?;
 
-    msg!("Assign the account to the owning program");
+    verbose_msg!("Assign the account to the owning program");
     invoke_signed(
         &system_instruction::assign(new_account_info.key, program_id),
         accounts,
-        &[&signer_seeds],
+        &[signer_seeds],
     )?;
     Ok(())
 }
@@ -222,4 +823,4 @@ pub fn create_new_account_raw<'a>(
 /// `sol_memcmp`
 pub fn cmp_pubkeys(a: &Pubkey, b: &Pubkey) -> bool {
     sol_memcmp(a.as_ref(), b.as_ref(), PUBKEY_BYTES) == 0
-}
\ No newline at end of file
+}
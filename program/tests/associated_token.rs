@@ -0,0 +1,222 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_escrow_program_address, find_mint_config_program_address, find_program_authority,
+    find_withdrawal_program_address,
+    instruction::{self, InitEscrowArgs, WithdrawArgs},
+    state::mint_config::MintParams,
+    utils::calculate_fee,
+};
+use common::*;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+
+const FEE_BPS: u16 = 250;
+const FIXED_FEE: u64 = 5_000;
+
+fn total_fee(amount: u64) -> u64 {
+    calculate_fee(amount, FEE_BPS as u64).unwrap() + FIXED_FEE
+}
+
+struct Setup {
+    context: ProgramTestContext,
+    mint: Pubkey,
+    wallet_token: Pubkey,
+    vault_token: Pubkey,
+}
+
+/// Starts with a fresh mint nobody but the wallet and the vault holds an account of
+async fn setup(wallet: &Keypair) -> Setup {
+    let mut program_test = program_test();
+    let mint = Pubkey::new_unique();
+    let wallet_token = Pubkey::new_unique();
+    let vault_token = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(INITIAL_AMOUNT));
+    program_test.add_account(
+        wallet_token,
+        token_account(&mint, &wallet.pubkey(), INITIAL_AMOUNT, None),
+    );
+    program_test.add_account(
+        vault_token,
+        token_account(&mint, &find_program_authority(&card::id()).0, 0, None),
+    );
+    program_test.add_account(
+        find_mint_config_program_address(&card::id(), &mint).0,
+        mint_config_account(
+            &mint,
+            &get_associated_token_address(&deposit::id(), &mint),
+            &get_associated_token_address(&fee::id(), &mint),
+            FEE_BPS,
+            FIXED_FEE,
+        ),
+    );
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    Setup {
+        context,
+        mint,
+        wallet_token,
+        vault_token,
+    }
+}
+
+fn init_escrow(setup: &Setup, wallet: &Keypair, dst_token: &Pubkey, amount: u64) -> Instruction {
+    let reference = Pubkey::new_unique();
+    let (escrow, bump) = find_escrow_program_address(&card::id(), &reference);
+    instruction::with_associated_token_accounts(
+        instruction::init_escrow(
+            &card::id(),
+            &wallet.pubkey(),
+            &authority().pubkey(),
+            &setup.context.payer.pubkey(),
+            &escrow,
+            &find_program_authority(&card::id()).0,
+            &setup.vault_token,
+            &setup.wallet_token,
+            dst_token,
+            &get_associated_token_address(&fee::id(), &setup.mint),
+            &setup.mint,
+            &reference,
+            InitEscrowArgs {
+                amount,
+                fee_bps: FEE_BPS,
+                fixed_fee: FIXED_FEE,
+                bump,
+                merchant: None,
+                split: None,
+                hold: false,
+                mcc: 0,
+            },
+        ),
+        &deposit::id(),
+        &fee::id(),
+    )
+}
+
+fn withdraw(setup: &Setup, wallet: &Keypair, receiver: &Pubkey, amount: u64) -> Instruction {
+    let key = Pubkey::new_unique();
+    let (withdraw, bump) = find_withdrawal_program_address(&card::id(), &key);
+    instruction::with_associated_token_accounts(
+        instruction::withdraw(
+            &card::id(),
+            &wallet.pubkey(),
+            &authority().pubkey(),
+            &setup.context.payer.pubkey(),
+            &withdraw,
+            &setup.wallet_token,
+            &get_associated_token_address(receiver, &setup.mint),
+            &get_associated_token_address(&fee::id(), &setup.mint),
+            &setup.mint,
+            WithdrawArgs {
+                amount,
+                fee_bps: FEE_BPS,
+                key,
+                bump,
+                fixed_fee: FIXED_FEE,
+            },
+        ),
+        receiver,
+        &fee::id(),
+    )
+}
+
+#[tokio::test]
+async fn test_escrow_creates_collection_accounts() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let amount = 1_000_000;
+    let dst_token = get_associated_token_address(&deposit::id(), &setup.mint);
+
+    let instruction = init_escrow(&setup, &wallet, &dst_token, amount);
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    // the accounts already exist the second time around
+    let instruction = init_escrow(&setup, &wallet, &dst_token, amount);
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+
+    let context = &mut setup.context;
+    assert_eq!(token_amount(context, &dst_token).await, 0);
+    assert_eq!(
+        token_amount(
+            context,
+            &get_associated_token_address(&fee::id(), &setup.mint)
+        )
+        .await,
+        0
+    );
+    assert_eq!(
+        token_amount(context, &setup.vault_token).await,
+        2 * (amount + total_fee(amount))
+    );
+}
+
+#[tokio::test]
+async fn test_escrow_rejects_non_associated_account() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let dst_token = Pubkey::new_unique();
+    // the mint config points at the bogus account so only the address derivation rejects it
+    let instruction = instruction::set_mint_config(
+        &card::id(),
+        &authority().pubkey(),
+        &setup.mint,
+        MintParams {
+            enabled: true,
+            min_amount: 0,
+            max_amount: u64::MAX,
+            fee_bps: 0,
+            fixed_fee: 0,
+            deposit_collection: dst_token,
+            fee_collection: get_associated_token_address(&fee::id(), &setup.mint),
+        },
+    );
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+
+    let instruction = init_escrow(&setup, &wallet, &dst_token, 1_000_000);
+    let result = process(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert_card_error(result, CardError::InvalidAssociatedTokenAccount);
+}
+
+#[tokio::test]
+async fn test_withdraw_creates_destination_account() {
+    let wallet = Keypair::new();
+    let receiver = Pubkey::new_unique();
+    let mut setup = setup(&wallet).await;
+    let amount = 1_000_000;
+
+    let instruction = withdraw(&setup, &wallet, &receiver, amount);
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+
+    let context = &mut setup.context;
+    assert_eq!(
+        token_amount(
+            context,
+            &get_associated_token_address(&receiver, &setup.mint)
+        )
+        .await,
+        amount
+    );
+    assert_eq!(
+        token_amount(
+            context,
+            &get_associated_token_address(&fee::id(), &setup.mint)
+        )
+        .await,
+        total_fee(amount)
+    );
+    assert_eq!(
+        token_amount(context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - amount - total_fee(amount)
+    );
+}
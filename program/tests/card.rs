@@ -0,0 +1,706 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_card_program_address, find_deposit_program_address, find_merchant_program_address,
+    find_program_authority, find_wrap_program_address,
+    instruction::{self, DepositArgs, InitCardArgs, OnboardMerchantArgs},
+    state::{
+        card::{
+            Card, CardAuthority, CardLimits, CardParams, CardStatus, CategoryMode, MccRange,
+            MerchantCategories, MAX_MCC_RANGES,
+        },
+        merchant::{MerchantParams, Settlement, MAX_MERCHANT_MINTS},
+    },
+};
+use common::*;
+use solana_program::{
+    clock::UnixTimestamp, instruction::Instruction, program_pack::Pack, pubkey::Pubkey,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+/// 2024-01-29 00:00:00 UTC
+const JANUARY_29: UnixTimestamp = 1_706_486_400;
+const DAY: UnixTimestamp = 24 * 60 * 60;
+
+/// Card spending native SOL from `wallet`
+fn card_params(wallet: &Pubkey) -> CardParams {
+    CardParams {
+        funding_token: *wallet,
+        limits: CardLimits {
+            per_transaction: LAMPORTS_PER_SOL,
+            daily: 3 * LAMPORTS_PER_SOL / 2,
+            monthly: 5 * LAMPORTS_PER_SOL / 2,
+        },
+    }
+}
+
+async fn setup(wallet: &Keypair) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), 0, 0)
+        .start_with_context()
+        .await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    set_time(&mut context, JANUARY_29).await;
+    context
+}
+
+fn init_card(admin: &Pubkey, payer: &Pubkey, owner: &Pubkey, params: CardParams) -> Instruction {
+    instruction::init_card(
+        &card::id(),
+        admin,
+        payer,
+        InitCardArgs {
+            owner: *owner,
+            params,
+            bump: find_card_program_address(&card::id(), owner).1,
+        },
+    )
+}
+
+fn init_escrow(payer: &Pubkey, wallet: &Keypair, amount: u64) -> Instruction {
+    let escrow = NativeEscrow::new();
+    escrow.init_with(
+        &authority().pubkey(),
+        payer,
+        wallet,
+        &deposit::id(),
+        escrow.args(amount),
+    )
+}
+
+/// Onboard a merchant paid in native SOL at the deposit collection, returns its id
+async fn onboard_merchant(context: &mut ProgramTestContext) -> Pubkey {
+    let id = Pubkey::new_unique();
+    let mut settlements = [Settlement::default(); MAX_MERCHANT_MINTS];
+    settlements[0] = Settlement {
+        mint: spl_token::native_mint::id(),
+        token: deposit::id(),
+    };
+    let payer = context.payer.pubkey();
+    process(
+        context,
+        instruction::onboard_merchant(
+            &card::id(),
+            &authority().pubkey(),
+            &payer,
+            OnboardMerchantArgs {
+                id,
+                params: MerchantParams {
+                    settlement_count: 1,
+                    settlements,
+                    ..MerchantParams::default()
+                },
+                bump: find_merchant_program_address(&card::id(), &id).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    id
+}
+
+/// `InitEscrow` paying `merchant` of category `mcc`
+fn init_escrow_at(
+    payer: &Pubkey,
+    wallet: &Keypair,
+    amount: u64,
+    merchant: &Pubkey,
+    mcc: u16,
+) -> Instruction {
+    let escrow = NativeEscrow::new();
+    let mut args = escrow.args(amount);
+    args.merchant = Some(*merchant);
+    args.mcc = mcc;
+    escrow.init_with(&authority().pubkey(), payer, wallet, &deposit::id(), args)
+}
+
+async fn card_state(context: &mut ProgramTestContext, owner: &Pubkey) -> Card {
+    let data = account_data(context, &find_card_program_address(&card::id(), owner).0).await;
+    Card::unpack(&data).unwrap()
+}
+
+#[tokio::test]
+async fn test_escrows_count_against_card_limits() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    process(
+        &mut context,
+        init_card(
+            &authority().pubkey(),
+            &payer,
+            &wallet.pubkey(),
+            card_params(&wallet.pubkey()),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let signers = [&wallet, &authority()];
+
+    let result = process(
+        &mut context,
+        init_escrow(&payer, &wallet, LAMPORTS_PER_SOL + 1),
+        &signers,
+    )
+    .await;
+    assert_card_error(result, CardError::TransactionLimitExceeded);
+    for amount in [LAMPORTS_PER_SOL, LAMPORTS_PER_SOL / 2] {
+        process(&mut context, init_escrow(&payer, &wallet, amount), &signers)
+            .await
+            .unwrap();
+    }
+    let result = process(&mut context, init_escrow(&payer, &wallet, 1), &signers).await;
+    assert_card_error(result, CardError::DailyLimitExceeded);
+
+    // the daily counter starts over the next day, the monthly one keeps counting
+    set_time(&mut context, JANUARY_29 + DAY).await;
+    process(
+        &mut context,
+        init_escrow(&payer, &wallet, LAMPORTS_PER_SOL),
+        &signers,
+    )
+    .await
+    .unwrap();
+    set_time(&mut context, JANUARY_29 + 2 * DAY).await;
+    let result = process(&mut context, init_escrow(&payer, &wallet, 1), &signers).await;
+    assert_card_error(result, CardError::MonthlyLimitExceeded);
+    let card = card_state(&mut context, &wallet.pubkey()).await;
+    assert_eq!(card.daily_spent, LAMPORTS_PER_SOL);
+    assert_eq!(card.monthly_spent, 5 * LAMPORTS_PER_SOL / 2);
+
+    // and the monthly one with February
+    set_time(&mut context, JANUARY_29 + 3 * DAY).await;
+    process(
+        &mut context,
+        init_escrow(&payer, &wallet, LAMPORTS_PER_SOL),
+        &signers,
+    )
+    .await
+    .unwrap();
+    let card = card_state(&mut context, &wallet.pubkey()).await;
+    assert_eq!(card.monthly_spent, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn test_card_is_issued_and_updated_by_admin() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let funding_token = Pubkey::new_unique();
+    let outsider = Keypair::new();
+
+    let result = process(
+        &mut context,
+        init_card(
+            &outsider.pubkey(),
+            &payer,
+            &wallet.pubkey(),
+            card_params(&funding_token),
+        ),
+        &[&outsider],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+    process(
+        &mut context,
+        init_card(
+            &authority().pubkey(),
+            &payer,
+            &wallet.pubkey(),
+            card_params(&funding_token),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    // the card is only spent from its funding account
+    let result = process(
+        &mut context,
+        init_escrow(&payer, &wallet, LAMPORTS_PER_SOL),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidCardFundingAccount);
+    process(
+        &mut context,
+        instruction::update_card(
+            &card::id(),
+            &authority().pubkey(),
+            &wallet.pubkey(),
+            card_params(&wallet.pubkey()),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        init_escrow(&payer, &wallet, LAMPORTS_PER_SOL),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    let card = card_state(&mut context, &wallet.pubkey()).await;
+    assert_eq!(card.owner, wallet.pubkey());
+    assert_eq!(card.daily_spent, LAMPORTS_PER_SOL);
+}
+
+fn deposit(payer: &Pubkey, wallet: &Keypair, amount: u64) -> Instruction {
+    let key = Pubkey::new_unique();
+    let (deposit, bump) = find_deposit_program_address(&card::id(), &key);
+    instruction::deposit_native(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        payer,
+        &deposit,
+        &deposit::id(),
+        &fee::id(),
+        &find_program_authority(&card::id()).0,
+        &find_wrap_program_address(&card::id(), &key).0,
+        DepositArgs {
+            amount,
+            fee_bps: 0,
+            key,
+            bump,
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_frozen_card_stops_spending() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let owner = wallet.pubkey();
+    let admin = authority().pubkey();
+    process(
+        &mut context,
+        init_card(&admin, &payer, &owner, card_params(&owner)),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let signers = [&wallet, &authority()];
+
+    // a lost card is frozen by its holder right away
+    process(
+        &mut context,
+        instruction::freeze_card(&card::id(), &owner, &owner),
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+    let result = process(&mut context, init_escrow(&payer, &wallet, 1), &signers).await;
+    assert_card_error(result, CardError::CardFrozen);
+    let result = process(&mut context, deposit(&payer, &wallet, 1), &signers).await;
+    assert_card_error(result, CardError::CardFrozen);
+    process(
+        &mut context,
+        instruction::unfreeze_card(&card::id(), &owner, &owner),
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+    process(&mut context, init_escrow(&payer, &wallet, 1), &signers)
+        .await
+        .unwrap();
+
+    // the issuer's freeze stands until the issuer lifts it
+    let outsider = Keypair::new();
+    let result = process(
+        &mut context,
+        instruction::freeze_card(&card::id(), &outsider.pubkey(), &owner),
+        &[&outsider],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+    process(
+        &mut context,
+        instruction::freeze_card(&card::id(), &admin, &owner),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(
+        &mut context,
+        instruction::freeze_card(&card::id(), &owner, &owner),
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+    let card = card_state(&mut context, &owner).await;
+    assert_eq!(card.status, CardStatus::Frozen);
+    assert_eq!(card.frozen_by, CardAuthority::Issuer);
+    let result = process(
+        &mut context,
+        instruction::unfreeze_card(&card::id(), &owner, &owner),
+        &[&wallet],
+    )
+    .await;
+    assert_card_error(result, CardError::CardFrozenByIssuer);
+    process(
+        &mut context,
+        instruction::unfreeze_card(&card::id(), &admin, &owner),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = process(
+        &mut context,
+        instruction::unfreeze_card(&card::id(), &admin, &owner),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::CardNotFrozen);
+    let card = card_state(&mut context, &owner).await;
+    assert_eq!(card.status, CardStatus::Active);
+    assert_eq!(card.frozen_by, CardAuthority::None);
+}
+
+fn categories(mode: CategoryMode, ranges: &[(u16, u16)]) -> MerchantCategories {
+    let mut categories = MerchantCategories {
+        mode,
+        range_count: ranges.len() as u8,
+        ranges: [MccRange::default(); MAX_MCC_RANGES],
+    };
+    for (range, &(start, end)) in categories.ranges.iter_mut().zip(ranges) {
+        *range = MccRange { start, end };
+    }
+    categories
+}
+
+#[tokio::test]
+async fn test_card_categories_restrict_merchants() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let owner = wallet.pubkey();
+    let admin = authority().pubkey();
+    process(
+        &mut context,
+        init_card(&admin, &payer, &owner, card_params(&owner)),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let merchant = onboard_merchant(&mut context).await;
+    let signers = [&wallet, &authority()];
+
+    // the holder keeps their card away from restaurants
+    process(
+        &mut context,
+        instruction::set_card_categories(
+            &card::id(),
+            &owner,
+            &owner,
+            categories(CategoryMode::Deny, &[(5812, 5814)]),
+        ),
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        init_escrow_at(&payer, &wallet, 1, &merchant, 5813),
+        &signers,
+    )
+    .await;
+    assert_card_error(result, CardError::MerchantCategoryBlocked);
+    process(
+        &mut context,
+        init_escrow_at(&payer, &wallet, 1, &merchant, 5411),
+        &signers,
+    )
+    .await
+    .unwrap();
+
+    // a corporate card is limited to travel and groceries by the issuer
+    let result = process(
+        &mut context,
+        instruction::set_card_categories(
+            &card::id(),
+            &admin,
+            &owner,
+            categories(CategoryMode::Allow, &[(4112, 4111)]),
+        ),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidMerchantCategories);
+    process(
+        &mut context,
+        instruction::set_card_categories(
+            &card::id(),
+            &admin,
+            &owner,
+            categories(CategoryMode::Allow, &[(4111, 4131), (5411, 5411)]),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    for mcc in [5813, 5999] {
+        let result = process(
+            &mut context,
+            init_escrow_at(&payer, &wallet, 1, &merchant, mcc),
+            &signers,
+        )
+        .await;
+        assert_card_error(result, CardError::MerchantCategoryBlocked);
+    }
+    process(
+        &mut context,
+        init_escrow_at(&payer, &wallet, 1, &merchant, 4121),
+        &signers,
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        instruction::set_card_categories(
+            &card::id(),
+            &owner,
+            &owner,
+            categories(CategoryMode::Any, &[]),
+        ),
+        &[&wallet],
+    )
+    .await;
+    assert_card_error(result, CardError::CategoriesSetByIssuer);
+    let card = card_state(&mut context, &owner).await;
+    assert_eq!(card.categories.mode, CategoryMode::Allow);
+    assert_eq!(card.categories_set_by, CardAuthority::Issuer);
+}
+
+#[tokio::test]
+async fn test_restricted_card_needs_a_merchant() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let owner = wallet.pubkey();
+    process(
+        &mut context,
+        init_card(&authority().pubkey(), &payer, &owner, card_params(&owner)),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let merchant = onboard_merchant(&mut context).await;
+    let signers = [&wallet, &authority()];
+    process(
+        &mut context,
+        instruction::set_card_categories(
+            &card::id(),
+            &owner,
+            &owner,
+            categories(CategoryMode::Deny, &[(5812, 5814)]),
+        ),
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+
+    // an escrow naming no merchant could claim any category
+    let escrow = NativeEscrow::new();
+    let mut args = escrow.args(1);
+    args.mcc = 5411;
+    let result = process(
+        &mut context,
+        escrow.init_with(&authority().pubkey(), &payer, &wallet, &deposit::id(), args),
+        &signers,
+    )
+    .await;
+    assert_card_error(result, CardError::MerchantCategoryBlocked);
+    process(
+        &mut context,
+        init_escrow_at(&payer, &wallet, 1, &merchant, 5411),
+        &signers,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_closed_card_goes_back_to_no_card() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let owner = wallet.pubkey();
+    let admin = authority().pubkey();
+    let card_key = find_card_program_address(&card::id(), &owner).0;
+    process(
+        &mut context,
+        init_card(&admin, &payer, &owner, card_params(&owner)),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    let outsider = Keypair::new();
+    let result = process(
+        &mut context,
+        instruction::close_card(&card::id(), &outsider.pubkey(), &owner, &owner),
+        &[&outsider],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+
+    // the holder can't close a card the issuer froze
+    process(
+        &mut context,
+        instruction::freeze_card(&card::id(), &admin, &owner),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let close = instruction::close_card(&card::id(), &owner, &owner, &owner);
+    let result = process(&mut context, close.clone(), &[&wallet]).await;
+    assert_card_error(result, CardError::CardFrozenByIssuer);
+    process(
+        &mut context,
+        instruction::unfreeze_card(&card::id(), &admin, &owner),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    let rent = balance(&mut context, &card_key).await;
+    let owner_before = balance(&mut context, &owner).await;
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, close, &[&wallet]).await.unwrap();
+    assert!(context
+        .banks_client
+        .get_account(card_key)
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(balance(&mut context, &owner).await, owner_before + rent);
+
+    // without a card the wallet spends past the limits it had
+    process(
+        &mut context,
+        init_escrow(&payer, &wallet, 2 * LAMPORTS_PER_SOL),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        init_card(&admin, &payer, &owner, card_params(&owner)),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        card_state(&mut context, &owner).await.status,
+        CardStatus::Active
+    );
+}
+
+#[tokio::test]
+async fn test_wallet_without_card_creates_escrow() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let card_key = find_card_program_address(&card::id(), &wallet.pubkey()).0;
+
+    let mut instruction = init_escrow(&payer, &wallet, 2 * LAMPORTS_PER_SOL);
+    instruction
+        .accounts
+        .retain(|account| account.pubkey != card_key);
+    let escrow = instruction.accounts[3].pubkey;
+    process(&mut context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    let state = escrow_state(&mut context, &escrow).await;
+    assert_eq!(state.amount, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(state.card, Pubkey::default());
+}
+
+#[tokio::test]
+async fn test_canceled_escrow_gives_back_card_spending() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let owner = wallet.pubkey();
+    process(
+        &mut context,
+        init_card(&authority().pubkey(), &payer, &owner, card_params(&owner)),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let signers = [&wallet, &authority()];
+    let card_key = find_card_program_address(&card::id(), &owner).0;
+
+    let yesterday = NativeEscrow::new();
+    process(
+        &mut context,
+        yesterday.init_with(
+            &authority().pubkey(),
+            &payer,
+            &wallet,
+            &deposit::id(),
+            yesterday.args(LAMPORTS_PER_SOL),
+        ),
+        &signers,
+    )
+    .await
+    .unwrap();
+    let state = escrow_state(&mut context, &yesterday.escrow).await;
+    assert_eq!(state.card, card_key);
+    assert_eq!(state.charged_at, JANUARY_29);
+
+    set_time(&mut context, JANUARY_29 + DAY).await;
+    let today = NativeEscrow::new();
+    process(
+        &mut context,
+        today.init_with(
+            &authority().pubkey(),
+            &payer,
+            &wallet,
+            &deposit::id(),
+            today.args(LAMPORTS_PER_SOL / 2),
+        ),
+        &signers,
+    )
+    .await
+    .unwrap();
+
+    // the card account is needed to give the amount back
+    let result = process(&mut context, today.cancel(&wallet), &[&authority()]).await;
+    assert_card_error(result, CardError::InvalidCard);
+    process(
+        &mut context,
+        instruction::with_card(today.cancel(&wallet), &card::id(), &owner),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let card = card_state(&mut context, &owner).await;
+    assert_eq!(card.daily_spent, 0);
+    assert_eq!(card.monthly_spent, LAMPORTS_PER_SOL);
+
+    // yesterday's spending is only still counted by the month
+    process(
+        &mut context,
+        instruction::with_card(yesterday.cancel(&wallet), &card::id(), &owner),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let card = card_state(&mut context, &owner).await;
+    assert_eq!(card.daily_spent, 0);
+    assert_eq!(card.monthly_spent, 0);
+}
@@ -0,0 +1,532 @@
+//! Fixtures shared by the integration tests, each test file using its own subset
+#![allow(dead_code)]
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_config_program_address, find_escrow_program_address, find_escrow_vault_program_address,
+    find_mint_config_program_address, find_program_authority, find_program_data_address,
+    instruction::{self, Authorization, InitConfigArgs, InitEscrowArgs},
+    processor::Processor,
+    state::{
+        config::Roles,
+        escrow::Escrow,
+        mint_config::{MintConfig, MintParams},
+    },
+};
+use solana_program::{
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::{Clock, UnixTimestamp},
+    ed25519_program,
+    instruction::{Instruction, InstructionError},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+
+/// Supply of the test mints, all of it held by the wallet
+pub const INITIAL_AMOUNT: u64 = 1_000_000_000;
+
+/// Upgrade authority of the program, the key the tests hand every role to
+pub fn authority() -> Keypair {
+    read_keypair_file(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/authority.json"
+    ))
+    .unwrap()
+}
+
+/// Every role held by `key`
+pub fn roles(key: Pubkey) -> Roles {
+    Roles {
+        settler: key,
+        canceler: key,
+        deposit_approver: key,
+        withdrawal_approver: key,
+        rent_closer: key,
+        config_admin: key,
+    }
+}
+
+/// Program data account naming `upgrade_authority`, as the upgradeable loader
+/// writes it on deployment
+pub fn program_data_account(upgrade_authority: &Pubkey) -> Account {
+    let data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(*upgrade_authority),
+    })
+    .unwrap();
+    let mut account = Account::new(
+        Rent::default().minimum_balance(data.len()),
+        data.len(),
+        &bpf_loader_upgradeable::id(),
+    );
+    account.data = data;
+    account
+}
+
+/// Program test of the program deployed by `authority`
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new("card", card::id(), processor!(Processor::process));
+    program_test.add_account(
+        find_program_data_address(&card::id()),
+        program_data_account(&authority().pubkey()),
+    );
+    program_test
+}
+
+/// Create the config handing out `roles`
+pub async fn init_config(context: &mut ProgramTestContext, roles: Roles) {
+    let payer = context.payer.pubkey();
+    process(
+        context,
+        instruction::init_config(
+            &card::id(),
+            &authority().pubkey(),
+            &payer,
+            InitConfigArgs {
+                roles,
+                bump: find_config_program_address(&card::id()).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+}
+
+/// System account of `lamports`
+pub fn system_account(lamports: u64) -> Account {
+    Account::new(lamports, 0, &system_program::id())
+}
+
+/// Mint of `supply` without mint or freeze authority
+pub fn mint_account(supply: u64) -> Account {
+    let mut account = Account::new(
+        Rent::default().minimum_balance(Mint::LEN),
+        Mint::LEN,
+        &spl_token::id(),
+    );
+    Mint::pack(
+        Mint {
+            mint_authority: COption::None,
+            supply,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        },
+        &mut account.data,
+    )
+    .unwrap();
+    account
+}
+
+/// Mint config supporting any amount of `mint` at the given default fee, as
+/// `InitMintConfig` creates it
+pub fn mint_config_account(
+    mint: &Pubkey,
+    deposit_collection: &Pubkey,
+    fee_collection: &Pubkey,
+    fee_bps: u16,
+    fixed_fee: u64,
+) -> Account {
+    let mut account = Account::new(
+        Rent::default().minimum_balance(MintConfig::LEN),
+        MintConfig::LEN,
+        &card::id(),
+    );
+    MintConfig::pack(
+        MintConfig {
+            is_initialized: true,
+            mint: *mint,
+            params: MintParams {
+                enabled: true,
+                min_amount: 0,
+                max_amount: u64::MAX,
+                fee_bps,
+                fixed_fee,
+                deposit_collection: *deposit_collection,
+                fee_collection: *fee_collection,
+            },
+            has_fee_schedule: false,
+            fee_schedule_bump: 0,
+            bump: find_mint_config_program_address(&card::id(), mint).1,
+        },
+        &mut account.data,
+    )
+    .unwrap();
+    account
+}
+
+/// Token account of `owner`, spendable by `delegate` when given
+pub fn token_account(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    delegate: Option<&Pubkey>,
+) -> Account {
+    let mut account = Account::new(
+        Rent::default().minimum_balance(TokenAccount::LEN),
+        TokenAccount::LEN,
+        &spl_token::id(),
+    );
+    TokenAccount::pack(
+        TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: delegate.copied().into(),
+            delegated_amount: delegate.map_or(0, |_| u64::MAX),
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        },
+        &mut account.data,
+    )
+    .unwrap();
+    account
+}
+
+pub async fn process(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Processes the authorization and the instruction it approves in one transaction
+pub async fn process_all(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// Ed25519 program instruction verifying `signer`'s signature over the authorization
+pub fn ed25519_authorization(signer: &Keypair, authorization: &Authorization) -> Instruction {
+    ed25519_signature(signer, &authorization.message().unwrap())
+}
+
+/// Ed25519 program instruction verifying `signer`'s signature over `message`
+pub fn ed25519_signature(signer: &Keypair, message: &[u8]) -> Instruction {
+    const DATA_START: u16 = 16;
+    let signature = signer.sign_message(message);
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signer.pubkey().as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(message);
+    Instruction::new_with_bytes(ed25519_program::id(), &data, vec![])
+}
+
+/// Assert the instruction at `index` of the transaction failed with `error`
+pub fn assert_instruction_error(result: Result<(), BanksClientError>, index: u8, error: CardError) {
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(index, InstructionError::Custom(error as u32))
+    );
+}
+
+pub fn assert_card_error(result: Result<(), BanksClientError>, error: CardError) {
+    assert_instruction_error(result, 0, error);
+}
+
+pub async fn set_time(context: &mut ProgramTestContext, unix_timestamp: UnixTimestamp) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+pub async fn now(context: &mut ProgramTestContext) -> UnixTimestamp {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp
+}
+
+pub async fn balance(context: &mut ProgramTestContext, key: &Pubkey) -> u64 {
+    context.banks_client.get_balance(*key).await.unwrap()
+}
+
+pub async fn account_data(context: &mut ProgramTestContext, key: &Pubkey) -> Vec<u8> {
+    context
+        .banks_client
+        .get_account(*key)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+pub async fn token_account_state(context: &mut ProgramTestContext, key: &Pubkey) -> TokenAccount {
+    TokenAccount::unpack(&account_data(context, key).await).unwrap()
+}
+
+pub async fn token_amount(context: &mut ProgramTestContext, key: &Pubkey) -> u64 {
+    token_account_state(context, key).await.amount
+}
+
+pub async fn escrow_state(context: &mut ProgramTestContext, key: &Pubkey) -> Escrow {
+    Escrow::unpack(&account_data(context, key).await).unwrap()
+}
+
+/// Program test paying native SOL escrows from `wallet` to the deposit and fee
+/// collection wallets, at the given default fee
+pub fn native_program_test(wallet: &Pubkey, fee_bps: u16, fixed_fee: u64) -> ProgramTest {
+    let mut program_test = program_test();
+    let rent_exempt = Rent::default().minimum_balance(0);
+    program_test.add_account(deposit::id(), system_account(rent_exempt));
+    program_test.add_account(fee::id(), system_account(rent_exempt));
+    program_test.add_account(*wallet, system_account(10 * LAMPORTS_PER_SOL));
+    program_test.add_account(
+        find_mint_config_program_address(&card::id(), &spl_token::native_mint::id()).0,
+        mint_config_account(
+            &spl_token::native_mint::id(),
+            &deposit::id(),
+            &fee::id(),
+            fee_bps,
+            fixed_fee,
+        ),
+    );
+    program_test
+}
+
+/// Native SOL escrow of a fresh reference, held by its escrow vault
+pub struct NativeEscrow {
+    pub reference: Pubkey,
+    pub escrow: Pubkey,
+    pub vault: Pubkey,
+}
+
+impl NativeEscrow {
+    pub fn new() -> Self {
+        let reference = Pubkey::new_unique();
+        Self {
+            reference,
+            escrow: find_escrow_program_address(&card::id(), &reference).0,
+            vault: find_escrow_vault_program_address(&card::id(), &reference).0,
+        }
+    }
+
+    /// Arguments of an escrow of `amount` without fee, merchant or split
+    pub fn args(&self, amount: u64) -> InitEscrowArgs {
+        InitEscrowArgs {
+            amount,
+            fee_bps: 0,
+            fixed_fee: 0,
+            bump: find_escrow_program_address(&card::id(), &self.reference).1,
+            merchant: None,
+            split: None,
+            hold: false,
+            mcc: 0,
+        }
+    }
+
+    /// `InitEscrow` approved by `authority`, settling to `dst`
+    pub fn init_with(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        wallet: &Keypair,
+        dst: &Pubkey,
+        args: InitEscrowArgs,
+    ) -> Instruction {
+        instruction::init_escrow(
+            &card::id(),
+            &wallet.pubkey(),
+            authority,
+            payer,
+            &self.escrow,
+            &find_program_authority(&card::id()).0,
+            &self.vault,
+            &wallet.pubkey(),
+            dst,
+            &fee::id(),
+            &spl_token::native_mint::id(),
+            &self.reference,
+            args,
+        )
+    }
+
+    /// `InitEscrow` of one SOL to the deposit collection
+    pub fn init(&self, payer: &Pubkey, wallet: &Keypair) -> Instruction {
+        self.init_with(
+            &authority().pubkey(),
+            payer,
+            wallet,
+            &deposit::id(),
+            self.args(LAMPORTS_PER_SOL),
+        )
+    }
+
+    /// `Settle` approved by `authority`, paying `dst`
+    pub fn settle_with(&self, authority: &Pubkey, dst: &Pubkey) -> Instruction {
+        instruction::settle_escrow(
+            &card::id(),
+            authority,
+            dst,
+            &fee::id(),
+            &self.vault,
+            &self.escrow,
+            &spl_token::native_mint::id(),
+            &find_program_authority(&card::id()).0,
+        )
+    }
+
+    pub fn settle(&self) -> Instruction {
+        self.settle_with(&authority().pubkey(), &deposit::id())
+    }
+
+    /// `Cancel` approved by `canceler`, returning the funds to `wallet`
+    pub fn cancel_with(&self, canceler: &Pubkey, wallet: &Keypair) -> Instruction {
+        instruction::cancel_escrow(
+            &card::id(),
+            canceler,
+            &self.escrow,
+            &wallet.pubkey(),
+            &self.vault,
+            &spl_token::native_mint::id(),
+            &find_program_authority(&card::id()).0,
+        )
+    }
+
+    pub fn cancel(&self, wallet: &Keypair) -> Instruction {
+        self.cancel_with(&authority().pubkey(), wallet)
+    }
+
+    /// `Close` approved by `rent_closer`, returning the rent to `payer`
+    pub fn close_with(&self, rent_closer: &Pubkey, payer: &Pubkey) -> Instruction {
+        instruction::close_escrow(&card::id(), rent_closer, &self.escrow, payer)
+    }
+
+    pub fn close(&self, payer: &Pubkey) -> Instruction {
+        self.close_with(&authority().pubkey(), payer)
+    }
+}
+
+/// Token accounts of a fresh mint: the wallet holding the whole supply, a vault of
+/// the program authority and the collection accounts of the mint config
+pub struct TokenSetup {
+    pub context: ProgramTestContext,
+    pub mint: Pubkey,
+    pub wallet_token: Pubkey,
+    pub vault_token: Pubkey,
+    pub dst_token: Pubkey,
+    pub fee_token: Pubkey,
+}
+
+impl TokenSetup {
+    /// Started with a config handing every role to `authority`, the collection
+    /// accounts being spent by `collection_delegate` when given
+    pub async fn start(
+        wallet: &Pubkey,
+        fee_bps: u16,
+        fixed_fee: u64,
+        collection_delegate: Option<&Pubkey>,
+    ) -> Self {
+        let mut program_test = program_test();
+        let mint = Pubkey::new_unique();
+        let wallet_token = Pubkey::new_unique();
+        let vault_token = Pubkey::new_unique();
+        let dst_token = Pubkey::new_unique();
+        let fee_token = Pubkey::new_unique();
+        program_test.add_account(mint, mint_account(INITIAL_AMOUNT));
+        program_test.add_account(
+            wallet_token,
+            token_account(&mint, wallet, INITIAL_AMOUNT, None),
+        );
+        program_test.add_account(
+            vault_token,
+            token_account(&mint, &find_program_authority(&card::id()).0, 0, None),
+        );
+        program_test.add_account(
+            dst_token,
+            token_account(&mint, &deposit::id(), 0, collection_delegate),
+        );
+        program_test.add_account(
+            fee_token,
+            token_account(&mint, &fee::id(), 0, collection_delegate),
+        );
+        program_test.add_account(
+            find_mint_config_program_address(&card::id(), &mint).0,
+            mint_config_account(&mint, &dst_token, &fee_token, fee_bps, fixed_fee),
+        );
+        let mut context = program_test.start_with_context().await;
+        init_config(&mut context, roles(authority().pubkey())).await;
+        Self {
+            context,
+            mint,
+            wallet_token,
+            vault_token,
+            dst_token,
+            fee_token,
+        }
+    }
+}
+
+/// Processes the instruction and returns the transaction fee paid by the payer
+pub async fn process_fee(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> u64 {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    let fee = context
+        .banks_client
+        .get_fee_for_message(transaction.message().clone())
+        .await
+        .unwrap()
+        .unwrap();
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    fee
+}
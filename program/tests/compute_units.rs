@@ -0,0 +1,106 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use card::{
+    find_escrow_program_address, find_program_authority,
+    instruction::{self, InitEscrowArgs},
+};
+use common::*;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const FIXED_FEE: u64 = 10_000;
+const AMOUNT: u64 = 1_000_000;
+/// Upper bound of `InitEscrow`, which creates the escrow and moves the amount and fee
+const INIT_ESCROW_UNITS: u64 = 50_000;
+/// Upper bound of `Settle`, which pays out the amount and the fee from the vault
+const SETTLE_UNITS: u64 = 30_000;
+
+/// Compute units consumed by the transaction of `instruction`
+async fn compute_units(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+    signers: &[&Keypair],
+) -> u64 {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    result.result.unwrap();
+    result.metadata.unwrap().compute_units_consumed
+}
+
+fn init_escrow(setup: &TokenSetup, wallet: &Keypair) -> (Pubkey, Instruction) {
+    let reference = Pubkey::new_unique();
+    let (escrow, bump) = find_escrow_program_address(&card::id(), &reference);
+    let instruction = instruction::init_escrow(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &setup.context.payer.pubkey(),
+        &escrow,
+        &find_program_authority(&card::id()).0,
+        &setup.vault_token,
+        &setup.wallet_token,
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.mint,
+        &reference,
+        InitEscrowArgs {
+            amount: AMOUNT,
+            fee_bps: 0,
+            fixed_fee: FIXED_FEE,
+            bump,
+            merchant: None,
+            split: None,
+            hold: false,
+            mcc: 0,
+        },
+    );
+    (escrow, instruction)
+}
+
+#[tokio::test]
+async fn test_escrow_compute_units() {
+    let wallet = Keypair::new();
+    let mut setup = TokenSetup::start(&wallet.pubkey(), 0, FIXED_FEE, None).await;
+
+    let (escrow, instruction) = init_escrow(&setup, &wallet);
+    let units = compute_units(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert!(
+        units <= INIT_ESCROW_UNITS,
+        "InitEscrow consumed {} compute units",
+        units
+    );
+
+    let instruction = instruction::settle_escrow(
+        &card::id(),
+        &authority().pubkey(),
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.vault_token,
+        &escrow,
+        &setup.mint,
+        &find_program_authority(&card::id()).0,
+    );
+    let units = compute_units(&mut setup.context, instruction, &[&authority()]).await;
+    assert!(
+        units <= SETTLE_UNITS,
+        "Settle consumed {} compute units",
+        units
+    );
+}
@@ -0,0 +1,203 @@
+mod common;
+
+use card::{
+    error::CardError,
+    find_card_program_address, find_deposit_program_address,
+    instruction::{self, BatchDeposit, DepositBatchArgs, InitCardArgs},
+    state::{
+        card::{Card, CardLimits, CardParams},
+        deposit::{Deposit, MAX_BATCH_DEPOSITS},
+    },
+};
+use common::*;
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    packet::PACKET_DATA_SIZE,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const FIXED_FEE: u64 = 10_000;
+const FEE_BPS: u16 = 100;
+const AMOUNT: u64 = 10_000_000;
+/// Default compute budget of an instruction
+const COMPUTE_BUDGET: u64 = 200_000;
+
+async fn setup(wallet: &Keypair) -> TokenSetup {
+    TokenSetup::start(&wallet.pubkey(), 0, FIXED_FEE, None).await
+}
+
+fn deposit_batch(setup: &TokenSetup, wallet: &Keypair, deposits: Vec<BatchDeposit>) -> Instruction {
+    instruction::deposit_batch(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &setup.context.payer.pubkey(),
+        &setup.wallet_token,
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.mint,
+        DepositBatchArgs {
+            fee_bps: FEE_BPS,
+            deposits,
+        },
+    )
+}
+
+fn batch_deposits(count: usize) -> Vec<BatchDeposit> {
+    (0..count)
+        .map(|_| {
+            let key = Pubkey::new_unique();
+            BatchDeposit {
+                amount: AMOUNT,
+                key,
+                bump: find_deposit_program_address(&card::id(), &key).1,
+            }
+        })
+        .collect()
+}
+
+fn signed_transaction(
+    context: &ProgramTestContext,
+    instruction: Instruction,
+    wallet: &Keypair,
+) -> Transaction {
+    Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, wallet, &authority()],
+        context.last_blockhash,
+    )
+}
+
+fn transaction_size(transaction: &Transaction) -> usize {
+    // compact length of the signatures followed by the signatures and the message
+    1 + transaction.signatures.len() * 64 + transaction.message_data().len()
+}
+
+#[tokio::test]
+async fn test_deposit_batch_fits_a_transaction() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let deposits = batch_deposits(MAX_BATCH_DEPOSITS);
+    let keys: Vec<Pubkey> = deposits.iter().map(|deposit| deposit.key).collect();
+    let transaction = signed_transaction(
+        &setup.context,
+        deposit_batch(&setup, &wallet, deposits),
+        &wallet,
+    );
+    assert!(transaction_size(&transaction) <= PACKET_DATA_SIZE);
+    let oversized = signed_transaction(
+        &setup.context,
+        deposit_batch(&setup, &wallet, batch_deposits(MAX_BATCH_DEPOSITS + 1)),
+        &wallet,
+    );
+    assert!(transaction_size(&oversized) > PACKET_DATA_SIZE);
+    // the program refuses a batch past the limit even where a transaction could carry it
+    let result = setup
+        .context
+        .banks_client
+        .process_transaction(oversized)
+        .await;
+    assert_card_error(result, CardError::InvalidInstruction);
+
+    let result = setup
+        .context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    result.result.unwrap();
+    assert!(result.metadata.unwrap().compute_units_consumed <= COMPUTE_BUDGET);
+
+    // one transfer moved the summed amounts and one the summed fees
+    let context = &mut setup.context;
+    let total = AMOUNT * MAX_BATCH_DEPOSITS as u64;
+    let fees = FIXED_FEE * MAX_BATCH_DEPOSITS as u64;
+    assert_eq!(token_amount(context, &setup.dst_token).await, total);
+    assert_eq!(token_amount(context, &setup.fee_token).await, fees);
+    assert_eq!(
+        token_amount(context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - total - fees
+    );
+    for key in keys {
+        let deposit = context
+            .banks_client
+            .get_account(find_deposit_program_address(&card::id(), &key).0)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(deposit.owner, card::id());
+        assert!(Deposit::unpack(&deposit.data).unwrap().is_initialized);
+    }
+}
+
+#[tokio::test]
+async fn test_deposit_batch_is_all_or_nothing() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let instruction = deposit_batch(&setup, &wallet, vec![]);
+    let result = process(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert_card_error(result, CardError::InvalidInstruction);
+
+    let mut deposits = batch_deposits(2);
+    deposits.push(deposits[0].clone());
+    let instruction = deposit_batch(&setup, &wallet, deposits);
+    let result = process(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.wallet_token).await,
+        INITIAL_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn test_deposit_batch_counts_as_one_card_transaction() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let owner = wallet.pubkey();
+    let payer = setup.context.payer.pubkey();
+    let instruction = instruction::init_card(
+        &card::id(),
+        &authority().pubkey(),
+        &payer,
+        InitCardArgs {
+            owner,
+            params: CardParams {
+                funding_token: setup.wallet_token,
+                limits: CardLimits {
+                    per_transaction: 2 * AMOUNT,
+                    daily: u64::MAX,
+                    monthly: u64::MAX,
+                },
+            },
+            bump: find_card_program_address(&card::id(), &owner).1,
+        },
+    );
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+
+    // each deposit is within the limit, their sum isn't
+    let instruction = deposit_batch(&setup, &wallet, batch_deposits(3));
+    let result = process(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert_card_error(result, CardError::TransactionLimitExceeded);
+    let instruction = deposit_batch(&setup, &wallet, batch_deposits(2));
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    let data = account_data(
+        &mut setup.context,
+        &find_card_program_address(&card::id(), &owner).0,
+    )
+    .await;
+    assert_eq!(Card::unpack(&data).unwrap().daily_spent, 2 * AMOUNT);
+}
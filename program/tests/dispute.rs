@@ -0,0 +1,236 @@
+mod common;
+
+use card::{
+    collections::deposit,
+    error::CardError,
+    instruction::{self, DisputeArgs, DisputeOutcome, ResolveDisputeArgs},
+    state::{
+        config::PAUSE_SETTLE,
+        escrow::{DisputeStatus, DISPUTE_WINDOW},
+    },
+};
+use common::*;
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const REASON: u16 = 4837;
+
+async fn setup(wallet: &Keypair) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), 0, 0)
+        .start_with_context()
+        .await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    context
+}
+
+impl NativeEscrow {
+    fn dispute(&self) -> Instruction {
+        instruction::dispute(
+            &card::id(),
+            &authority().pubkey(),
+            &self.escrow,
+            DisputeArgs { reason: REASON },
+        )
+    }
+
+    fn resolve(
+        &self,
+        authority: &Pubkey,
+        outcome: DisputeOutcome,
+        completion: Option<Instruction>,
+    ) -> Instruction {
+        instruction::resolve_dispute(
+            &card::id(),
+            authority,
+            &self.escrow,
+            ResolveDisputeArgs { outcome },
+            completion,
+        )
+    }
+}
+
+#[tokio::test]
+async fn test_dispute_blocks_settlement_until_released() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    process(
+        &mut context,
+        escrow.init(&payer, &wallet),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    process(&mut context, escrow.dispute(), &[&authority()])
+        .await
+        .unwrap();
+
+    let result = process(&mut context, escrow.settle(), &[&authority()]).await;
+    assert_card_error(result, CardError::EscrowDisputed);
+    let result = process(&mut context, escrow.cancel(&wallet), &[&authority()]).await;
+    assert_card_error(result, CardError::EscrowDisputed);
+    let outsider = Keypair::new();
+    let result = process(
+        &mut context,
+        escrow.resolve(
+            &outsider.pubkey(),
+            DisputeOutcome::Release,
+            Some(escrow.settle()),
+        ),
+        &[&outsider, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingCancelerRole);
+
+    // releasing a pending escrow settles it, which a pause of settlements stops
+    let pause = |flags| instruction::set_pause(&card::id(), &authority().pubkey(), flags);
+    process(&mut context, pause(PAUSE_SETTLE), &[&authority()])
+        .await
+        .unwrap();
+    let release = escrow.resolve(
+        &authority().pubkey(),
+        DisputeOutcome::Release,
+        Some(escrow.settle()),
+    );
+    let result = process(&mut context, release.clone(), &[&authority()]).await;
+    assert_card_error(result, CardError::ProgramPaused);
+    process(&mut context, pause(0), &[&authority()])
+        .await
+        .unwrap();
+
+    let deposit_before = balance(&mut context, &deposit::id()).await;
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, release, &[&authority()])
+        .await
+        .unwrap();
+    assert_eq!(
+        balance(&mut context, &deposit::id()).await,
+        deposit_before + LAMPORTS_PER_SOL
+    );
+    let state = escrow_state(&mut context, &escrow.escrow).await;
+    assert!(state.is_settled);
+    assert_eq!(state.dispute_status, DisputeStatus::Released);
+    assert_eq!(state.dispute_reason, REASON);
+    assert!(state.disputed_at > 0 && state.resolved_at >= state.disputed_at);
+
+    // an escrow is only disputed once
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut context, escrow.dispute(), &[&authority()]).await;
+    assert_card_error(result, CardError::DisputeNotAllowed);
+}
+
+#[tokio::test]
+async fn test_refunded_dispute_cancels_pending_escrow() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    let wallet_before = balance(&mut context, &wallet.pubkey()).await;
+    process(
+        &mut context,
+        escrow.init(&payer, &wallet),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+
+    let result = process(
+        &mut context,
+        escrow.resolve(
+            &authority().pubkey(),
+            DisputeOutcome::Refund,
+            Some(escrow.cancel(&wallet)),
+        ),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::EscrowNotDisputed);
+    process(&mut context, escrow.dispute(), &[&authority()])
+        .await
+        .unwrap();
+    // the completing instruction has to be about the disputed escrow
+    let other = NativeEscrow::new();
+    let result = process(
+        &mut context,
+        escrow.resolve(
+            &authority().pubkey(),
+            DisputeOutcome::Refund,
+            Some(other.cancel(&wallet)),
+        ),
+        &[&authority()],
+    )
+    .await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(
+        &mut context,
+        escrow.resolve(
+            &authority().pubkey(),
+            DisputeOutcome::Refund,
+            Some(escrow.cancel(&wallet)),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(balance(&mut context, &wallet.pubkey()).await, wallet_before);
+    let state = escrow_state(&mut context, &escrow.escrow).await;
+    assert!(state.is_canceled);
+    assert_eq!(state.dispute_status, DisputeStatus::Refunded);
+}
+
+#[tokio::test]
+async fn test_settled_escrow_dispute_window() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let disputed = NativeEscrow::new();
+    let late = NativeEscrow::new();
+    for escrow in [&disputed, &late] {
+        process(
+            &mut context,
+            escrow.init(&payer, &wallet),
+            &[&wallet, &authority()],
+        )
+        .await
+        .unwrap();
+        process(&mut context, escrow.settle(), &[&authority()])
+            .await
+            .unwrap();
+    }
+
+    process(&mut context, disputed.dispute(), &[&authority()])
+        .await
+        .unwrap();
+    let result = process(&mut context, disputed.close(&payer), &[&authority()]).await;
+    assert_card_error(result, CardError::EscrowDisputed);
+    process(
+        &mut context,
+        disputed.resolve(&authority().pubkey(), DisputeOutcome::Release, None),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, disputed.close(&payer), &[&authority()])
+        .await
+        .unwrap();
+
+    let now = now(&mut context).await;
+    set_time(&mut context, now + DISPUTE_WINDOW + 1).await;
+    let result = process(&mut context, late.dispute(), &[&authority()]).await;
+    assert_card_error(result, CardError::DisputeNotAllowed);
+}
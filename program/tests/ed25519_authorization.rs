@@ -0,0 +1,240 @@
+mod common;
+
+use borsh::BorshSerialize;
+use card::{
+    collections::deposit,
+    error::CardError,
+    instruction::{self, Authorization, AuthorizationAction},
+    state::escrow::{Payee, ShareKind, Split, MAX_PAYEES},
+};
+use common::*;
+use solana_program::{hash::Hash, instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+const AMOUNT: u64 = LAMPORTS_PER_SOL;
+
+async fn setup(wallet: &Keypair) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), 0, 0)
+        .start_with_context()
+        .await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    context
+}
+
+/// The card instruction follows the ed25519 one
+fn assert_card_error(result: Result<(), BanksClientError>, error: CardError) {
+    assert_instruction_error(result, 1, error);
+}
+
+impl NativeEscrow {
+    fn authorization(&self, action: AuthorizationAction, expiry: i64) -> Authorization {
+        let args_hash = match action {
+            AuthorizationAction::InitEscrow => {
+                Authorization::hash_args(&self.args(AMOUNT)).unwrap()
+            }
+            _ => Hash::default(),
+        };
+        Authorization {
+            program_id: card::id(),
+            reference: self.reference,
+            amount: AMOUNT,
+            action,
+            expiry,
+            args_hash,
+        }
+    }
+
+    fn init_authorized(&self, payer: &Pubkey, wallet: &Keypair) -> Instruction {
+        instruction::with_ed25519_authorization(self.init(payer, wallet), &authority().pubkey())
+    }
+
+    fn settle_authorized(&self) -> Instruction {
+        instruction::with_ed25519_authorization(self.settle(), &authority().pubkey())
+    }
+
+    fn cancel_authorized(&self, wallet: &Keypair) -> Instruction {
+        instruction::with_ed25519_authorization(self.cancel(wallet), &authority().pubkey())
+    }
+}
+
+#[tokio::test]
+async fn test_escrow_authorized_off_chain() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let expiry = now(&mut context).await + 60;
+    let escrow = NativeEscrow::new();
+
+    process_all(
+        &mut context,
+        &[
+            ed25519_authorization(
+                &authority(),
+                &escrow.authorization(AuthorizationAction::InitEscrow, expiry),
+            ),
+            escrow.init_authorized(&payer, &wallet),
+        ],
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+    let deposit_before = context
+        .banks_client
+        .get_balance(deposit::id())
+        .await
+        .unwrap();
+    process_all(
+        &mut context,
+        &[
+            ed25519_authorization(
+                &authority(),
+                &escrow.authorization(AuthorizationAction::Settle, expiry),
+            ),
+            escrow.settle_authorized(),
+        ],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(deposit::id())
+            .await
+            .unwrap(),
+        deposit_before + AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn test_authorization_rejected() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let now = now(&mut context).await;
+    let escrow = NativeEscrow::new();
+
+    let expired = escrow.authorization(AuthorizationAction::InitEscrow, now - 1);
+    let result = process_all(
+        &mut context,
+        &[
+            ed25519_authorization(&authority(), &expired),
+            escrow.init_authorized(&payer, &wallet),
+        ],
+        &[&wallet],
+    )
+    .await;
+    assert_card_error(result, CardError::AuthorizationExpired);
+
+    let forged = escrow.authorization(AuthorizationAction::InitEscrow, now + 60);
+    let result = process_all(
+        &mut context,
+        &[
+            ed25519_authorization(&Keypair::new(), &forged),
+            escrow.init_authorized(&payer, &wallet),
+        ],
+        &[&wallet],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidAuthorization);
+
+    // a signature over the bare encoding isn't an authorization
+    let undomained = escrow.authorization(AuthorizationAction::InitEscrow, now + 60);
+    let result = process_all(
+        &mut context,
+        &[
+            ed25519_signature(&authority(), &undomained.try_to_vec().unwrap()),
+            escrow.init_authorized(&payer, &wallet),
+        ],
+        &[&wallet],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidAuthorization);
+
+    // the authority's approval for another program doesn't carry over
+    let mut elsewhere = escrow.authorization(AuthorizationAction::InitEscrow, now + 60);
+    elsewhere.program_id = Pubkey::new_unique();
+    let result = process_all(
+        &mut context,
+        &[
+            ed25519_authorization(&authority(), &elsewhere),
+            escrow.init_authorized(&payer, &wallet),
+        ],
+        &[&wallet],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidAuthorization);
+
+    process_all(
+        &mut context,
+        &[
+            ed25519_authorization(
+                &authority(),
+                &escrow.authorization(AuthorizationAction::InitEscrow, now + 60),
+            ),
+            escrow.init_authorized(&payer, &wallet),
+        ],
+        &[&wallet],
+    )
+    .await
+    .unwrap();
+    // a settlement approval doesn't approve canceling
+    let result = process_all(
+        &mut context,
+        &[
+            ed25519_authorization(
+                &authority(),
+                &escrow.authorization(AuthorizationAction::Settle, now + 60),
+            ),
+            escrow.cancel_authorized(&wallet),
+        ],
+        &[],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidAuthorization);
+}
+
+#[tokio::test]
+async fn test_authorization_binds_escrow_terms() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let expiry = now(&mut context).await + 60;
+    let escrow = NativeEscrow::new();
+    let payee = Pubkey::new_unique();
+
+    // the approved escrow pays the deposit collection, the submitted one a split
+    let mut payees = [Payee::default(); MAX_PAYEES];
+    payees[0] = Payee {
+        token: payee,
+        kind: ShareKind::Bps,
+        share: 10_000,
+    };
+    let mut args = escrow.args(AMOUNT);
+    args.split = Some(Split {
+        payee_count: 1,
+        payees,
+        dust_payee: 0,
+    });
+    let tampered = instruction::with_ed25519_authorization(
+        escrow.init_with(&authority().pubkey(), &payer, &wallet, &deposit::id(), args),
+        &authority().pubkey(),
+    );
+    let result = process_all(
+        &mut context,
+        &[
+            ed25519_authorization(
+                &authority(),
+                &escrow.authorization(AuthorizationAction::InitEscrow, expiry),
+            ),
+            tampered,
+        ],
+        &[&wallet],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidAuthorization);
+}
@@ -0,0 +1,114 @@
+use borsh::BorshSerialize;
+use bytemuck::{bytes_of, bytes_of_mut};
+use card::state::escrow::{
+    DisputeStatus, Escrow, Payee, PodEscrow, ShareKind, Split, ESCROW_DATA_SIZE, MAX_PAYEES,
+};
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+use std::mem::size_of;
+
+/// Escrow with every field set apart from its default
+fn populated_escrow() -> Escrow {
+    let mut payees = [Payee::default(); MAX_PAYEES];
+    payees[0] = Payee {
+        token: Pubkey::new_unique(),
+        kind: ShareKind::Amount,
+        share: 1_000,
+    };
+    payees[1] = Payee {
+        token: Pubkey::new_unique(),
+        kind: ShareKind::Bps,
+        share: 10_000,
+    };
+    Escrow {
+        is_initialized: true,
+        is_settled: true,
+        is_canceled: false,
+        amount: 5_000,
+        fee_bps: 250,
+        fixed_fee: 30,
+        src_token: Pubkey::new_unique(),
+        dst_token: Pubkey::new_unique(),
+        vault_token: Pubkey::new_unique(),
+        fee_token: Pubkey::new_unique(),
+        mint: Pubkey::new_unique(),
+        reference: Pubkey::new_unique(),
+        fee: 155,
+        merchant: Pubkey::new_unique(),
+        split: Split {
+            payee_count: 2,
+            payees,
+            dust_payee: 1,
+        },
+        refunded_amount: 1_200,
+        refunded_fee: 37,
+        dispute_status: DisputeStatus::Refunded,
+        settled_at: 1_700_000_000,
+        disputed_at: 1_700_086_400,
+        dispute_reason: 4853,
+        resolved_at: -1,
+        is_hold: true,
+        authority_bump: 254,
+        vault_bump: 253,
+        card: Pubkey::new_unique(),
+        charged_at: 1_699_999_000,
+    }
+}
+
+#[test]
+fn test_pod_escrow_matches_borsh_layout() {
+    assert_eq!(size_of::<PodEscrow>(), ESCROW_DATA_SIZE);
+    assert_eq!(Escrow::LEN, ESCROW_DATA_SIZE);
+
+    let escrow = populated_escrow();
+    let pod = PodEscrow::from(&escrow);
+    assert_eq!(escrow.try_to_vec().unwrap(), bytes_of(&pod));
+
+    let mut data = vec![0; Escrow::LEN];
+    Escrow::pack(escrow.clone(), &mut data).unwrap();
+    assert_eq!(Escrow::unpack(&data).unwrap(), escrow);
+
+    // fields updated in place are what unpacking reads back
+    let view = PodEscrow::load_mut(&mut data).unwrap();
+    view.refunded_amount = 5_000.into();
+    view.dispute_status = DisputeStatus::Disputed as u8;
+    let unpacked = Escrow::unpack(&data).unwrap();
+    assert_eq!(unpacked.refunded_amount, 5_000);
+    assert_eq!(unpacked.dispute_status, DisputeStatus::Disputed);
+}
+
+#[test]
+fn test_pod_escrow_rejects_invalid_data() {
+    let escrow = populated_escrow();
+
+    let mut pod = PodEscrow::from(&escrow);
+    pod.dispute_status = 4;
+    assert_eq!(
+        Escrow::unpack(bytes_of(&pod)).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+
+    let mut pod = PodEscrow::from(&escrow);
+    pod.split.payees[0].kind = 2;
+    assert_eq!(
+        Escrow::unpack(bytes_of(&pod)).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+
+    let mut data = bytes_of(&PodEscrow::from(&escrow)).to_vec();
+    data.push(0);
+    assert_eq!(
+        PodEscrow::load(&data).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+    assert_eq!(
+        PodEscrow::load(&data[..ESCROW_DATA_SIZE - 1]).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+
+    let mut pod = PodEscrow::from(&escrow);
+    pod.is_initialized = false.into();
+    assert_eq!(
+        PodEscrow::load_mut(bytes_of_mut(&mut pod)).unwrap_err(),
+        ProgramError::UninitializedAccount
+    );
+}
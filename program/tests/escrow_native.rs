@@ -0,0 +1,167 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    utils::calculate_fee,
+};
+use common::*;
+use solana_program::{instruction::Instruction, pubkey::Pubkey, rent::Rent};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+const FEE_BPS: u16 = 250;
+const FIXED_FEE: u64 = 5_000;
+
+fn total_fee(amount: u64) -> u64 {
+    calculate_fee(amount, FEE_BPS as u64).unwrap() + FIXED_FEE
+}
+
+async fn setup(wallet: &Keypair) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), FEE_BPS, FIXED_FEE)
+        .start_with_context()
+        .await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    context
+}
+
+async fn balances(context: &mut ProgramTestContext, keys: &[Pubkey]) -> Vec<u64> {
+    let mut balances = vec![];
+    for key in keys {
+        balances.push(balance(context, key).await);
+    }
+    balances
+}
+
+impl NativeEscrow {
+    fn init_fee(&self, payer: &Pubkey, wallet: &Keypair, amount: u64) -> Instruction {
+        let mut args = self.args(amount);
+        args.fee_bps = FEE_BPS;
+        args.fixed_fee = FIXED_FEE;
+        self.init_with(&authority().pubkey(), payer, wallet, &deposit::id(), args)
+    }
+}
+
+#[tokio::test]
+async fn test_native_escrow_settle_conserves_lamports() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    let amount = LAMPORTS_PER_SOL;
+    let keys = [
+        wallet.pubkey(),
+        payer,
+        deposit::id(),
+        fee::id(),
+        escrow.escrow,
+        escrow.vault,
+    ];
+    let before = balances(&mut context, &keys).await;
+
+    let mut tx_fees = process_fee(
+        &mut context,
+        escrow.init_fee(&payer, &wallet, amount),
+        &[&wallet, &authority()],
+    )
+    .await;
+    let funded = balances(&mut context, &keys).await;
+    let reserve = Rent::default().minimum_balance(0);
+    assert_eq!(funded[0], before[0] - amount - total_fee(amount));
+    assert_eq!(funded[5], amount + total_fee(amount) + reserve);
+    assert_eq!(
+        funded.iter().sum::<u64>() + tx_fees,
+        before.iter().sum::<u64>()
+    );
+
+    tx_fees += process_fee(&mut context, escrow.settle(), &[&authority()]).await;
+    let settled = balances(&mut context, &keys).await;
+    assert_eq!(settled[2], before[2] + amount);
+    assert_eq!(settled[3], before[3] + total_fee(amount));
+    assert_eq!(settled[5], 0);
+    assert_eq!(
+        settled.iter().sum::<u64>() + tx_fees,
+        before.iter().sum::<u64>()
+    );
+
+    tx_fees += process_fee(&mut context, escrow.close(&payer), &[&authority()]).await;
+    let closed = balances(&mut context, &keys).await;
+    assert_eq!(closed[1], before[1] - tx_fees);
+    assert_eq!(closed[4], 0);
+    assert_eq!(closed[5], 0);
+    assert_eq!(
+        closed.iter().sum::<u64>() + tx_fees,
+        before.iter().sum::<u64>()
+    );
+}
+
+#[tokio::test]
+async fn test_native_escrow_cancel_refunds_everything() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    let amount = LAMPORTS_PER_SOL / 2;
+    let keys = [
+        wallet.pubkey(),
+        payer,
+        deposit::id(),
+        fee::id(),
+        escrow.escrow,
+        escrow.vault,
+    ];
+    let before = balances(&mut context, &keys).await;
+
+    let mut tx_fees = process_fee(
+        &mut context,
+        escrow.init_fee(&payer, &wallet, amount),
+        &[&wallet, &authority()],
+    )
+    .await;
+    tx_fees += process_fee(&mut context, escrow.cancel(&wallet), &[&authority()]).await;
+    tx_fees += process_fee(&mut context, escrow.close(&payer), &[&authority()]).await;
+
+    let after = balances(&mut context, &keys).await;
+    assert_eq!(after[0], before[0]);
+    assert_eq!(after[1], before[1] - tx_fees);
+    assert_eq!(after[2], before[2]);
+    assert_eq!(after[3], before[3]);
+    assert_eq!(after[4], 0);
+    assert_eq!(after[5], 0);
+}
+
+#[tokio::test]
+async fn test_native_escrows_below_rent_minimum_use_separate_vaults() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let first = NativeEscrow::new();
+    let second = NativeEscrow::new();
+    let amount = 1_000;
+    let reserve = Rent::default().minimum_balance(0);
+
+    process_fee(
+        &mut context,
+        first.init_fee(&payer, &wallet, amount),
+        &[&wallet, &authority()],
+    )
+    .await;
+    process_fee(
+        &mut context,
+        second.init_fee(&payer, &wallet, amount),
+        &[&wallet, &authority()],
+    )
+    .await;
+    let funded = balances(&mut context, &[first.vault, second.vault]).await;
+    assert_eq!(funded, vec![amount + total_fee(amount) + reserve; 2]);
+
+    process_fee(&mut context, first.settle(), &[&authority()]).await;
+    let after = balances(&mut context, &[first.vault, second.vault]).await;
+    assert_eq!(after, vec![0, amount + total_fee(amount) + reserve]);
+
+    process_fee(&mut context, second.cancel(&wallet), &[&authority()]).await;
+    let after = balances(&mut context, &[first.vault, second.vault]).await;
+    assert_eq!(after, vec![0, 0]);
+}
@@ -0,0 +1,234 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_fee_schedule_program_address, find_mint_config_program_address,
+    instruction::{self, InitFeeScheduleArgs, InitMintConfigArgs},
+    state::{
+        fee_schedule::{FeeRule, FeeRules, FeeTier, MAX_FEE_TIERS},
+        mint_config::MintParams,
+    },
+};
+use common::*;
+use solana_program::rent::Rent;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+/// Escrow fee of 1% below 1 SOL and 0.5% from there on, plus 1_000 lamports,
+/// never below 2_000 lamports nor above 0.1 SOL
+fn escrow_rule() -> FeeRule {
+    let mut tiers = [FeeTier::default(); MAX_FEE_TIERS];
+    tiers[0] = FeeTier {
+        min_amount: 0,
+        fee_bps: 100,
+    };
+    tiers[1] = FeeTier {
+        min_amount: LAMPORTS_PER_SOL,
+        fee_bps: 50,
+    };
+    FeeRule {
+        tier_count: 2,
+        tiers,
+        fixed_fee: 1_000,
+        min_fee: 2_000,
+        max_fee: LAMPORTS_PER_SOL / 10,
+    }
+}
+
+fn rules(allow_override: bool) -> FeeRules {
+    FeeRules {
+        allow_override,
+        deposit: FeeRule::default(),
+        withdrawal: FeeRule::default(),
+        escrow: escrow_rule(),
+    }
+}
+
+/// Starts with native SOL supported at a 2.5% default fee, scheduled by `rules` if any
+async fn setup(wallet: &Keypair, rules: Option<FeeRules>) -> ProgramTestContext {
+    let mut program_test = program_test();
+    let rent_exempt = Rent::default().minimum_balance(0);
+    program_test.add_account(deposit::id(), system_account(rent_exempt));
+    program_test.add_account(fee::id(), system_account(rent_exempt));
+    program_test.add_account(wallet.pubkey(), system_account(100 * LAMPORTS_PER_SOL));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let key = authority().pubkey();
+    init_config(&mut context, roles(key)).await;
+    let mint = spl_token::native_mint::id();
+    process(
+        &mut context,
+        instruction::init_mint_config(
+            &card::id(),
+            &key,
+            &payer,
+            &mint,
+            InitMintConfigArgs {
+                params: MintParams {
+                    enabled: true,
+                    min_amount: 0,
+                    max_amount: u64::MAX,
+                    fee_bps: 250,
+                    fixed_fee: 0,
+                    deposit_collection: deposit::id(),
+                    fee_collection: fee::id(),
+                },
+                bump: find_mint_config_program_address(&card::id(), &mint).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let rules = match rules {
+        Some(rules) => rules,
+        None => return context,
+    };
+    process(
+        &mut context,
+        instruction::init_fee_schedule(
+            &card::id(),
+            &key,
+            &payer,
+            &mint,
+            InitFeeScheduleArgs {
+                rules,
+                bump: find_fee_schedule_program_address(&card::id(), &mint).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    context
+}
+
+/// Creates a native escrow of `amount` asking for `fee_bps`, returns the fee it charges
+async fn escrow_fee(
+    context: &mut ProgramTestContext,
+    wallet: &Keypair,
+    amount: u64,
+    fee_bps: u16,
+) -> u64 {
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    let mut args = escrow.args(amount);
+    args.fee_bps = fee_bps;
+    process(
+        context,
+        escrow.init_with(&authority().pubkey(), &payer, wallet, &deposit::id(), args),
+        &[wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    escrow_state(context, &escrow.escrow).await.fee
+}
+
+#[tokio::test]
+async fn test_escrow_fee_follows_schedule() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet, Some(rules(false))).await;
+
+    // the caller's fee is ignored without override
+    assert_eq!(
+        escrow_fee(&mut context, &wallet, LAMPORTS_PER_SOL / 2, 0).await,
+        5_000_000 + 1_000
+    );
+    assert_eq!(
+        escrow_fee(&mut context, &wallet, 2 * LAMPORTS_PER_SOL, 10000).await,
+        10_000_000 + 1_000
+    );
+    assert_eq!(escrow_fee(&mut context, &wallet, 1_000, 0).await, 2_000);
+    assert_eq!(
+        escrow_fee(&mut context, &wallet, 50 * LAMPORTS_PER_SOL, 0).await,
+        LAMPORTS_PER_SOL / 10
+    );
+}
+
+#[tokio::test]
+async fn test_caller_may_only_lower_the_fee() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet, Some(rules(true))).await;
+
+    assert_eq!(
+        escrow_fee(&mut context, &wallet, LAMPORTS_PER_SOL / 2, 10).await,
+        500_000
+    );
+    assert_eq!(
+        escrow_fee(&mut context, &wallet, LAMPORTS_PER_SOL / 2, 10000).await,
+        5_000_000 + 1_000
+    );
+}
+
+#[tokio::test]
+async fn test_default_fee_without_schedule() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet, None).await;
+
+    // the caller can't lower the mint's default fee either
+    assert_eq!(
+        escrow_fee(&mut context, &wallet, LAMPORTS_PER_SOL / 2, 0).await,
+        12_500_000
+    );
+}
+
+#[tokio::test]
+async fn test_fee_schedule_cannot_be_omitted() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet, Some(rules(false))).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    let mut args = escrow.args(LAMPORTS_PER_SOL / 2);
+    args.fee_bps = 0;
+
+    let mut instruction =
+        escrow.init_with(&authority().pubkey(), &payer, &wallet, &deposit::id(), args);
+    let fee_schedule =
+        find_fee_schedule_program_address(&card::id(), &spl_token::native_mint::id()).0;
+    instruction
+        .accounts
+        .retain(|account| account.pubkey != fee_schedule);
+    let result = process(&mut context, instruction, &[&wallet, &authority()]).await;
+    assert_card_error(result, CardError::InvalidFeeSchedule);
+}
+
+#[tokio::test]
+async fn test_set_fee_schedule() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet, Some(rules(false))).await;
+    let mint = spl_token::native_mint::id();
+
+    let mut unsorted = rules(false);
+    unsorted.escrow.tiers.swap(0, 1);
+    let result = process(
+        &mut context,
+        instruction::set_fee_schedule(&card::id(), &authority().pubkey(), &mint, unsorted),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidFeeSchedule);
+    let outsider = Keypair::new();
+    let result = process(
+        &mut context,
+        instruction::set_fee_schedule(&card::id(), &outsider.pubkey(), &mint, rules(true)),
+        &[&outsider],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+
+    process(
+        &mut context,
+        instruction::set_fee_schedule(&card::id(), &authority().pubkey(), &mint, rules(true)),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        escrow_fee(&mut context, &wallet, LAMPORTS_PER_SOL / 2, 0).await,
+        0
+    );
+}
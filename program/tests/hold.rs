@@ -0,0 +1,233 @@
+mod common;
+
+use card::{
+    error::CardError,
+    find_escrow_program_address, find_program_authority,
+    instruction::{self, DisputeArgs, DisputeOutcome, InitEscrowArgs, ResolveDisputeArgs},
+};
+use common::*;
+use solana_program::{
+    instruction::{Instruction, InstructionError},
+    program_option::COption,
+    pubkey::Pubkey,
+};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const FIXED_FEE: u64 = 10_000;
+
+async fn setup(wallet: &Keypair) -> TokenSetup {
+    TokenSetup::start(&wallet.pubkey(), 0, FIXED_FEE, None).await
+}
+
+/// `InitEscrow` of a hold on the wallet token account, the vault being the source itself
+fn init_hold(setup: &TokenSetup, wallet: &Keypair, reference: &Pubkey, amount: u64) -> Instruction {
+    let (escrow, bump) = find_escrow_program_address(&card::id(), reference);
+    instruction::init_escrow(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &setup.context.payer.pubkey(),
+        &escrow,
+        &find_program_authority(&card::id()).0,
+        &setup.wallet_token,
+        &setup.wallet_token,
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.mint,
+        reference,
+        InitEscrowArgs {
+            amount,
+            fee_bps: 0,
+            fixed_fee: FIXED_FEE,
+            bump,
+            merchant: None,
+            split: None,
+            hold: true,
+            mcc: 0,
+        },
+    )
+}
+
+#[tokio::test]
+async fn test_hold_keeps_funds_in_source_until_settled() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let authority_key = find_program_authority(&card::id()).0;
+    let amount = 1_000_000;
+    let reference = Pubkey::new_unique();
+    let instruction = init_hold(&setup, &wallet, &reference, amount);
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    let wallet_token = token_account_state(&mut setup.context, &setup.wallet_token).await;
+    assert_eq!(wallet_token.amount, INITIAL_AMOUNT);
+    assert_eq!(wallet_token.delegate, COption::Some(authority_key));
+    assert_eq!(wallet_token.delegated_amount, amount + FIXED_FEE);
+
+    let (escrow, _) = find_escrow_program_address(&card::id(), &reference);
+    let settle = instruction::settle_escrow(
+        &card::id(),
+        &authority().pubkey(),
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.wallet_token,
+        &escrow,
+        &setup.mint,
+        &authority_key,
+    );
+    process(&mut setup.context, settle, &[&authority()])
+        .await
+        .unwrap();
+    let context = &mut setup.context;
+    let wallet_token = token_account_state(context, &setup.wallet_token).await;
+    assert_eq!(wallet_token.amount, INITIAL_AMOUNT - amount - FIXED_FEE);
+    assert_eq!(wallet_token.delegated_amount, 0);
+    assert_eq!(
+        token_account_state(context, &setup.dst_token).await.amount,
+        amount
+    );
+    assert_eq!(
+        token_account_state(context, &setup.fee_token).await.amount,
+        FIXED_FEE
+    );
+}
+
+#[tokio::test]
+async fn test_canceled_hold_comes_off_the_allowance() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let authority_key = find_program_authority(&card::id()).0;
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    let instruction = init_hold(&setup, &wallet, &first, 1_000_000);
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    let instruction = init_hold(&setup, &wallet, &second, 2_000_000);
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    let wallet_token = token_account_state(&mut setup.context, &setup.wallet_token).await;
+    assert_eq!(wallet_token.delegated_amount, 3_000_000 + 2 * FIXED_FEE);
+
+    let (wallet_token, mint) = (setup.wallet_token, setup.mint);
+    let cancel_unsigned = |reference: &Pubkey| {
+        instruction::cancel_escrow(
+            &card::id(),
+            &authority().pubkey(),
+            &find_escrow_program_address(&card::id(), reference).0,
+            &wallet_token,
+            &wallet_token,
+            &mint,
+            &authority_key,
+        )
+    };
+    let cancel = |reference: &Pubkey| {
+        instruction::with_source_owner(cancel_unsigned(reference), &wallet.pubkey())
+    };
+
+    // the canceler releases a hold without the owner, whose allowance then stands
+    let instruction = cancel_unsigned(&first);
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+    let wallet_token = token_account_state(&mut setup.context, &setup.wallet_token).await;
+    assert_eq!(wallet_token.amount, INITIAL_AMOUNT);
+    assert_eq!(wallet_token.delegated_amount, 3_000_000 + 2 * FIXED_FEE);
+    let state = escrow_state(
+        &mut setup.context,
+        &find_escrow_program_address(&card::id(), &first).0,
+    )
+    .await;
+    assert!(state.is_canceled);
+
+    // signed by the owner, the hold comes off the allowance
+
+    let instruction = cancel(&second);
+    process(&mut setup.context, instruction, &[&authority(), &wallet])
+        .await
+        .unwrap();
+    let wallet_token = token_account_state(&mut setup.context, &setup.wallet_token).await;
+    assert_eq!(wallet_token.amount, INITIAL_AMOUNT);
+    assert_eq!(wallet_token.delegate, COption::Some(authority_key));
+    assert_eq!(wallet_token.delegated_amount, 1_000_000 + FIXED_FEE);
+}
+
+#[tokio::test]
+async fn test_disputed_hold_is_refunded_without_the_owner() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let authority_key = find_program_authority(&card::id()).0;
+    let reference = Pubkey::new_unique();
+    let instruction = init_hold(&setup, &wallet, &reference, 1_000_000);
+    process(&mut setup.context, instruction, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    let escrow = find_escrow_program_address(&card::id(), &reference).0;
+    process(
+        &mut setup.context,
+        instruction::dispute(
+            &card::id(),
+            &authority().pubkey(),
+            &escrow,
+            DisputeArgs { reason: 0 },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    let cancel = instruction::cancel_escrow(
+        &card::id(),
+        &authority().pubkey(),
+        &escrow,
+        &setup.wallet_token,
+        &setup.wallet_token,
+        &setup.mint,
+        &authority_key,
+    );
+    process(
+        &mut setup.context,
+        instruction::resolve_dispute(
+            &card::id(),
+            &authority().pubkey(),
+            &escrow,
+            ResolveDisputeArgs {
+                outcome: DisputeOutcome::Refund,
+            },
+            Some(cancel),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    assert!(escrow_state(&mut setup.context, &escrow).await.is_canceled);
+    let wallet_token = token_account_state(&mut setup.context, &setup.wallet_token).await;
+    assert_eq!(wallet_token.amount, INITIAL_AMOUNT);
+}
+
+#[tokio::test]
+async fn test_hold_requires_spl_token_and_balance() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let reference = Pubkey::new_unique();
+    let instruction = init_hold(&setup, &wallet, &reference, INITIAL_AMOUNT);
+    let result = process(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InsufficientFunds)
+    );
+
+    let mut instruction = init_hold(&setup, &wallet, &reference, 1_000_000);
+    let native_mint = spl_token::native_mint::id();
+    for account in instruction.accounts.iter_mut() {
+        if account.pubkey == setup.mint {
+            account.pubkey = native_mint;
+        }
+    }
+    let result = process(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert_card_error(result, CardError::HoldNotSupported);
+}
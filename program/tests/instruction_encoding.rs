@@ -0,0 +1,173 @@
+mod common;
+
+use borsh::BorshSerialize;
+use card::{
+    error::CardError,
+    instruction::{
+        BatchDeposit, CardInstruction, ChargeMandateArgs, DepositBatchArgs, InitEscrowArgs,
+        RefundArgs, SetPauseArgs,
+    },
+    state::escrow::{Payee, ShareKind, Split, MAX_PAYEES},
+};
+use common::*;
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+#[test]
+fn test_discriminators_are_stable() {
+    let instructions = [
+        (CardInstruction::Settle, 3),
+        (CardInstruction::Cancel, 4),
+        (CardInstruction::Close, 5),
+        (
+            CardInstruction::SetPause(SetPauseArgs { paused_flags: 1 }),
+            10,
+        ),
+        (
+            CardInstruction::Refund(RefundArgs {
+                amount: 1,
+                refund_fee: true,
+                refunded_amount: 0,
+            }),
+            18,
+        ),
+        (CardInstruction::FreezeCard, 23),
+        (
+            CardInstruction::ChargeMandate(ChargeMandateArgs { amount: 7 }),
+            27,
+        ),
+        (CardInstruction::CancelPayout, 31),
+    ];
+    for (instruction, discriminator) in instructions {
+        assert_eq!(instruction.discriminator(), discriminator);
+        let data = instruction.try_to_vec().unwrap();
+        assert_eq!(data[0], discriminator);
+        assert_eq!(CardInstruction::unpack(&data).unwrap(), instruction);
+    }
+}
+
+#[test]
+fn test_init_escrow_args_read_appended_fields_as_default() {
+    // arguments as encoded by clients predating merchants, splits, holds and categories
+    let mut data = vec![CardInstruction::INIT_ESCROW];
+    data.extend_from_slice(&1_000u64.to_le_bytes());
+    data.extend_from_slice(&150u16.to_le_bytes());
+    data.extend_from_slice(&20u64.to_le_bytes());
+    data.push(254);
+    assert_eq!(
+        CardInstruction::unpack(&data).unwrap(),
+        CardInstruction::InitEscrow(InitEscrowArgs {
+            amount: 1_000,
+            fee_bps: 150,
+            fixed_fee: 20,
+            bump: 254,
+            merchant: None,
+            split: None,
+            hold: false,
+            mcc: 0,
+        })
+    );
+
+    let mut payees = [Payee::default(); MAX_PAYEES];
+    payees[0] = Payee {
+        token: Pubkey::new_unique(),
+        kind: ShareKind::Bps,
+        share: 10_000,
+    };
+    let instruction = CardInstruction::InitEscrow(InitEscrowArgs {
+        amount: 1_000,
+        fee_bps: 150,
+        fixed_fee: 20,
+        bump: 254,
+        merchant: Some(Pubkey::new_unique()),
+        split: Some(Split {
+            payee_count: 1,
+            payees,
+            dust_payee: 0,
+        }),
+        hold: true,
+        mcc: 5411,
+    });
+    let mut data = instruction.try_to_vec().unwrap();
+    assert_eq!(CardInstruction::unpack(&data).unwrap(), instruction);
+
+    data.push(0);
+    assert_eq!(
+        CardInstruction::unpack(&data).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+}
+
+#[test]
+fn test_deposit_batch_args_end_the_instruction_data() {
+    let instruction = CardInstruction::InitDepositBatch(DepositBatchArgs {
+        fee_bps: 150,
+        deposits: vec![
+            BatchDeposit {
+                amount: 1_000,
+                key: Pubkey::new_unique(),
+                bump: 254,
+            },
+            BatchDeposit {
+                amount: 2_000,
+                key: Pubkey::new_unique(),
+                bump: 253,
+            },
+        ],
+    });
+    let mut data = instruction.try_to_vec().unwrap();
+    assert_eq!(CardInstruction::unpack(&data).unwrap(), instruction);
+
+    // bytes past the last field are not read as a field appended later
+    data.push(0);
+    assert_eq!(
+        CardInstruction::unpack(&data).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+
+    // the fields of the first release are still required
+    data.truncate(data.len() - 2);
+    assert_eq!(
+        CardInstruction::unpack(&data).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+}
+
+#[test]
+fn test_unpack_rejects_invalid_data() {
+    assert_eq!(
+        CardInstruction::unpack(&[]).unwrap_err(),
+        CardError::InvalidInstruction.into()
+    );
+    assert_eq!(
+        CardInstruction::unpack(&[36]).unwrap_err(),
+        CardError::InvalidInstruction.into()
+    );
+    assert_eq!(
+        CardInstruction::unpack(&[CardInstruction::SETTLE, 0]).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    assert_eq!(
+        CardInstruction::unpack(&[CardInstruction::CHARGE_MANDATE, 1, 2]).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+    let mut data = vec![CardInstruction::SET_PAUSE];
+    data.extend_from_slice(&1u64.to_le_bytes());
+    data.push(0);
+    assert_eq!(
+        CardInstruction::unpack(&data).unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_instruction_fails() {
+    let mut context = program_test().start_with_context().await;
+
+    let result = process(
+        &mut context,
+        Instruction::new_with_bytes(card::id(), &[u8::MAX], vec![]),
+        &[],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidInstruction);
+}
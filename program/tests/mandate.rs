@@ -0,0 +1,454 @@
+mod common;
+
+use card::{
+    error::CardError,
+    find_card_program_address, find_escrow_program_address, find_mandate_delegate_program_address,
+    find_mandate_program_address, find_merchant_program_address, find_program_authority,
+    instruction::{
+        self, ChargeMandateArgs, InitCardArgs, InitEscrowArgs, InitMandateArgs, OnboardMerchantArgs,
+    },
+    state::{
+        card::{
+            CardLimits, CardParams, CategoryMode, MccRange, MerchantCategories, MAX_MCC_RANGES,
+        },
+        mandate::{Mandate, MandateTerms},
+        merchant::{MerchantParams, Settlement, MAX_MERCHANT_MINTS},
+    },
+};
+use common::*;
+use solana_program::{
+    clock::UnixTimestamp,
+    instruction::{Instruction, InstructionError},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+/// 2024-01-01 00:00:00 UTC
+const START: UnixTimestamp = 1_704_067_200;
+const PERIOD: UnixTimestamp = 30 * 24 * 60 * 60;
+const MAX_AMOUNT: u64 = 100_000;
+const HOLD_FEE: u64 = 10_000;
+/// Category of the merchants, direct marketing subscriptions
+const MCC: u16 = 5968;
+
+/// The wallet token account is delegated to the mandate delegate, as the owner
+/// does alongside signing a mandate
+struct Setup {
+    context: ProgramTestContext,
+    merchant: Keypair,
+    wallet_token: Pubkey,
+    merchant_token: Pubkey,
+}
+
+async fn setup(wallet: &Keypair) -> Setup {
+    let mut program_test = program_test();
+    let mint = Pubkey::new_unique();
+    let wallet_token = Pubkey::new_unique();
+    let merchant_token = Pubkey::new_unique();
+    let merchant = Keypair::new();
+    program_test.add_account(mint, mint_account(INITIAL_AMOUNT));
+    program_test.add_account(
+        wallet_token,
+        token_account(
+            &mint,
+            &wallet.pubkey(),
+            INITIAL_AMOUNT,
+            Some(&mandate_delegate(&wallet.pubkey(), &merchant.pubkey())),
+        ),
+    );
+    program_test.add_account(
+        merchant_token,
+        token_account(&mint, &merchant.pubkey(), 0, None),
+    );
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    onboard(&mut context, &merchant.pubkey(), &mint, &merchant_token).await;
+    set_time(&mut context, START).await;
+    Setup {
+        context,
+        merchant,
+        wallet_token,
+        merchant_token,
+    }
+}
+
+fn mandate_delegate(owner: &Pubkey, merchant: &Pubkey) -> Pubkey {
+    let mandate = find_mandate_program_address(&card::id(), owner, merchant).0;
+    find_mandate_delegate_program_address(&card::id(), &mandate).0
+}
+
+/// Onboard `merchant` settling `mint` to `token`
+async fn onboard(
+    context: &mut ProgramTestContext,
+    merchant: &Pubkey,
+    mint: &Pubkey,
+    token: &Pubkey,
+) {
+    let payer = context.payer.pubkey();
+    let mut settlements = [Settlement::default(); MAX_MERCHANT_MINTS];
+    settlements[0] = Settlement {
+        mint: *mint,
+        token: *token,
+    };
+    process(
+        context,
+        instruction::onboard_merchant(
+            &card::id(),
+            &authority().pubkey(),
+            &payer,
+            OnboardMerchantArgs {
+                id: *merchant,
+                params: MerchantParams {
+                    settlement_count: 1,
+                    settlements,
+                    mcc: MCC,
+                    ..MerchantParams::default()
+                },
+                bump: find_merchant_program_address(&card::id(), merchant).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+}
+
+/// Mandate of three periods signed by `wallet`
+async fn sign_mandate(setup: &mut Setup, wallet: &Keypair) {
+    let merchant = setup.merchant.pubkey();
+    sign_mandate_on(&mut setup.context, wallet, &merchant, &setup.wallet_token).await;
+}
+
+/// Mandate of three periods charging `source_token` of `wallet` to `merchant`
+async fn sign_mandate_on(
+    context: &mut ProgramTestContext,
+    wallet: &Keypair,
+    merchant: &Pubkey,
+    source_token: &Pubkey,
+) {
+    let payer = context.payer.pubkey();
+    let merchant = *merchant;
+    let instruction = instruction::init_mandate(
+        &card::id(),
+        &wallet.pubkey(),
+        &payer,
+        source_token,
+        InitMandateArgs {
+            merchant,
+            terms: MandateTerms {
+                max_amount: MAX_AMOUNT,
+                period: PERIOD,
+                start: START,
+                end: START + 3 * PERIOD,
+            },
+            bump: find_mandate_program_address(&card::id(), &wallet.pubkey(), &merchant).1,
+        },
+    );
+    process(context, instruction, &[wallet]).await.unwrap();
+}
+
+fn charge(setup: &Setup, authority: &Pubkey, owner: &Pubkey, amount: u64) -> Instruction {
+    instruction::charge_mandate(
+        &card::id(),
+        authority,
+        owner,
+        &setup.merchant.pubkey(),
+        &setup.wallet_token,
+        &setup.merchant_token,
+        ChargeMandateArgs { amount },
+    )
+}
+
+#[tokio::test]
+async fn test_mandate_charges_once_per_period() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    sign_mandate(&mut setup, &wallet).await;
+    let owner = wallet.pubkey();
+    let merchant = setup.merchant.pubkey();
+    let merchant_signer = Keypair::from_bytes(&setup.merchant.to_bytes()).unwrap();
+
+    let instruction = charge(&setup, &merchant, &owner, MAX_AMOUNT + 1);
+    let result = process(&mut setup.context, instruction, &[&merchant_signer]).await;
+    assert_card_error(result, CardError::MandateAmountExceeded);
+    let instruction = charge(&setup, &merchant, &owner, 60_000);
+    process(&mut setup.context, instruction, &[&merchant_signer])
+        .await
+        .unwrap();
+    let instruction = charge(&setup, &merchant, &owner, 40_000);
+    let result = process(&mut setup.context, instruction, &[&merchant_signer]).await;
+    assert_card_error(result, CardError::MandateAlreadyCharged);
+
+    // the settler charges the next period on behalf of the merchant
+    set_time(&mut setup.context, START + PERIOD + 1).await;
+    let instruction = charge(&setup, &authority().pubkey(), &owner, MAX_AMOUNT);
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+
+    set_time(&mut setup.context, START + 3 * PERIOD).await;
+    setup.context.last_blockhash = setup.context.get_new_latest_blockhash().await.unwrap();
+    let instruction = charge(&setup, &merchant, &owner, 40_000);
+    let result = process(&mut setup.context, instruction, &[&merchant_signer]).await;
+    assert_card_error(result, CardError::MandateInactive);
+
+    let context = &mut setup.context;
+    assert_eq!(
+        token_amount(context, &setup.merchant_token).await,
+        60_000 + MAX_AMOUNT
+    );
+    assert_eq!(
+        token_amount(context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - 60_000 - MAX_AMOUNT
+    );
+    let account = context
+        .banks_client
+        .get_account(find_mandate_program_address(&card::id(), &owner, &merchant).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let mandate = Mandate::unpack(&account.data).unwrap();
+    assert_eq!(mandate.charge_count, 2);
+    assert_eq!(mandate.total_charged, 60_000 + MAX_AMOUNT);
+    assert_eq!(mandate.last_charged_at, START + PERIOD + 1);
+}
+
+#[tokio::test]
+async fn test_mandate_is_charged_by_merchant_until_canceled() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    sign_mandate(&mut setup, &wallet).await;
+    let owner = wallet.pubkey();
+    let merchant = setup.merchant.pubkey();
+    let outsider = Keypair::new();
+
+    let instruction = charge(&setup, &outsider.pubkey(), &owner, 1);
+    let result = process(&mut setup.context, instruction, &[&outsider]).await;
+    assert_card_error(result, CardError::MissingSettlerRole);
+    set_time(&mut setup.context, START - 1).await;
+    let instruction = charge(&setup, &authority().pubkey(), &owner, 1);
+    let result = process(&mut setup.context, instruction, &[&authority()]).await;
+    assert_card_error(result, CardError::MandateInactive);
+
+    let instruction = instruction::cancel_mandate(
+        &card::id(),
+        &outsider.pubkey(),
+        &merchant,
+        &owner,
+        &setup.wallet_token,
+    );
+    let result = process(&mut setup.context, instruction, &[&outsider]).await;
+    assert_card_error(result, CardError::InvalidOwner);
+    let instruction =
+        instruction::cancel_mandate(&card::id(), &owner, &merchant, &owner, &setup.wallet_token);
+    process(&mut setup.context, instruction, &[&wallet])
+        .await
+        .unwrap();
+    let token = token_account_state(&mut setup.context, &setup.wallet_token).await;
+    assert_eq!(token.delegate, COption::None);
+    set_time(&mut setup.context, START).await;
+    setup.context.last_blockhash = setup.context.get_new_latest_blockhash().await.unwrap();
+    let instruction = charge(&setup, &authority().pubkey(), &owner, 1);
+    let result = process(&mut setup.context, instruction, &[&authority()]).await;
+    assert_card_error(result, CardError::InvalidOwner);
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.merchant_token).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_hold_and_mandate_on_the_same_source() {
+    let wallet = Keypair::new();
+    let mut setup = TokenSetup::start(&wallet.pubkey(), 0, HOLD_FEE, None).await;
+    let merchant = Keypair::new();
+    let owner = wallet.pubkey();
+    let (mint, wallet_token, dst_token) = (setup.mint, setup.wallet_token, setup.dst_token);
+    let context = &mut setup.context;
+    onboard(context, &merchant.pubkey(), &mint, &dst_token).await;
+    set_time(context, START).await;
+    sign_mandate_on(context, &wallet, &merchant.pubkey(), &wallet_token).await;
+    let delegate = mandate_delegate(&owner, &merchant.pubkey());
+    let approve = |delegate: &Pubkey, amount| {
+        spl_token::instruction::approve(
+            &spl_token::id(),
+            &wallet_token,
+            delegate,
+            &owner,
+            &[],
+            amount,
+        )
+        .unwrap()
+    };
+    process(context, approve(&delegate, 3 * MAX_AMOUNT), &[&wallet])
+        .await
+        .unwrap();
+    let charge = |amount| {
+        instruction::charge_mandate(
+            &card::id(),
+            &merchant.pubkey(),
+            &owner,
+            &merchant.pubkey(),
+            &wallet_token,
+            &dst_token,
+            ChargeMandateArgs { amount },
+        )
+    };
+    process(context, charge(MAX_AMOUNT), &[&merchant])
+        .await
+        .unwrap();
+
+    // the hold takes over the delegate of the source, its allowance isn't the mandate's
+    let reference = Pubkey::new_unique();
+    let (escrow, bump) = find_escrow_program_address(&card::id(), &reference);
+    let authority_key = find_program_authority(&card::id()).0;
+    let payer = context.payer.pubkey();
+    let hold = instruction::init_escrow(
+        &card::id(),
+        &owner,
+        &authority().pubkey(),
+        &payer,
+        &escrow,
+        &authority_key,
+        &wallet_token,
+        &wallet_token,
+        &dst_token,
+        &setup.fee_token,
+        &mint,
+        &reference,
+        InitEscrowArgs {
+            amount: 1_000_000,
+            fee_bps: 0,
+            fixed_fee: HOLD_FEE,
+            bump,
+            merchant: None,
+            split: None,
+            hold: true,
+            mcc: 0,
+        },
+    );
+    process(context, hold, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    set_time(context, START + PERIOD).await;
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = process(context, charge(MAX_AMOUNT), &[&merchant]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(spl_token::error::TokenError::OwnerMismatch as u32)
+        )
+    );
+    let token = token_account_state(context, &wallet_token).await;
+    assert_eq!(token.delegate, COption::Some(authority_key));
+    assert_eq!(token.delegated_amount, 1_000_000 + HOLD_FEE);
+
+    // settling the hold leaves the mandate to the owner's next approval
+    let settle = instruction::settle_escrow(
+        &card::id(),
+        &authority().pubkey(),
+        &dst_token,
+        &setup.fee_token,
+        &wallet_token,
+        &escrow,
+        &mint,
+        &authority_key,
+    );
+    process(context, settle, &[&authority()]).await.unwrap();
+    process(context, approve(&delegate, 2 * MAX_AMOUNT), &[&wallet])
+        .await
+        .unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(context, charge(MAX_AMOUNT), &[&merchant])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_amount(context, &dst_token).await,
+        1_000_000 + 2 * MAX_AMOUNT
+    );
+}
+
+#[tokio::test]
+async fn test_mandate_charge_checks_card_categories() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    sign_mandate(&mut setup, &wallet).await;
+    let owner = wallet.pubkey();
+    let admin = authority().pubkey();
+    let payer = setup.context.payer.pubkey();
+    process(
+        &mut setup.context,
+        instruction::init_card(
+            &card::id(),
+            &admin,
+            &payer,
+            InitCardArgs {
+                owner,
+                params: CardParams {
+                    funding_token: setup.wallet_token,
+                    limits: CardLimits {
+                        per_transaction: MAX_AMOUNT,
+                        daily: MAX_AMOUNT,
+                        monthly: MAX_AMOUNT,
+                    },
+                },
+                bump: find_card_program_address(&card::id(), &owner).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let set_categories = |mode| {
+        let mut ranges = [MccRange::default(); MAX_MCC_RANGES];
+        ranges[0] = MccRange {
+            start: MCC,
+            end: MCC,
+        };
+        instruction::set_card_categories(
+            &card::id(),
+            &admin,
+            &owner,
+            MerchantCategories {
+                mode,
+                range_count: 1,
+                ranges,
+            },
+        )
+    };
+
+    process(
+        &mut setup.context,
+        set_categories(CategoryMode::Deny),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let instruction = charge(&setup, &admin, &owner, MAX_AMOUNT);
+    let result = process(&mut setup.context, instruction, &[&authority()]).await;
+    assert_card_error(result, CardError::MerchantCategoryBlocked);
+
+    process(
+        &mut setup.context,
+        set_categories(CategoryMode::Allow),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    setup.context.last_blockhash = setup.context.get_new_latest_blockhash().await.unwrap();
+    let instruction = charge(&setup, &admin, &owner, MAX_AMOUNT);
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.merchant_token).await,
+        MAX_AMOUNT
+    );
+}
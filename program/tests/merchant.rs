@@ -0,0 +1,266 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_merchant_program_address,
+    instruction::{self, OnboardMerchantArgs},
+    state::{
+        escrow::Escrow,
+        merchant::{MerchantParams, MerchantStatus, Settlement, MAX_MERCHANT_MINTS},
+    },
+};
+use common::*;
+use solana_program::{instruction::Instruction, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+/// Merchant paid in native SOL at `wallet`
+fn native_params(wallet: &Pubkey) -> MerchantParams {
+    let mut settlements = [Settlement::default(); MAX_MERCHANT_MINTS];
+    settlements[0] = Settlement {
+        mint: spl_token::native_mint::id(),
+        token: *wallet,
+    };
+    MerchantParams {
+        settlement_count: 1,
+        settlements,
+        has_fee_override: false,
+        fee_bps: 0,
+        fixed_fee: 0,
+        mcc: 0,
+    }
+}
+
+async fn setup(wallet: &Keypair) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), 0, 1_000_000)
+        .start_with_context()
+        .await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    context
+}
+
+async fn onboard(context: &mut ProgramTestContext, id: &Pubkey, params: MerchantParams) {
+    let payer = context.payer.pubkey();
+    process(
+        context,
+        instruction::onboard_merchant(
+            &card::id(),
+            &authority().pubkey(),
+            &payer,
+            OnboardMerchantArgs {
+                id: *id,
+                params,
+                bump: find_merchant_program_address(&card::id(), id).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+}
+
+impl NativeEscrow {
+    fn init_merchant(
+        &self,
+        payer: &Pubkey,
+        wallet: &Keypair,
+        dst: &Pubkey,
+        merchant: &Pubkey,
+    ) -> Instruction {
+        let mut args = self.args(LAMPORTS_PER_SOL);
+        args.fixed_fee = 1_000_000;
+        args.merchant = Some(*merchant);
+        self.init_with(&authority().pubkey(), payer, wallet, dst, args)
+    }
+
+    fn settle_merchant(&self, dst: &Pubkey, merchant: &Pubkey) -> Instruction {
+        instruction::with_merchant(
+            self.settle_with(&authority().pubkey(), dst),
+            &card::id(),
+            merchant,
+        )
+    }
+}
+
+#[tokio::test]
+async fn test_escrow_settles_to_merchant() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let merchant = Pubkey::new_unique();
+    let merchant_wallet = Pubkey::new_unique();
+    onboard(
+        &mut context,
+        &merchant,
+        MerchantParams {
+            has_fee_override: true,
+            fixed_fee: 10_000,
+            ..native_params(&merchant_wallet)
+        },
+    )
+    .await;
+
+    let escrow = NativeEscrow::new();
+    let result = process(
+        &mut context,
+        escrow.init_merchant(&payer, &wallet, &deposit::id(), &merchant),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidCollectionAccount);
+    process(
+        &mut context,
+        escrow.init_merchant(&payer, &wallet, &merchant_wallet, &merchant),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    let account = context
+        .banks_client
+        .get_account(escrow.escrow)
+        .await
+        .unwrap()
+        .unwrap();
+    let state = Escrow::unpack(&account.data).unwrap();
+    assert_eq!(state.merchant, merchant);
+    // the merchant's fee replaces the mint's
+    assert_eq!(state.fee, 10_000);
+
+    let fee_before = balance(&mut context, &fee::id()).await;
+    process(
+        &mut context,
+        escrow.settle_merchant(&merchant_wallet, &merchant),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        balance(&mut context, &merchant_wallet).await,
+        LAMPORTS_PER_SOL
+    );
+    assert_eq!(balance(&mut context, &fee::id()).await, fee_before + 10_000);
+}
+
+#[tokio::test]
+async fn test_suspended_merchant_can_only_be_canceled() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let merchant = Pubkey::new_unique();
+    let merchant_wallet = Pubkey::new_unique();
+    onboard(&mut context, &merchant, native_params(&merchant_wallet)).await;
+    let escrow = NativeEscrow::new();
+    process(
+        &mut context,
+        escrow.init_merchant(&payer, &wallet, &merchant_wallet, &merchant),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+
+    let outsider = Keypair::new();
+    let result = process(
+        &mut context,
+        instruction::set_merchant_status(
+            &card::id(),
+            &outsider.pubkey(),
+            &merchant,
+            MerchantStatus::Suspended,
+        ),
+        &[&outsider],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+    process(
+        &mut context,
+        instruction::set_merchant_status(
+            &card::id(),
+            &authority().pubkey(),
+            &merchant,
+            MerchantStatus::Suspended,
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    let result = process(
+        &mut context,
+        NativeEscrow::new().init_merchant(&payer, &wallet, &merchant_wallet, &merchant),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MerchantSuspended);
+    let result = process(
+        &mut context,
+        escrow.settle_merchant(&merchant_wallet, &merchant),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MerchantSuspended);
+    process(&mut context, escrow.cancel(&wallet), &[&authority()])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_update_merchant_moves_settlement() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let merchant = Pubkey::new_unique();
+    let old_wallet = Pubkey::new_unique();
+    let new_wallet = Pubkey::new_unique();
+    onboard(&mut context, &merchant, native_params(&old_wallet)).await;
+    let escrow = NativeEscrow::new();
+    process(
+        &mut context,
+        escrow.init_merchant(&payer, &wallet, &old_wallet, &merchant),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+
+    let mut params = native_params(&new_wallet);
+    params.settlements[1] = params.settlements[0];
+    params.settlement_count = 2;
+    let result = process(
+        &mut context,
+        instruction::update_merchant(&card::id(), &authority().pubkey(), &merchant, params),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidMerchantConfig);
+    process(
+        &mut context,
+        instruction::update_merchant(
+            &card::id(),
+            &authority().pubkey(),
+            &merchant,
+            native_params(&new_wallet),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    let result = process(
+        &mut context,
+        escrow.settle_merchant(&old_wallet, &merchant),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidDstTokenOwner);
+    process(
+        &mut context,
+        escrow.settle_merchant(&new_wallet, &merchant),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(balance(&mut context, &new_wallet).await, LAMPORTS_PER_SOL);
+}
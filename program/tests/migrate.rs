@@ -0,0 +1,301 @@
+mod common;
+
+use borsh::BorshSerialize;
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_escrow_program_address, find_escrow_vault_program_address, find_program_authority,
+    instruction,
+    state::escrow::{ESCROW_DATA_SIZE, ESCROW_LEGACY_DATA_SIZE},
+    utils::calculate_fee,
+};
+use common::*;
+use solana_program::{instruction::Instruction, pubkey::Pubkey, rent::Rent};
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    instruction::InstructionError,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const FEE_BPS: u16 = 250;
+const FIXED_FEE: u64 = 5_000;
+
+fn total_fee(amount: u64) -> u64 {
+    calculate_fee(amount, FEE_BPS as u64).unwrap() + FIXED_FEE
+}
+
+/// Escrow as the first release of the program laid it out
+#[derive(BorshSerialize)]
+struct EscrowV0 {
+    is_initialized: bool,
+    is_settled: bool,
+    is_canceled: bool,
+    amount: u64,
+    fee_bps: u16,
+    fixed_fee: u64,
+    src_token: Pubkey,
+    dst_token: Pubkey,
+    vault_token: Pubkey,
+    fee_token: Pubkey,
+    mint: Pubkey,
+    reference: Pubkey,
+}
+
+/// Program account holding `data` at the minimum balance of its size
+fn program_account(data: Vec<u8>) -> AccountSharedData {
+    AccountSharedData::from(Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: card::id(),
+        executable: false,
+        rent_epoch: 0,
+    })
+}
+
+/// Funds an escrow of `amount` written by the first release, returns its key
+fn legacy_escrow(setup: &mut TokenSetup, amount: u64) -> Pubkey {
+    let reference = Pubkey::new_unique();
+    let escrow = find_escrow_program_address(&card::id(), &reference).0;
+    let data = EscrowV0 {
+        is_initialized: true,
+        is_settled: false,
+        is_canceled: false,
+        amount,
+        fee_bps: FEE_BPS,
+        fixed_fee: FIXED_FEE,
+        src_token: setup.wallet_token,
+        dst_token: setup.dst_token,
+        vault_token: setup.vault_token,
+        fee_token: setup.fee_token,
+        mint: setup.mint,
+        reference,
+    }
+    .try_to_vec()
+    .unwrap();
+    assert_eq!(data.len(), ESCROW_LEGACY_DATA_SIZE);
+    setup.context.set_account(&escrow, &program_account(data));
+    setup.context.set_account(
+        &setup.vault_token,
+        &AccountSharedData::from(token_account(
+            &setup.mint,
+            &find_program_authority(&card::id()).0,
+            amount + total_fee(amount),
+            None,
+        )),
+    );
+    escrow
+}
+
+fn settle(setup: &TokenSetup, escrow: &Pubkey) -> Instruction {
+    instruction::settle_escrow(
+        &card::id(),
+        &authority().pubkey(),
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.vault_token,
+        escrow,
+        &setup.mint,
+        &find_program_authority(&card::id()).0,
+    )
+}
+
+async fn migrate(
+    context: &mut ProgramTestContext,
+    account: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let payer = context.payer.pubkey();
+    process(
+        context,
+        instruction::migrate(&card::id(), &payer, account),
+        &[],
+    )
+    .await
+}
+
+/// Native escrow of `amount` written by the first release, its lamports held by the
+/// program authority along with those of the other escrows
+fn legacy_native_escrow(
+    context: &mut ProgramTestContext,
+    wallet: &Pubkey,
+    amount: u64,
+    is_settled: bool,
+) -> NativeEscrow {
+    let escrow = NativeEscrow::new();
+    let data = EscrowV0 {
+        is_initialized: true,
+        is_settled,
+        is_canceled: false,
+        amount,
+        fee_bps: FEE_BPS,
+        fixed_fee: FIXED_FEE,
+        src_token: *wallet,
+        dst_token: deposit::id(),
+        vault_token: find_program_authority(&card::id()).0,
+        fee_token: fee::id(),
+        mint: spl_token::native_mint::id(),
+        reference: escrow.reference,
+    }
+    .try_to_vec()
+    .unwrap();
+    assert_eq!(data.len(), ESCROW_LEGACY_DATA_SIZE);
+    context.set_account(&escrow.escrow, &program_account(data));
+    escrow
+}
+
+async fn migrate_native(
+    context: &mut ProgramTestContext,
+    escrow: &NativeEscrow,
+) -> Result<(), BanksClientError> {
+    let payer = context.payer.pubkey();
+    process(
+        context,
+        instruction::with_native_vault(
+            instruction::migrate(&card::id(), &payer, &escrow.escrow),
+            &card::id(),
+            &escrow.reference,
+        ),
+        &[],
+    )
+    .await
+}
+
+async fn native_setup(wallet: &Keypair, pooled: u64) -> ProgramTestContext {
+    let mut program_test = native_program_test(&wallet.pubkey(), FEE_BPS, FIXED_FEE);
+    program_test.add_account(
+        find_program_authority(&card::id()).0,
+        Account::new(pooled, 0, &solana_program::system_program::id()),
+    );
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    context
+}
+
+#[tokio::test]
+async fn test_migrated_baseline_escrow_settles() {
+    let wallet = Keypair::new();
+    let mut setup = TokenSetup::start(&wallet.pubkey(), FEE_BPS, FIXED_FEE, None).await;
+    let amount = 1_000_000;
+    let escrow = legacy_escrow(&mut setup, amount);
+
+    // the current layout doesn't read the old one
+    let instruction = settle(&setup, &escrow);
+    let result = process(&mut setup.context, instruction.clone(), &[&authority()]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    migrate(&mut setup.context, &escrow).await.unwrap();
+    let context = &mut setup.context;
+    assert_eq!(account_data(context, &escrow).await.len(), ESCROW_DATA_SIZE);
+    assert_eq!(
+        balance(context, &escrow).await,
+        Rent::default().minimum_balance(ESCROW_DATA_SIZE)
+    );
+    let state = escrow_state(context, &escrow).await;
+    assert_eq!(state.fee, total_fee(amount));
+    assert_eq!(state.merchant, Pubkey::default());
+    assert_eq!(state.split.payee_count, 0);
+    assert_eq!(state.authority_bump, find_program_authority(&card::id()).1);
+    assert_eq!(state.vault_bump, 0);
+    assert_eq!(state.card, Pubkey::default());
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+    let context = &mut setup.context;
+    assert_eq!(token_amount(context, &setup.dst_token).await, amount);
+    assert_eq!(
+        token_amount(context, &setup.fee_token).await,
+        total_fee(amount)
+    );
+    assert_eq!(token_amount(context, &setup.vault_token).await, 0);
+}
+
+#[tokio::test]
+async fn test_current_escrow_is_not_migrated() {
+    let wallet = Keypair::new();
+    let mut setup = TokenSetup::start(&wallet.pubkey(), FEE_BPS, FIXED_FEE, None).await;
+    let escrow = legacy_escrow(&mut setup, 1_000_000);
+
+    migrate(&mut setup.context, &escrow).await.unwrap();
+    let context = &mut setup.context;
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = migrate(context, &escrow).await;
+    assert_card_error(result, CardError::NotMigratable);
+}
+
+#[tokio::test]
+async fn test_migrated_baseline_native_escrow_settles() {
+    let wallet = Keypair::new();
+    let amount = LAMPORTS_PER_SOL;
+    let other = 3 * LAMPORTS_PER_SOL;
+    let pooled = amount + total_fee(amount) + other;
+    let mut context = native_setup(&wallet, pooled).await;
+    let escrow = legacy_native_escrow(&mut context, &wallet.pubkey(), amount, false);
+    let authority_key = find_program_authority(&card::id()).0;
+
+    // the vault of the escrow isn't known before the migration
+    let result = migrate(&mut context, &escrow.escrow).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+
+    migrate_native(&mut context, &escrow).await.unwrap();
+    let state = escrow_state(&mut context, &escrow.escrow).await;
+    assert_eq!(state.fee, total_fee(amount));
+    assert_eq!(state.vault_token, escrow.vault);
+    assert_eq!(
+        state.vault_bump,
+        find_escrow_vault_program_address(&card::id(), &escrow.reference).1
+    );
+    let reserve = Rent::default().minimum_balance(0);
+    assert_eq!(
+        balance(&mut context, &escrow.vault).await,
+        amount + total_fee(amount) + reserve
+    );
+    assert_eq!(balance(&mut context, &authority_key).await, other);
+
+    let deposit_before = balance(&mut context, &deposit::id()).await;
+    let fee_before = balance(&mut context, &fee::id()).await;
+    process(&mut context, escrow.settle(), &[&authority()])
+        .await
+        .unwrap();
+    assert_eq!(
+        balance(&mut context, &deposit::id()).await,
+        deposit_before + amount
+    );
+    assert_eq!(
+        balance(&mut context, &fee::id()).await,
+        fee_before + total_fee(amount)
+    );
+    assert_eq!(balance(&mut context, &escrow.vault).await, 0);
+    // the funds of the other escrows stay with the program authority
+    assert_eq!(balance(&mut context, &authority_key).await, other);
+}
+
+#[tokio::test]
+async fn test_migrated_settled_native_escrow_keeps_pool() {
+    let wallet = Keypair::new();
+    let other = 3 * LAMPORTS_PER_SOL;
+    let mut context = native_setup(&wallet, other).await;
+    let escrow = legacy_native_escrow(&mut context, &wallet.pubkey(), LAMPORTS_PER_SOL, true);
+
+    migrate_native(&mut context, &escrow).await.unwrap();
+    let state = escrow_state(&mut context, &escrow.escrow).await;
+    assert!(state.is_settled);
+    assert_eq!(state.vault_token, escrow.vault);
+    assert_eq!(
+        balance(&mut context, &find_program_authority(&card::id()).0).await,
+        other
+    );
+    assert_eq!(
+        balance(&mut context, &escrow.vault).await,
+        Rent::default().minimum_balance(0)
+    );
+}
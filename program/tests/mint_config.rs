@@ -0,0 +1,181 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_mint_config_program_address,
+    instruction::{self, InitMintConfigArgs},
+    state::mint_config::{MintConfig, MintParams},
+};
+use common::*;
+use solana_program::{instruction::Instruction, program_pack::Pack, pubkey::Pubkey, rent::Rent};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+/// Native SOL params accepting escrows between 1_000 lamports and 1 SOL
+fn native_params() -> MintParams {
+    MintParams {
+        enabled: true,
+        min_amount: 1_000,
+        max_amount: LAMPORTS_PER_SOL,
+        fee_bps: 100,
+        fixed_fee: 5_000,
+        deposit_collection: deposit::id(),
+        fee_collection: fee::id(),
+    }
+}
+
+/// Starts with every role held by the authority and no supported mint
+async fn setup(wallet: &Keypair) -> ProgramTestContext {
+    let mut program_test = program_test();
+    let rent_exempt = Rent::default().minimum_balance(0);
+    program_test.add_account(deposit::id(), system_account(rent_exempt));
+    program_test.add_account(fee::id(), system_account(rent_exempt));
+    program_test.add_account(wallet.pubkey(), system_account(10 * LAMPORTS_PER_SOL));
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    context
+}
+
+fn init_mint_config(admin: &Pubkey, payer: &Pubkey, params: MintParams) -> Instruction {
+    let mint = spl_token::native_mint::id();
+    instruction::init_mint_config(
+        &card::id(),
+        admin,
+        payer,
+        &mint,
+        InitMintConfigArgs {
+            params,
+            bump: find_mint_config_program_address(&card::id(), &mint).1,
+        },
+    )
+}
+
+fn init_escrow(payer: &Pubkey, wallet: &Keypair, dst: &Pubkey, amount: u64) -> Instruction {
+    let escrow = NativeEscrow::new();
+    escrow.init_with(
+        &authority().pubkey(),
+        payer,
+        wallet,
+        dst,
+        escrow.args(amount),
+    )
+}
+
+#[tokio::test]
+async fn test_init_mint_config_requires_admin() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+    let outsider = Keypair::new();
+
+    let result = process(
+        &mut context,
+        init_mint_config(&outsider.pubkey(), &payer, native_params()),
+        &[&outsider],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+    let result = process(
+        &mut context,
+        init_mint_config(
+            &authority().pubkey(),
+            &payer,
+            MintParams {
+                min_amount: LAMPORTS_PER_SOL + 1,
+                ..native_params()
+            },
+        ),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidMintConfig);
+
+    process(
+        &mut context,
+        init_mint_config(&authority().pubkey(), &payer, native_params()),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let mint = spl_token::native_mint::id();
+    let account = context
+        .banks_client
+        .get_account(find_mint_config_program_address(&card::id(), &mint).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let mint_config = MintConfig::unpack(&account.data).unwrap();
+    assert_eq!(mint_config.mint, mint);
+    assert_eq!(mint_config.params, native_params());
+}
+
+#[tokio::test]
+async fn test_escrow_limited_to_supported_mints() {
+    let wallet = Keypair::new();
+    let mut context = setup(&wallet).await;
+    let payer = context.payer.pubkey();
+
+    let result = process(
+        &mut context,
+        init_escrow(&payer, &wallet, &deposit::id(), LAMPORTS_PER_SOL),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MintNotSupported);
+
+    process(
+        &mut context,
+        init_mint_config(&authority().pubkey(), &payer, native_params()),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        init_escrow(&payer, &wallet, &deposit::id(), LAMPORTS_PER_SOL + 1),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::AmountOutOfRange);
+    let result = process(
+        &mut context,
+        init_escrow(&payer, &wallet, &Pubkey::new_unique(), LAMPORTS_PER_SOL),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidCollectionAccount);
+    process(
+        &mut context,
+        init_escrow(&payer, &wallet, &deposit::id(), LAMPORTS_PER_SOL),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+
+    process(
+        &mut context,
+        instruction::set_mint_config(
+            &card::id(),
+            &authority().pubkey(),
+            &spl_token::native_mint::id(),
+            MintParams {
+                enabled: false,
+                ..native_params()
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        init_escrow(&payer, &wallet, &deposit::id(), LAMPORTS_PER_SOL),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MintNotSupported);
+}
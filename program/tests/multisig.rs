@@ -0,0 +1,199 @@
+mod common;
+
+use card::{
+    collections::deposit,
+    error::CardError,
+    find_multisig_program_address,
+    instruction::{self, InitMultisigArgs, SetMultisigArgs},
+    state::{config::Roles, multisig::Multisig},
+};
+use common::*;
+use solana_program::{instruction::Instruction, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+fn multisig() -> Pubkey {
+    find_multisig_program_address(&card::id()).0
+}
+
+/// Starts with a 2-of-3 multisig holding the escrow roles
+async fn setup(wallet: &Keypair, members: &[Keypair]) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), 0, 0)
+        .start_with_context()
+        .await;
+    let payer = context.payer.pubkey();
+    let admin = authority().pubkey();
+    init_config(
+        &mut context,
+        Roles {
+            settler: multisig(),
+            canceler: multisig(),
+            rent_closer: multisig(),
+            ..roles(admin)
+        },
+    )
+    .await;
+    process(
+        &mut context,
+        instruction::init_multisig(
+            &card::id(),
+            &admin,
+            &payer,
+            &multisig(),
+            InitMultisigArgs {
+                threshold: 2,
+                signers: members.iter().map(|member| member.pubkey()).collect(),
+                bump: find_multisig_program_address(&card::id()).1,
+            },
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    context
+}
+
+impl NativeEscrow {
+    fn init_multisig(&self, payer: &Pubkey, wallet: &Keypair, signers: &[&Pubkey]) -> Instruction {
+        instruction::with_multisig_approval(
+            self.init_with(
+                &multisig(),
+                payer,
+                wallet,
+                &deposit::id(),
+                self.args(LAMPORTS_PER_SOL),
+            ),
+            &multisig(),
+            signers,
+        )
+    }
+
+    fn settle_multisig(&self, signers: &[&Pubkey]) -> Instruction {
+        instruction::with_multisig_approval(
+            self.settle_with(&multisig(), &deposit::id()),
+            &multisig(),
+            signers,
+        )
+    }
+
+    fn close_multisig(&self, payer: &Pubkey, signers: &[&Pubkey]) -> Instruction {
+        instruction::with_multisig_approval(
+            self.close_with(&multisig(), payer),
+            &multisig(),
+            signers,
+        )
+    }
+}
+
+#[tokio::test]
+async fn test_escrow_approved_by_multisig() {
+    let wallet = Keypair::new();
+    let members = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let mut context = setup(&wallet, &members).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    let [first, second, third] = &members;
+
+    process(
+        &mut context,
+        escrow.init_multisig(&payer, &wallet, &[&first.pubkey(), &third.pubkey()]),
+        &[&wallet, first, third],
+    )
+    .await
+    .unwrap();
+
+    // the same member signing twice is a single approval
+    let result = process(
+        &mut context,
+        escrow.settle_multisig(&[&second.pubkey(), &second.pubkey()]),
+        &[second],
+    )
+    .await;
+    assert_card_error(result, CardError::NotEnoughMultisigSigners);
+    // signers from outside the set don't count
+    let outsider = Keypair::new();
+    let result = process(
+        &mut context,
+        escrow.settle_multisig(&[&second.pubkey(), &outsider.pubkey()]),
+        &[second, &outsider],
+    )
+    .await;
+    assert_card_error(result, CardError::NotEnoughMultisigSigners);
+
+    process(
+        &mut context,
+        escrow.settle_multisig(&[&first.pubkey(), &second.pubkey()]),
+        &[first, second],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        escrow.close_multisig(&payer, &[&second.pubkey(), &third.pubkey()]),
+        &[second, third],
+    )
+    .await
+    .unwrap();
+    assert!(context
+        .banks_client
+        .get_account(escrow.escrow)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_set_multisig() {
+    let wallet = Keypair::new();
+    let members = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let mut context = setup(&wallet, &members).await;
+    let [first, second, _] = &members;
+    let newcomer = Keypair::new();
+
+    let set = |threshold, signers: &[&Keypair], approvers: &[&Keypair]| {
+        instruction::set_multisig(
+            &card::id(),
+            &multisig(),
+            &approvers
+                .iter()
+                .map(|approver| approver.pubkey())
+                .collect::<Vec<_>>()
+                .iter()
+                .collect::<Vec<_>>(),
+            SetMultisigArgs {
+                threshold,
+                signers: signers.iter().map(|signer| signer.pubkey()).collect(),
+            },
+        )
+    };
+
+    let result = process(&mut context, set(1, &[&newcomer], &[first]), &[first]).await;
+    assert_card_error(result, CardError::NotEnoughMultisigSigners);
+    let result = process(
+        &mut context,
+        set(3, &[first, &newcomer], &[first, second]),
+        &[first, second],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidMultisigConfig);
+
+    process(
+        &mut context,
+        set(1, &[&newcomer], &[first, second]),
+        &[first, second],
+    )
+    .await
+    .unwrap();
+    let account = context
+        .banks_client
+        .get_account(multisig())
+        .await
+        .unwrap()
+        .unwrap();
+    let multisig = Multisig::unpack(&account.data).unwrap();
+    assert_eq!(multisig.threshold, 1);
+    assert_eq!(multisig.signers(), &[newcomer.pubkey()]);
+}
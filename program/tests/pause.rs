@@ -0,0 +1,120 @@
+mod common;
+
+use card::{
+    error::CardError,
+    instruction,
+    state::config::{Roles, PAUSE_ALL, PAUSE_INIT_ESCROW},
+};
+use common::*;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+/// Starts with every role but the admin held by the authority
+async fn setup(wallet: &Keypair, admin: &Keypair) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), 0, 0)
+        .start_with_context()
+        .await;
+    init_config(
+        &mut context,
+        Roles {
+            config_admin: admin.pubkey(),
+            ..roles(authority().pubkey())
+        },
+    )
+    .await;
+    context
+}
+
+#[tokio::test]
+async fn test_pause_blocks_new_escrows() {
+    let wallet = Keypair::new();
+    let admin = Keypair::new();
+    let mut context = setup(&wallet, &admin).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+
+    let result = process(
+        &mut context,
+        instruction::set_pause(&card::id(), &authority().pubkey(), PAUSE_INIT_ESCROW),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+    process(
+        &mut context,
+        instruction::set_pause(&card::id(), &admin.pubkey(), PAUSE_INIT_ESCROW),
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let result = process(
+        &mut context,
+        escrow.init(&payer, &wallet),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::ProgramPaused);
+
+    process(
+        &mut context,
+        instruction::set_pause(&card::id(), &admin.pubkey(), 0),
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let escrow = NativeEscrow::new();
+    process(
+        &mut context,
+        escrow.init(&payer, &wallet),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_cancel_allowed_while_paused() {
+    let wallet = Keypair::new();
+    let admin = Keypair::new();
+    let mut context = setup(&wallet, &admin).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+
+    process(
+        &mut context,
+        escrow.init(&payer, &wallet),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    let wallet_before = context
+        .banks_client
+        .get_balance(wallet.pubkey())
+        .await
+        .unwrap();
+    process(
+        &mut context,
+        instruction::set_pause(&card::id(), &admin.pubkey(), PAUSE_ALL),
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let result = process(&mut context, escrow.settle(), &[&authority()]).await;
+    assert_card_error(result, CardError::ProgramPaused);
+    process(&mut context, escrow.cancel(&wallet), &[&authority()])
+        .await
+        .unwrap();
+    assert_eq!(
+        context
+            .banks_client
+            .get_balance(wallet.pubkey())
+            .await
+            .unwrap(),
+        wallet_before + LAMPORTS_PER_SOL
+    );
+}
@@ -0,0 +1,225 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_mint_config_program_address, find_payout_program_address, find_program_authority,
+    instruction::{self, SchedulePayoutArgs},
+    state::{
+        config::{PAUSE_ALL, PAUSE_INIT_WITHDRAWAL},
+        payout::ScheduledPayout,
+    },
+};
+use common::*;
+use solana_program::{clock::UnixTimestamp, program_pack::Pack, pubkey::Pubkey, rent::Rent};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+
+/// 2024-01-01 00:00:00 UTC
+const START: UnixTimestamp = 1_704_067_200;
+const DAY: UnixTimestamp = 24 * 60 * 60;
+
+struct Setup {
+    context: ProgramTestContext,
+    mint: Pubkey,
+    wallet_token: Pubkey,
+    vault_token: Pubkey,
+    beneficiary_token: Pubkey,
+}
+
+async fn setup(wallet: &Keypair) -> Setup {
+    let mut program_test = program_test();
+    let mint = Pubkey::new_unique();
+    let wallet_token = Pubkey::new_unique();
+    let vault_token = Pubkey::new_unique();
+    let beneficiary_token = Pubkey::new_unique();
+    program_test.add_account(mint, mint_account(INITIAL_AMOUNT));
+    program_test.add_account(
+        wallet_token,
+        token_account(&mint, &wallet.pubkey(), INITIAL_AMOUNT, None),
+    );
+    program_test.add_account(
+        vault_token,
+        token_account(&mint, &find_program_authority(&card::id()).0, 0, None),
+    );
+    program_test.add_account(
+        beneficiary_token,
+        token_account(&mint, &Pubkey::new_unique(), 0, None),
+    );
+    program_test.add_account(
+        find_mint_config_program_address(&card::id(), &mint).0,
+        mint_config_account(&mint, &deposit::id(), &fee::id(), 0, 0),
+    );
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    set_time(&mut context, START).await;
+    Setup {
+        context,
+        mint,
+        wallet_token,
+        vault_token,
+        beneficiary_token,
+    }
+}
+
+/// Payout of `amount` unlocking a day from the start, returning its key
+async fn schedule(setup: &mut Setup, wallet: &Keypair, amount: u64) -> Pubkey {
+    let key = Pubkey::new_unique();
+    let payer = setup.context.payer.pubkey();
+    let instruction = instruction::schedule_payout(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &payer,
+        &setup.wallet_token,
+        &setup.vault_token,
+        &setup.beneficiary_token,
+        &setup.mint,
+        SchedulePayoutArgs {
+            amount,
+            unlock_at: START + DAY,
+            key,
+            bump: find_payout_program_address(&card::id(), &key).1,
+        },
+    );
+    process(&mut setup.context, instruction, &[wallet, &authority()])
+        .await
+        .unwrap();
+    key
+}
+
+async fn payout_state(context: &mut ProgramTestContext, key: &Pubkey) -> ScheduledPayout {
+    let data = account_data(context, &find_payout_program_address(&card::id(), key).0).await;
+    ScheduledPayout::unpack(&data).unwrap()
+}
+
+#[tokio::test]
+async fn test_payout_executes_once_unlocked() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let amount = 1_000_000;
+    let key = schedule(&mut setup, &wallet, amount).await;
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - amount
+    );
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.vault_token).await,
+        amount
+    );
+
+    let payer = setup.context.payer.pubkey();
+    assert_eq!(
+        payout_state(&mut setup.context, &key).await.rent_payer,
+        payer
+    );
+    let execute = instruction::execute_payout(
+        &card::id(),
+        &key,
+        &setup.vault_token,
+        &setup.beneficiary_token,
+        &payer,
+    );
+    let result = process(&mut setup.context, execute.clone(), &[]).await;
+    assert_card_error(result, CardError::PayoutLocked);
+
+    // anyone cranks it once unlocked, unless withdrawals are paused
+    set_time(&mut setup.context, START + DAY).await;
+    let pause = |flags| instruction::set_pause(&card::id(), &authority().pubkey(), flags);
+    process(
+        &mut setup.context,
+        pause(PAUSE_INIT_WITHDRAWAL),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    setup.context.last_blockhash = setup.context.get_new_latest_blockhash().await.unwrap();
+    let result = process(&mut setup.context, execute.clone(), &[]).await;
+    assert_card_error(result, CardError::ProgramPaused);
+    process(&mut setup.context, pause(0), &[&authority()])
+        .await
+        .unwrap();
+    setup.context.last_blockhash = setup.context.get_new_latest_blockhash().await.unwrap();
+    process(&mut setup.context, execute.clone(), &[])
+        .await
+        .unwrap();
+
+    // the account is closed, its rent back with the payer
+    let payout = find_payout_program_address(&card::id(), &key).0;
+    let context = &mut setup.context;
+    assert!(context
+        .banks_client
+        .get_account(payout)
+        .await
+        .unwrap()
+        .is_none());
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let result = process(context, execute, &[]).await;
+    assert_card_error(result, CardError::InvalidOwner);
+    assert_eq!(
+        token_amount(context, &setup.beneficiary_token).await,
+        amount
+    );
+    assert_eq!(token_amount(context, &setup.vault_token).await, 0);
+}
+
+#[tokio::test]
+async fn test_payout_is_canceled_by_authority_before_unlock() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let first = schedule(&mut setup, &wallet, 1_000_000).await;
+    let second = schedule(&mut setup, &wallet, 2_000_000).await;
+    let (vault_token, wallet_token) = (setup.vault_token, setup.wallet_token);
+    let payer = setup.context.payer.pubkey();
+    let cancel = |authority: &Pubkey, key: &Pubkey| {
+        instruction::cancel_payout(
+            &card::id(),
+            authority,
+            key,
+            &vault_token,
+            &wallet_token,
+            &payer,
+        )
+    };
+
+    let outsider = Keypair::new();
+    let instruction = cancel(&outsider.pubkey(), &first);
+    let result = process(&mut setup.context, instruction, &[&outsider]).await;
+    assert_card_error(result, CardError::MissingWithdrawalApproverRole);
+
+    // canceling returns the funds even while withdrawals are paused
+    let instruction = instruction::set_pause(&card::id(), &authority().pubkey(), PAUSE_ALL);
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+    let rent = Rent::default().minimum_balance(ScheduledPayout::LEN);
+    let payer_before = balance(&mut setup.context, &payer).await;
+    let instruction = cancel(&authority().pubkey(), &first);
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - 2_000_000
+    );
+    // the payer gets the rent back, less the fee of the transaction it paid
+    let payer_after = balance(&mut setup.context, &payer).await;
+    assert!(payer_after > payer_before && payer_after <= payer_before + rent);
+    let payout = find_payout_program_address(&card::id(), &first).0;
+    assert!(setup
+        .context
+        .banks_client
+        .get_account(payout)
+        .await
+        .unwrap()
+        .is_none());
+
+    set_time(&mut setup.context, START + DAY).await;
+    let instruction = cancel(&authority().pubkey(), &second);
+    let result = process(&mut setup.context, instruction, &[&authority()]).await;
+    assert_card_error(result, CardError::PayoutUnlocked);
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.vault_token).await,
+        2_000_000
+    );
+}
@@ -0,0 +1,321 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    error::CardError,
+    find_escrow_program_address, find_mint_config_program_address, find_program_authority,
+    instruction::{self, Authorization, AuthorizationAction, InitEscrowArgs, RefundArgs},
+};
+use common::*;
+use solana_program::{instruction::Instruction, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::signature::{Keypair, Signer};
+
+const FIXED_FEE: u64 = 10_000;
+
+/// The collection accounts are spent by delegates standing in for the collection owners
+struct Setup {
+    context: ProgramTestContext,
+    mint: Pubkey,
+    wallet_token: Pubkey,
+    vault_token: Pubkey,
+    dst_token: Pubkey,
+    fee_token: Pubkey,
+    dst_delegate: Keypair,
+    fee_delegate: Keypair,
+}
+
+async fn setup(wallet: &Keypair) -> Setup {
+    let mut program_test = program_test();
+    let mint = Pubkey::new_unique();
+    let wallet_token = Pubkey::new_unique();
+    let vault_token = Pubkey::new_unique();
+    let dst_token = Pubkey::new_unique();
+    let fee_token = Pubkey::new_unique();
+    let dst_delegate = Keypair::new();
+    let fee_delegate = Keypair::new();
+    program_test.add_account(mint, mint_account(INITIAL_AMOUNT));
+    program_test.add_account(
+        wallet_token,
+        token_account(&mint, &wallet.pubkey(), INITIAL_AMOUNT, None),
+    );
+    program_test.add_account(
+        vault_token,
+        token_account(&mint, &find_program_authority(&card::id()).0, 0, None),
+    );
+    program_test.add_account(
+        dst_token,
+        token_account(&mint, &deposit::id(), 0, Some(&dst_delegate.pubkey())),
+    );
+    program_test.add_account(
+        fee_token,
+        token_account(&mint, &fee::id(), 0, Some(&fee_delegate.pubkey())),
+    );
+    program_test.add_account(
+        find_mint_config_program_address(&card::id(), &mint).0,
+        mint_config_account(&mint, &dst_token, &fee_token, 0, FIXED_FEE),
+    );
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    Setup {
+        context,
+        mint,
+        wallet_token,
+        vault_token,
+        dst_token,
+        fee_token,
+        dst_delegate,
+        fee_delegate,
+    }
+}
+
+/// Escrow of `amount`, initialized and settled
+async fn settled_escrow(setup: &mut Setup, wallet: &Keypair, amount: u64) -> Pubkey {
+    let reference = Pubkey::new_unique();
+    let (escrow, bump) = find_escrow_program_address(&card::id(), &reference);
+    let payer = setup.context.payer.pubkey();
+    let init = instruction::init_escrow(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &payer,
+        &escrow,
+        &find_program_authority(&card::id()).0,
+        &setup.vault_token,
+        &setup.wallet_token,
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.mint,
+        &reference,
+        InitEscrowArgs {
+            amount,
+            fee_bps: 0,
+            fixed_fee: FIXED_FEE,
+            bump,
+            merchant: None,
+            split: None,
+            hold: false,
+            mcc: 0,
+        },
+    );
+    process(&mut setup.context, init, &[wallet, &authority()])
+        .await
+        .unwrap();
+    let settle = instruction::settle_escrow(
+        &card::id(),
+        &authority().pubkey(),
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.vault_token,
+        &escrow,
+        &setup.mint,
+        &find_program_authority(&card::id()).0,
+    );
+    process(&mut setup.context, settle, &[&authority()])
+        .await
+        .unwrap();
+    escrow
+}
+
+fn refund(setup: &Setup, authority: &Pubkey, escrow: &Pubkey, args: RefundArgs) -> Instruction {
+    instruction::refund(
+        &card::id(),
+        authority,
+        escrow,
+        &setup.wallet_token,
+        &setup.dst_token,
+        &setup.dst_delegate.pubkey(),
+        &setup.fee_token,
+        &setup.fee_delegate.pubkey(),
+        &setup.mint,
+        args,
+    )
+}
+
+#[tokio::test]
+async fn test_partial_refunds_add_up_to_the_payment() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let amount = 1_000_000;
+    let escrow = settled_escrow(&mut setup, &wallet, amount).await;
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - amount - FIXED_FEE
+    );
+
+    let instruction = refund(
+        &setup,
+        &authority().pubkey(),
+        &escrow,
+        RefundArgs {
+            amount: 400_000,
+            refund_fee: true,
+            refunded_amount: 0,
+        },
+    );
+    process(
+        &mut setup.context,
+        instruction,
+        &[&authority(), &setup.dst_delegate, &setup.fee_delegate],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - 600_000 - 6_000
+    );
+
+    let instruction = refund(
+        &setup,
+        &authority().pubkey(),
+        &escrow,
+        RefundArgs {
+            amount: 600_001,
+            refund_fee: false,
+            refunded_amount: 400_000,
+        },
+    );
+    let result = process(
+        &mut setup.context,
+        instruction,
+        &[&authority(), &setup.dst_delegate],
+    )
+    .await;
+    assert_card_error(result, CardError::RefundTooLarge);
+    let instruction = refund(
+        &setup,
+        &authority().pubkey(),
+        &escrow,
+        RefundArgs {
+            amount: 600_000,
+            refund_fee: true,
+            refunded_amount: 400_000,
+        },
+    );
+    process(
+        &mut setup.context,
+        instruction,
+        &[&authority(), &setup.dst_delegate, &setup.fee_delegate],
+    )
+    .await
+    .unwrap();
+
+    let context = &mut setup.context;
+    assert_eq!(
+        token_amount(context, &setup.wallet_token).await,
+        INITIAL_AMOUNT
+    );
+    assert_eq!(token_amount(context, &setup.dst_token).await, 0);
+    assert_eq!(token_amount(context, &setup.fee_token).await, 0);
+    let state = escrow_state(context, &escrow).await;
+    assert_eq!(state.refunded_amount, amount);
+    assert_eq!(state.refunded_fee, FIXED_FEE);
+}
+
+#[tokio::test]
+async fn test_refund_requires_settled_escrow_and_canceler() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let payer = setup.context.payer.pubkey();
+    let reference = Pubkey::new_unique();
+    let (escrow, bump) = find_escrow_program_address(&card::id(), &reference);
+    let init = instruction::init_escrow(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &payer,
+        &escrow,
+        &find_program_authority(&card::id()).0,
+        &setup.vault_token,
+        &setup.wallet_token,
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.mint,
+        &reference,
+        InitEscrowArgs {
+            amount: 1_000_000,
+            fee_bps: 0,
+            fixed_fee: FIXED_FEE,
+            bump,
+            merchant: None,
+            split: None,
+            hold: false,
+            mcc: 0,
+        },
+    );
+    process(&mut setup.context, init, &[&wallet, &authority()])
+        .await
+        .unwrap();
+    let args = RefundArgs {
+        amount: 1_000,
+        refund_fee: false,
+        refunded_amount: 0,
+    };
+
+    let instruction = refund(&setup, &authority().pubkey(), &escrow, args.clone());
+    let result = process(
+        &mut setup.context,
+        instruction,
+        &[&authority(), &setup.dst_delegate],
+    )
+    .await;
+    assert_card_error(result, CardError::AccountNotSettled);
+
+    let escrow = settled_escrow(&mut setup, &wallet, 1_000_000).await;
+    let outsider = Keypair::new();
+    let instruction = refund(&setup, &outsider.pubkey(), &escrow, args);
+    let result = process(
+        &mut setup.context,
+        instruction,
+        &[&outsider, &setup.dst_delegate],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingCancelerRole);
+}
+
+#[tokio::test]
+async fn test_refund_authorization_refunds_once() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let amount = 1_000_000;
+    let escrow = settled_escrow(&mut setup, &wallet, amount).await;
+    let reference = escrow_state(&mut setup.context, &escrow).await.reference;
+    let args = RefundArgs {
+        amount: 400_000,
+        refund_fee: false,
+        refunded_amount: 0,
+    };
+    let authorization = Authorization {
+        program_id: card::id(),
+        reference,
+        amount: args.amount,
+        action: AuthorizationAction::Refund,
+        expiry: now(&mut setup.context).await + 60,
+        args_hash: Authorization::hash_args(&args).unwrap(),
+    };
+    let instructions = [
+        ed25519_authorization(&authority(), &authorization),
+        instruction::with_ed25519_authorization(
+            refund(&setup, &authority().pubkey(), &escrow, args),
+            &authority().pubkey(),
+        ),
+    ];
+    process_all(&mut setup.context, &instructions, &[&setup.dst_delegate])
+        .await
+        .unwrap();
+    assert_eq!(
+        token_amount(&mut setup.context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - 600_000 - FIXED_FEE
+    );
+
+    // the same approval sent again names the refunded amount it was given for
+    setup.context.last_blockhash = setup.context.get_new_latest_blockhash().await.unwrap();
+    let result = process_all(&mut setup.context, &instructions, &[&setup.dst_delegate]).await;
+    assert_instruction_error(result, 1, CardError::StaleRefund);
+    assert_eq!(
+        escrow_state(&mut setup.context, &escrow)
+            .await
+            .refunded_amount,
+        400_000
+    );
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use card::{
+    collections::deposit,
+    error::CardError,
+    find_config_program_address, instruction,
+    state::config::{Config, Roles, PAUSE_ALL},
+};
+use common::*;
+use solana_program::{instruction::AccountMeta, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+/// Keys holding one role each
+struct RoleKeys {
+    settler: Keypair,
+    canceler: Keypair,
+    rent_closer: Keypair,
+    admin: Keypair,
+}
+
+impl RoleKeys {
+    fn new() -> Self {
+        Self {
+            settler: Keypair::new(),
+            canceler: Keypair::new(),
+            rent_closer: Keypair::new(),
+            admin: Keypair::new(),
+        }
+    }
+
+    fn roles(&self) -> Roles {
+        Roles {
+            settler: self.settler.pubkey(),
+            canceler: self.canceler.pubkey(),
+            deposit_approver: authority().pubkey(),
+            withdrawal_approver: authority().pubkey(),
+            rent_closer: self.rent_closer.pubkey(),
+            config_admin: self.admin.pubkey(),
+        }
+    }
+}
+
+async fn setup(wallet: &Keypair, keys: &RoleKeys) -> ProgramTestContext {
+    let mut context = native_program_test(&wallet.pubkey(), 0, 0)
+        .start_with_context()
+        .await;
+    init_config(&mut context, keys.roles()).await;
+    context
+}
+
+#[tokio::test]
+async fn test_each_role_is_checked_separately() {
+    let wallet = Keypair::new();
+    let keys = RoleKeys::new();
+    let mut context = setup(&wallet, &keys).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+
+    // the old single authority holds none of the escrow roles anymore
+    let result = process(
+        &mut context,
+        escrow.init_with(
+            &authority().pubkey(),
+            &payer,
+            &wallet,
+            &deposit::id(),
+            escrow.args(LAMPORTS_PER_SOL),
+        ),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingSettlerRole);
+    process(
+        &mut context,
+        escrow.init_with(
+            &keys.settler.pubkey(),
+            &payer,
+            &wallet,
+            &deposit::id(),
+            escrow.args(LAMPORTS_PER_SOL),
+        ),
+        &[&wallet, &keys.settler],
+    )
+    .await
+    .unwrap();
+
+    let result = process(
+        &mut context,
+        escrow.cancel_with(&keys.settler.pubkey(), &wallet),
+        &[&keys.settler],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingCancelerRole);
+    process(
+        &mut context,
+        escrow.cancel_with(&keys.canceler.pubkey(), &wallet),
+        &[&keys.canceler],
+    )
+    .await
+    .unwrap();
+
+    let result = process(
+        &mut context,
+        escrow.close_with(&keys.canceler.pubkey(), &payer),
+        &[&keys.canceler],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingRentCloserRole);
+    process(
+        &mut context,
+        escrow.close_with(&keys.rent_closer.pubkey(), &payer),
+        &[&keys.rent_closer],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_set_config_requires_admin() {
+    let wallet = Keypair::new();
+    let keys = RoleKeys::new();
+    let mut context = setup(&wallet, &keys).await;
+    let rotated = RoleKeys::new();
+
+    let result = process(
+        &mut context,
+        instruction::set_config(&card::id(), &authority().pubkey(), rotated.roles()),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::MissingConfigAdminRole);
+    process(
+        &mut context,
+        instruction::set_config(&card::id(), &keys.admin.pubkey(), rotated.roles()),
+        &[&keys.admin],
+    )
+    .await
+    .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(find_config_program_address(&card::id()).0)
+        .await
+        .unwrap()
+        .unwrap();
+    let config = Config::unpack(&account.data).unwrap();
+    assert_eq!(config.roles, rotated.roles());
+}
+
+#[tokio::test]
+async fn test_init_config_requires_upgrade_authority() {
+    let mut context = program_test().start_with_context().await;
+    let impostor = Keypair::new();
+    let payer = context.payer.pubkey();
+
+    let result = process(
+        &mut context,
+        instruction::init_config(
+            &card::id(),
+            &impostor.pubkey(),
+            &payer,
+            instruction::InitConfigArgs {
+                roles: roles(impostor.pubkey()),
+                bump: find_config_program_address(&card::id()).1,
+            },
+        ),
+        &[&impostor],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidAuthorityId);
+    init_config(&mut context, roles(authority().pubkey())).await;
+}
+
+#[tokio::test]
+async fn test_set_pause_only_writes_the_config() {
+    let keys = RoleKeys::new();
+    let mut program_test = program_test();
+    let decoy = Pubkey::new_unique();
+    let mut account = Account::new(LAMPORTS_PER_SOL, Config::LEN, &card::id());
+    Config::pack(
+        Config {
+            is_initialized: true,
+            roles: keys.roles(),
+            ..Config::default()
+        },
+        &mut account.data,
+    )
+    .unwrap();
+    program_test.add_account(decoy, account);
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, keys.roles()).await;
+
+    let config = find_config_program_address(&card::id()).0;
+    let mut instruction = instruction::set_pause(&card::id(), &keys.admin.pubkey(), PAUSE_ALL);
+    instruction.accounts[1] = AccountMeta::new(decoy, false);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(config, false));
+    let result = process(&mut context, instruction, &[&keys.admin]).await;
+    assert_card_error(result, CardError::InvalidConfig);
+}
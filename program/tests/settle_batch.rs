@@ -0,0 +1,127 @@
+mod common;
+
+use card::{
+    error::CardError,
+    find_escrow_program_address, find_program_authority,
+    instruction::{self, InitEscrowArgs},
+};
+use common::*;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+const FIXED_FEE: u64 = 10_000;
+
+async fn setup(wallet: &Keypair) -> TokenSetup {
+    TokenSetup::start(&wallet.pubkey(), 0, FIXED_FEE, None).await
+}
+
+/// Escrow of `amount` paid into the shared vault, or held in the wallet token account
+async fn init_escrow(setup: &mut TokenSetup, wallet: &Keypair, amount: u64, hold: bool) -> Pubkey {
+    let reference = Pubkey::new_unique();
+    let (escrow, bump) = find_escrow_program_address(&card::id(), &reference);
+    let vault_token = if hold {
+        setup.wallet_token
+    } else {
+        setup.vault_token
+    };
+    let instruction = instruction::init_escrow(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &setup.context.payer.pubkey(),
+        &escrow,
+        &find_program_authority(&card::id()).0,
+        &vault_token,
+        &setup.wallet_token,
+        &setup.dst_token,
+        &setup.fee_token,
+        &setup.mint,
+        &reference,
+        InitEscrowArgs {
+            amount,
+            fee_bps: 0,
+            fixed_fee: FIXED_FEE,
+            bump,
+            merchant: None,
+            split: None,
+            hold,
+            mcc: 0,
+        },
+    );
+    process(&mut setup.context, instruction, &[wallet, &authority()])
+        .await
+        .unwrap();
+    escrow
+}
+
+#[tokio::test]
+async fn test_settle_batch_collects_fees_per_vault() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let first = init_escrow(&mut setup, &wallet, 1_000_000, false).await;
+    let second = init_escrow(&mut setup, &wallet, 2_000_000, false).await;
+    let held = init_escrow(&mut setup, &wallet, 3_000_000, true).await;
+    let canceled = init_escrow(&mut setup, &wallet, 4_000_000, false).await;
+    let instruction = instruction::cancel_escrow(
+        &card::id(),
+        &authority().pubkey(),
+        &canceled,
+        &setup.wallet_token,
+        &setup.vault_token,
+        &setup.mint,
+        &find_program_authority(&card::id()).0,
+    );
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+
+    let escrows = [
+        (first, setup.vault_token, setup.dst_token),
+        (second, setup.vault_token, setup.dst_token),
+        (held, setup.wallet_token, setup.dst_token),
+        (canceled, setup.vault_token, setup.dst_token),
+    ];
+    let instruction = instruction::settle_batch(
+        &card::id(),
+        &authority().pubkey(),
+        &setup.fee_token,
+        &setup.mint,
+        &escrows,
+    );
+    process(&mut setup.context, instruction, &[&authority()])
+        .await
+        .unwrap();
+
+    let context = &mut setup.context;
+    assert_eq!(token_amount(context, &setup.dst_token).await, 6_000_000);
+    assert_eq!(token_amount(context, &setup.fee_token).await, 3 * FIXED_FEE);
+    assert_eq!(token_amount(context, &setup.vault_token).await, 0);
+    assert_eq!(
+        token_amount(context, &setup.wallet_token).await,
+        INITIAL_AMOUNT - 6_000_000 - 3 * FIXED_FEE
+    );
+    for escrow in [first, second, held] {
+        assert!(escrow_state(context, &escrow).await.is_settled);
+    }
+    // the canceled escrow is skipped rather than failing the batch
+    let canceled = escrow_state(context, &canceled).await;
+    assert!(canceled.is_canceled && !canceled.is_settled);
+}
+
+#[tokio::test]
+async fn test_settle_batch_requires_settler() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let escrow = init_escrow(&mut setup, &wallet, 1_000_000, false).await;
+    let outsider = Keypair::new();
+    let instruction = instruction::settle_batch(
+        &card::id(),
+        &outsider.pubkey(),
+        &setup.fee_token,
+        &setup.mint,
+        &[(escrow, setup.vault_token, setup.dst_token)],
+    );
+    let result = process(&mut setup.context, instruction, &[&outsider]).await;
+    assert_card_error(result, CardError::MissingSettlerRole);
+    assert!(!escrow_state(&mut setup.context, &escrow).await.is_settled);
+}
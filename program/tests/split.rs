@@ -0,0 +1,245 @@
+mod common;
+
+use card::{
+    collections::deposit,
+    error::CardError,
+    instruction::{self, DisputeArgs, DisputeOutcome, ResolveDisputeArgs},
+    state::escrow::{Payee, ShareKind, Split, MAX_PAYEES},
+};
+use common::*;
+use solana_program::{instruction::Instruction, pubkey::Pubkey, rent::Rent};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+};
+
+/// Seller, platform and referrer wallets
+struct Payees {
+    seller: Pubkey,
+    platform: Pubkey,
+    referrer: Pubkey,
+}
+
+impl Payees {
+    fn new() -> Self {
+        Self {
+            seller: Pubkey::new_unique(),
+            platform: Pubkey::new_unique(),
+            referrer: Pubkey::new_unique(),
+        }
+    }
+
+    fn keys(&self) -> [Pubkey; 3] {
+        [self.seller, self.platform, self.referrer]
+    }
+
+    /// `seller_bps` to the seller and 30% to the platform after a fixed
+    /// referrer fee, the seller getting the dust
+    fn split(&self, seller_bps: u64) -> Split {
+        let mut payees = [Payee::default(); MAX_PAYEES];
+        payees[0] = Payee {
+            token: self.seller,
+            kind: ShareKind::Bps,
+            share: seller_bps,
+        };
+        payees[1] = Payee {
+            token: self.platform,
+            kind: ShareKind::Bps,
+            share: 3_000,
+        };
+        payees[2] = Payee {
+            token: self.referrer,
+            kind: ShareKind::Amount,
+            share: 10_000,
+        };
+        Split {
+            payee_count: 3,
+            payees,
+            dust_payee: 0,
+        }
+    }
+}
+
+async fn setup(wallet: &Keypair, payees: &Payees) -> ProgramTestContext {
+    let mut program_test = native_program_test(&wallet.pubkey(), 0, 0);
+    for payee in payees.keys() {
+        program_test.add_account(payee, system_account(Rent::default().minimum_balance(0)));
+    }
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    context
+}
+
+impl NativeEscrow {
+    fn init_split(
+        &self,
+        payer: &Pubkey,
+        wallet: &Keypair,
+        amount: u64,
+        split: Split,
+    ) -> Instruction {
+        let mut args = self.args(amount);
+        args.split = Some(split);
+        self.init_with(&authority().pubkey(), payer, wallet, &deposit::id(), args)
+    }
+
+    fn settle_split(&self, payees: &[Pubkey]) -> Instruction {
+        instruction::with_payees(self.settle(), payees)
+    }
+}
+
+#[tokio::test]
+async fn test_settle_splits_between_payees() {
+    let wallet = Keypair::new();
+    let payees = Payees::new();
+    let mut context = setup(&wallet, &payees).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    let amount = LAMPORTS_PER_SOL + 3;
+    process(
+        &mut context,
+        escrow.init_split(&payer, &wallet, amount, payees.split(7_000)),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+
+    let mut before = Vec::new();
+    for payee in payees.keys() {
+        before.push(balance(&mut context, &payee).await);
+    }
+    let deposit_before = balance(&mut context, &deposit::id()).await;
+    process(
+        &mut context,
+        escrow.settle_split(&payees.keys()),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    // 999_990_003 is left after the referrer, the seller gets the lamport of dust
+    let expected = [699_993_003, 299_997_000, 10_000];
+    for ((payee, before), expected) in payees.keys().iter().zip(before).zip(expected) {
+        assert_eq!(balance(&mut context, payee).await - before, expected);
+    }
+    assert_eq!(expected.iter().sum::<u64>(), amount);
+    assert_eq!(balance(&mut context, &deposit::id()).await, deposit_before);
+}
+
+#[tokio::test]
+async fn test_split_must_cover_the_amount() {
+    let wallet = Keypair::new();
+    let payees = Payees::new();
+    let mut context = setup(&wallet, &payees).await;
+    let payer = context.payer.pubkey();
+
+    let result = process(
+        &mut context,
+        NativeEscrow::new().init_split(&payer, &wallet, LAMPORTS_PER_SOL, payees.split(6_000)),
+        &[&wallet, &authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidPayees);
+
+    let escrow = NativeEscrow::new();
+    process(
+        &mut context,
+        escrow.init_split(&payer, &wallet, LAMPORTS_PER_SOL, payees.split(7_000)),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    let result = process(
+        &mut context,
+        escrow.settle_split(&[payees.seller, payees.platform]),
+        &[&authority()],
+    )
+    .await;
+    assert_card_error(result, CardError::InvalidPayeeAccount);
+    process(
+        &mut context,
+        escrow.settle_split(&payees.keys()),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+}
+
+impl NativeEscrow {
+    fn dispute(&self) -> Instruction {
+        instruction::dispute(
+            &card::id(),
+            &authority().pubkey(),
+            &self.escrow,
+            DisputeArgs { reason: 0 },
+        )
+    }
+}
+
+#[tokio::test]
+async fn test_pending_split_dispute_is_refunded() {
+    let wallet = Keypair::new();
+    let payees = Payees::new();
+    let mut context = setup(&wallet, &payees).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    process(
+        &mut context,
+        escrow.init_split(&payer, &wallet, LAMPORTS_PER_SOL, payees.split(7_000)),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    process(&mut context, escrow.dispute(), &[&authority()])
+        .await
+        .unwrap();
+
+    let wallet_before = balance(&mut context, &wallet.pubkey()).await;
+    process(
+        &mut context,
+        instruction::resolve_dispute(
+            &card::id(),
+            &authority().pubkey(),
+            &escrow.escrow,
+            ResolveDisputeArgs {
+                outcome: DisputeOutcome::Refund,
+            },
+            Some(escrow.cancel(&wallet)),
+        ),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        balance(&mut context, &wallet.pubkey()).await,
+        wallet_before + LAMPORTS_PER_SOL
+    );
+    assert!(escrow_state(&mut context, &escrow.escrow).await.is_canceled);
+}
+
+#[tokio::test]
+async fn test_settled_split_cannot_be_disputed() {
+    let wallet = Keypair::new();
+    let payees = Payees::new();
+    let mut context = setup(&wallet, &payees).await;
+    let payer = context.payer.pubkey();
+    let escrow = NativeEscrow::new();
+    process(
+        &mut context,
+        escrow.init_split(&payer, &wallet, LAMPORTS_PER_SOL, payees.split(7_000)),
+        &[&wallet, &authority()],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        escrow.settle_split(&payees.keys()),
+        &[&authority()],
+    )
+    .await
+    .unwrap();
+
+    let result = process(&mut context, escrow.dispute(), &[&authority()]).await;
+    assert_card_error(result, CardError::DisputeNotAllowed);
+}
@@ -0,0 +1,315 @@
+mod common;
+
+use card::{
+    collections::{deposit, fee},
+    find_card_program_address, find_deposit_program_address, find_mint_config_program_address,
+    find_program_authority, find_withdrawal_program_address, find_wrap_program_address,
+    instruction::{self, DepositArgs, InitCardArgs, WithdrawArgs},
+    state::{
+        card::{Card, CardLimits, CardParams},
+        FLAG_ACCOUNT_SIZE,
+    },
+    utils::calculate_fee,
+};
+use common::*;
+use solana_program::{
+    instruction::Instruction, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+    rent::Rent,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+const FEE_BPS: u16 = 250;
+const FIXED_FEE: u64 = 5_000;
+
+struct Setup {
+    context: ProgramTestContext,
+    collection_token: Pubkey,
+    fee_token: Pubkey,
+    wallet_token: Pubkey,
+}
+
+fn wrapped_account(owner: &Pubkey, amount: u64) -> Account {
+    let reserve = Rent::default().minimum_balance(TokenAccount::LEN);
+    let mut account = Account::new(reserve + amount, TokenAccount::LEN, &spl_token::id());
+    TokenAccount::pack(
+        TokenAccount {
+            mint: spl_token::native_mint::id(),
+            owner: *owner,
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::Some(reserve),
+            delegated_amount: 0,
+            close_authority: COption::None,
+        },
+        &mut account.data,
+    )
+    .unwrap();
+    account
+}
+
+async fn setup(wallet: &Keypair) -> Setup {
+    let mut program_test = program_test();
+    let collection_token = Pubkey::new_unique();
+    let fee_token = Pubkey::new_unique();
+    let wallet_token = Pubkey::new_unique();
+    program_test.add_account(collection_token, wrapped_account(&deposit::id(), 0));
+    program_test.add_account(fee_token, wrapped_account(&fee::id(), 0));
+    program_test.add_account(
+        wallet_token,
+        wrapped_account(&wallet.pubkey(), LAMPORTS_PER_SOL),
+    );
+    program_test.add_account(wallet.pubkey(), system_account(10 * LAMPORTS_PER_SOL));
+    program_test.add_account(
+        find_mint_config_program_address(&card::id(), &spl_token::native_mint::id()).0,
+        mint_config_account(
+            &spl_token::native_mint::id(),
+            &collection_token,
+            &fee_token,
+            FEE_BPS,
+            FIXED_FEE,
+        ),
+    );
+    let mut context = program_test.start_with_context().await;
+    init_config(&mut context, roles(authority().pubkey())).await;
+    Setup {
+        context,
+        collection_token,
+        fee_token,
+        wallet_token,
+    }
+}
+
+fn total_fee(amount: u64) -> u64 {
+    calculate_fee(amount, FEE_BPS as u64).unwrap() + FIXED_FEE
+}
+
+fn withdraw(
+    setup: &Setup,
+    wallet: &Keypair,
+    source: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) -> (Instruction, Pubkey) {
+    let key = Pubkey::new_unique();
+    let (withdraw, bump) = find_withdrawal_program_address(&card::id(), &key);
+    let wrap = find_wrap_program_address(&card::id(), &key).0;
+    let instruction = instruction::withdraw_native(
+        &card::id(),
+        &wallet.pubkey(),
+        &authority().pubkey(),
+        &setup.context.payer.pubkey(),
+        &withdraw,
+        source,
+        destination,
+        &setup.fee_token,
+        &find_program_authority(&card::id()).0,
+        &wrap,
+        WithdrawArgs {
+            amount,
+            fee_bps: FEE_BPS,
+            key,
+            bump,
+            fixed_fee: FIXED_FEE,
+        },
+    );
+    (instruction, wrap)
+}
+
+#[tokio::test]
+async fn test_deposit_wraps_native_sol() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let payer = setup.context.payer.pubkey();
+    let amount = LAMPORTS_PER_SOL;
+    // without a fee schedule the mint's default fee is charged, fixed part included
+    let fee = total_fee(amount);
+    let key = Pubkey::new_unique();
+    let (deposit, bump) = find_deposit_program_address(&card::id(), &key);
+    let wrap = find_wrap_program_address(&card::id(), &key).0;
+    let wallet_before = balance(&mut setup.context, &wallet.pubkey()).await;
+    let payer_before = balance(&mut setup.context, &payer).await;
+
+    let tx_fee = process_fee(
+        &mut setup.context,
+        instruction::deposit_native(
+            &card::id(),
+            &wallet.pubkey(),
+            &authority().pubkey(),
+            &payer,
+            &deposit,
+            &setup.collection_token,
+            &setup.fee_token,
+            &find_program_authority(&card::id()).0,
+            &wrap,
+            DepositArgs {
+                amount,
+                fee_bps: FEE_BPS,
+                key,
+                bump,
+            },
+        ),
+        &[&wallet, &authority()],
+    )
+    .await;
+
+    let context = &mut setup.context;
+    assert_eq!(
+        balance(context, &wallet.pubkey()).await,
+        wallet_before - amount - fee
+    );
+    assert_eq!(token_amount(context, &setup.collection_token).await, amount);
+    assert_eq!(token_amount(context, &setup.fee_token).await, fee);
+    assert_eq!(balance(context, &wrap).await, 0);
+    assert_eq!(
+        balance(context, &payer).await,
+        payer_before - tx_fee - Rent::default().minimum_balance(FLAG_ACCOUNT_SIZE)
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw_between_wallets() {
+    let wallet = Keypair::new();
+    let receiver = Pubkey::new_unique();
+    let mut setup = setup(&wallet).await;
+    let amount = LAMPORTS_PER_SOL / 2;
+    let wallet_before = balance(&mut setup.context, &wallet.pubkey()).await;
+
+    let (instruction, wrap) = withdraw(&setup, &wallet, &wallet.pubkey(), &receiver, amount);
+    process_fee(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+
+    let context = &mut setup.context;
+    assert_eq!(
+        balance(context, &wallet.pubkey()).await,
+        wallet_before - amount - total_fee(amount)
+    );
+    assert_eq!(balance(context, &receiver).await, amount);
+    assert_eq!(
+        token_amount(context, &setup.fee_token).await,
+        total_fee(amount)
+    );
+    assert_eq!(balance(context, &wrap).await, 0);
+}
+
+#[tokio::test]
+async fn test_withdraw_unwraps_to_wallet() {
+    let wallet = Keypair::new();
+    let receiver = Pubkey::new_unique();
+    let mut setup = setup(&wallet).await;
+    let amount = LAMPORTS_PER_SOL / 2;
+    let wallet_token = setup.wallet_token;
+    let payer = setup.context.payer.pubkey();
+    let payer_before = balance(&mut setup.context, &payer).await;
+
+    let (instruction, wrap) = withdraw(&setup, &wallet, &wallet_token, &receiver, amount);
+    let tx_fee = process_fee(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+
+    let context = &mut setup.context;
+    assert_eq!(
+        token_amount(context, &wallet_token).await,
+        LAMPORTS_PER_SOL - amount - total_fee(amount)
+    );
+    assert_eq!(balance(context, &receiver).await, amount);
+    assert_eq!(
+        token_amount(context, &setup.fee_token).await,
+        total_fee(amount)
+    );
+    assert_eq!(balance(context, &wrap).await, 0);
+    assert_eq!(
+        balance(context, &payer).await,
+        payer_before - tx_fee - Rent::default().minimum_balance(FLAG_ACCOUNT_SIZE)
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw_wraps_from_wallet() {
+    let wallet = Keypair::new();
+    let mut setup = setup(&wallet).await;
+    let amount = LAMPORTS_PER_SOL / 2;
+    let wallet_token = setup.wallet_token;
+    let wallet_before = balance(&mut setup.context, &wallet.pubkey()).await;
+
+    let (instruction, wrap) = withdraw(&setup, &wallet, &wallet.pubkey(), &wallet_token, amount);
+    process_fee(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+
+    let context = &mut setup.context;
+    assert_eq!(
+        balance(context, &wallet.pubkey()).await,
+        wallet_before - amount - total_fee(amount)
+    );
+    assert_eq!(
+        token_amount(context, &wallet_token).await,
+        LAMPORTS_PER_SOL + amount
+    );
+    assert_eq!(
+        token_amount(context, &setup.fee_token).await,
+        total_fee(amount)
+    );
+    assert_eq!(balance(context, &wrap).await, 0);
+}
+
+#[tokio::test]
+async fn test_withdraw_rejects_other_token_program() {
+    let wallet = Keypair::new();
+    let receiver = Pubkey::new_unique();
+    let mut setup = setup(&wallet).await;
+    let wallet_token = setup.wallet_token;
+
+    let (mut instruction, _) = withdraw(&setup, &wallet, &wallet_token, &receiver, 1_000);
+    instruction.accounts[10].pubkey = Pubkey::new_unique();
+    let result = process(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+    assert_eq!(
+        result.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw_counts_against_card() {
+    let wallet = Keypair::new();
+    let receiver = Pubkey::new_unique();
+    let mut setup = setup(&wallet).await;
+    let amount = LAMPORTS_PER_SOL / 2;
+    let payer = setup.context.payer.pubkey();
+    let card = find_card_program_address(&card::id(), &wallet.pubkey());
+    let instruction = instruction::init_card(
+        &card::id(),
+        &authority().pubkey(),
+        &payer,
+        InitCardArgs {
+            owner: wallet.pubkey(),
+            params: CardParams {
+                funding_token: wallet.pubkey(),
+                limits: CardLimits {
+                    per_transaction: LAMPORTS_PER_SOL,
+                    daily: LAMPORTS_PER_SOL,
+                    monthly: LAMPORTS_PER_SOL,
+                },
+            },
+            bump: card.1,
+        },
+    );
+    process_fee(&mut setup.context, instruction, &[&authority()]).await;
+
+    let (instruction, _) = withdraw(&setup, &wallet, &wallet.pubkey(), &receiver, amount);
+    process_fee(&mut setup.context, instruction, &[&wallet, &authority()]).await;
+
+    let account = setup
+        .context
+        .banks_client
+        .get_account(card.0)
+        .await
+        .unwrap()
+        .unwrap();
+    let card = Card::unpack(&account.data).unwrap();
+    assert_eq!(card.daily_spent, amount);
+    assert_eq!(card.monthly_spent, amount);
+}
@@ -9,7 +9,101 @@ import { AccountInfo, PublicKey } from '@solana/web3.js';
 import BN from 'bn.js';
 import { CardProgram } from '../cardProgram';
 
-export const MAX_ESCROW_DATA_LEN = 213;
+export const MAX_ESCROW_DATA_LEN = 505;
+// size of the escrows written by the first release, grown by the Migrate instruction
+export const MAX_ESCROW_LEGACY_DATA_LEN = 213;
+export const MAX_PAYEES = 4;
+
+export enum ShareKind {
+  Bps = 0,
+  Amount = 1,
+}
+
+export enum DisputeStatus {
+  None = 0,
+  Disputed = 1,
+  Released = 2,
+  Refunded = 3,
+}
+
+export type PayeeDataArgs = {
+  token: StringPublicKey;
+  kind: ShareKind;
+  share: BN;
+};
+
+export class PayeeData extends Borsh.Data<PayeeDataArgs> {
+  static readonly SCHEMA = PayeeData.struct([
+    ['token', 'pubkeyAsString'],
+    ['kind', 'u8'],
+    ['share', 'u64'],
+  ]);
+  token: StringPublicKey;
+  kind: ShareKind;
+  share: BN;
+
+  constructor(args: PayeeDataArgs) {
+    super(args);
+  }
+}
+
+export type SplitDataArgs = {
+  payeeCount: number;
+  payee0: PayeeData;
+  payee1: PayeeData;
+  payee2: PayeeData;
+  payee3: PayeeData;
+  dustPayee: number;
+};
+
+export class SplitData extends Borsh.Data<SplitDataArgs> {
+  static readonly SCHEMA = new Map([
+    ...SplitData.struct([
+      ['payeeCount', 'u8'],
+      ['payee0', PayeeData],
+      ['payee1', PayeeData],
+      ['payee2', PayeeData],
+      ['payee3', PayeeData],
+      ['dustPayee', 'u8'],
+    ]),
+    ...PayeeData.SCHEMA,
+  ]);
+  payeeCount: number;
+  payee0: PayeeData;
+  payee1: PayeeData;
+  payee2: PayeeData;
+  payee3: PayeeData;
+  dustPayee: number;
+
+  constructor(args: SplitDataArgs) {
+    super(args);
+  }
+
+  static fromPayees(payees: PayeeDataArgs[], dustPayee: number): SplitData {
+    const slots = [...Array(MAX_PAYEES).keys()].map(
+      (i) =>
+        new PayeeData(
+          payees[i] ?? {
+            token: PublicKey.default.toBase58(),
+            kind: ShareKind.Bps,
+            share: new BN(0),
+          },
+        ),
+    );
+    return new SplitData({
+      payeeCount: payees.length,
+      payee0: slots[0],
+      payee1: slots[1],
+      payee2: slots[2],
+      payee3: slots[3],
+      dustPayee,
+    });
+  }
+
+  payees(): PayeeData[] {
+    return [this.payee0, this.payee1, this.payee2, this.payee3].slice(0, this.payeeCount);
+  }
+}
 
 export type EscrowDataArgs = {
   isInitialized: boolean;
@@ -24,10 +118,108 @@ export type EscrowDataArgs = {
   feeToken: StringPublicKey;
   mint: StringPublicKey;
   reference: StringPublicKey;
+  fee: BN;
+  merchant: StringPublicKey;
+  split: SplitData;
+  refundedAmount: BN;
+  refundedFee: BN;
+  disputeStatus: DisputeStatus;
+  settledAt: BN;
+  disputedAt: BN;
+  disputeReason: number;
+  resolvedAt: BN;
+  isHold: boolean;
+  authorityBump: number;
+  vaultBump: number;
+  card: StringPublicKey;
+  chargedAt: BN;
 };
 
 export class EscrowData extends Borsh.Data<EscrowDataArgs> {
-  static readonly SCHEMA = EscrowData.struct([
+  static readonly SCHEMA = new Map([
+    ...EscrowData.struct([
+      ['isInitialized', 'u8'],
+      ['isSettled', 'u8'],
+      ['isCanceled', 'u8'],
+      ['amount', 'u64'],
+      ['feeBps', 'u16'],
+      ['fixedFee', 'u64'],
+      ['srcToken', 'pubkeyAsString'],
+      ['dstToken', 'pubkeyAsString'],
+      ['vaultToken', 'pubkeyAsString'],
+      ['feeToken', 'pubkeyAsString'],
+      ['mint', 'pubkeyAsString'],
+      ['reference', 'pubkeyAsString'],
+      ['fee', 'u64'],
+      ['merchant', 'pubkeyAsString'],
+      ['split', SplitData],
+      ['refundedAmount', 'u64'],
+      ['refundedFee', 'u64'],
+      ['disputeStatus', 'u8'],
+      ['settledAt', 'u64'],
+      ['disputedAt', 'u64'],
+      ['disputeReason', 'u16'],
+      ['resolvedAt', 'u64'],
+      ['isHold', 'u8'],
+      ['authorityBump', 'u8'],
+      ['vaultBump', 'u8'],
+      ['card', 'pubkeyAsString'],
+      ['chargedAt', 'u64'],
+    ]),
+    ...SplitData.SCHEMA,
+  ]);
+  isInitialized: boolean;
+  isSettled: boolean;
+  isCanceled: boolean;
+  amount: BN;
+  feeBps: number;
+  fixedFee: BN;
+  srcToken: StringPublicKey;
+  dstToken: StringPublicKey;
+  vaultToken: StringPublicKey;
+  feeToken: StringPublicKey;
+  mint: StringPublicKey;
+  reference: StringPublicKey;
+  fee: BN;
+  merchant: StringPublicKey;
+  split: SplitData;
+  refundedAmount: BN;
+  refundedFee: BN;
+  disputeStatus: DisputeStatus;
+  settledAt: BN;
+  disputedAt: BN;
+  disputeReason: number;
+  resolvedAt: BN;
+  isHold: boolean;
+  authorityBump: number;
+  vaultBump: number;
+  card: StringPublicKey;
+  chargedAt: BN;
+
+  constructor(args: EscrowDataArgs) {
+    super(args);
+  }
+}
+
+// fields of the escrows written by the first release, all the Migrate instruction needs
+export type EscrowLegacyDataArgs = Pick<
+  EscrowDataArgs,
+  | 'isInitialized'
+  | 'isSettled'
+  | 'isCanceled'
+  | 'amount'
+  | 'feeBps'
+  | 'fixedFee'
+  | 'srcToken'
+  | 'dstToken'
+  | 'vaultToken'
+  | 'feeToken'
+  | 'mint'
+  | 'reference'
+>;
+
+export class EscrowLegacyData extends Borsh.Data<EscrowLegacyDataArgs> {
+  static readonly SCHEMA = EscrowLegacyData.struct([
     ['isInitialized', 'u8'],
     ['isSettled', 'u8'],
     ['isCanceled', 'u8'],
@@ -54,13 +246,14 @@ export class EscrowData extends Borsh.Data<EscrowDataArgs> {
   mint: StringPublicKey;
   reference: StringPublicKey;
 
-  constructor(args: EscrowDataArgs) {
+  constructor(args: EscrowLegacyDataArgs) {
     super(args);
   }
 }
 
 export class Escrow extends Account<EscrowData> {
   static readonly PREFIX = 'escrow';
+  static readonly VAULT_PREFIX = 'vault';
   constructor(pubkey: AnyPublicKey, info: AccountInfo<Buffer>) {
     super(pubkey, info);
     this.data = EscrowData.deserialize(this.info.data);
@@ -69,6 +262,10 @@ export class Escrow extends Account<EscrowData> {
     }
   }
 
+  static isLegacy(info: AccountInfo<Buffer>): boolean {
+    return info.data.length === MAX_ESCROW_LEGACY_DATA_LEN;
+  }
+
   static async getPDA(key: AnyPublicKey) {
     const [pubKey] = await CardProgram.findEscrowAccount(new PublicKey(key));
     return pubKey;
@@ -4,6 +4,11 @@ import { Deposit, Escrow, Withdraw } from './accounts';
 
 export class CardProgram extends Program {
   static readonly PREFIX = 'card';
+  static readonly CONFIG_PREFIX = 'config';
+  static readonly MINT_CONFIG_PREFIX = 'mint';
+  static readonly FEE_SCHEDULE_PREFIX = 'fees';
+  static readonly MERCHANT_PREFIX = 'merchant';
+  static readonly CARD_PREFIX = 'card';
   static readonly PUBKEY = new PublicKey('cardFRMHxFN4X1urijmqb7gWSMT7bAep4Pd4LuLciG3');
 
   static async findProgramAuthority(): Promise<[PublicKey, number]> {
@@ -49,6 +54,77 @@ export class CardProgram extends Program {
     );
   }
 
+  static async findEscrowVaultAccount(key: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddress(
+      [
+        Buffer.from(CardProgram.PREFIX),
+        CardProgram.PUBKEY.toBuffer(),
+        key.toBuffer(),
+        Buffer.from(Escrow.VAULT_PREFIX),
+      ],
+      CardProgram.PUBKEY,
+    );
+  }
+
+  static async findConfigAccount(): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddress(
+      [
+        Buffer.from(CardProgram.PREFIX),
+        CardProgram.PUBKEY.toBuffer(),
+        Buffer.from(CardProgram.CONFIG_PREFIX),
+      ],
+      CardProgram.PUBKEY,
+    );
+  }
+
+  static async findMintConfigAccount(mint: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddress(
+      [
+        Buffer.from(CardProgram.PREFIX),
+        CardProgram.PUBKEY.toBuffer(),
+        mint.toBuffer(),
+        Buffer.from(CardProgram.MINT_CONFIG_PREFIX),
+      ],
+      CardProgram.PUBKEY,
+    );
+  }
+
+  static async findFeeScheduleAccount(mint: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddress(
+      [
+        Buffer.from(CardProgram.PREFIX),
+        CardProgram.PUBKEY.toBuffer(),
+        mint.toBuffer(),
+        Buffer.from(CardProgram.FEE_SCHEDULE_PREFIX),
+      ],
+      CardProgram.PUBKEY,
+    );
+  }
+
+  static async findMerchantAccount(id: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddress(
+      [
+        Buffer.from(CardProgram.PREFIX),
+        CardProgram.PUBKEY.toBuffer(),
+        id.toBuffer(),
+        Buffer.from(CardProgram.MERCHANT_PREFIX),
+      ],
+      CardProgram.PUBKEY,
+    );
+  }
+
+  static async findCardAccount(owner: PublicKey): Promise<[PublicKey, number]> {
+    return PublicKey.findProgramAddress(
+      [
+        Buffer.from(CardProgram.PREFIX),
+        CardProgram.PUBKEY.toBuffer(),
+        owner.toBuffer(),
+        Buffer.from(CardProgram.CARD_PREFIX),
+      ],
+      CardProgram.PUBKEY,
+    );
+  }
+
   static async findKey(key: string): Promise<[PublicKey, number]> {
     return PublicKey.findProgramAddress(
       [
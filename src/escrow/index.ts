@@ -13,13 +13,23 @@ import * as spl from '@solana/spl-token';
 import BN from 'bn.js';
 import { InitializePaymentInput, EscrowInput, WithdrawalInput, ResultContext } from './types';
 import { CardProgram } from '../cardProgram';
-import { Escrow } from '../accounts/escrow';
+import { Escrow, EscrowLegacyData } from '../accounts/escrow';
 import { InitEscrowArgs, InitEscrowParams } from '../transactions/InitEscrow';
 import { CancelEscrowArgs, CancelEscrowParams } from '../transactions/CancelEscrow';
 import { CloseEscrowArgs, CloseEscrowParams } from '../transactions/CloseEscrow';
 import { SettleEscrowArgs, SettleEscrowParams } from '../transactions/SettleEscrow';
 import { InitDepositArgs, InitDepositParams } from '../transactions/InitDeposit';
-import { InitWithdrawArgs, InitWithdrawParams } from '../transactions';
+import {
+  BatchDeposit,
+  InitDepositBatchArgs,
+  InitDepositBatchParams,
+  InitWithdrawArgs,
+  InitWithdrawParams,
+  MigrateArgs,
+  MigrateParams,
+  SettleBatchArgs,
+  SettleBatchParams,
+} from '../transactions';
 import { Deposit, Withdraw } from '../accounts';
 import { Account } from '@metaplex-foundation/mpl-core';
 
@@ -33,6 +43,7 @@ export const INVALID_SIGNATURE = 'Invalid signature';
 export const AMOUNT_MISMATCH = 'Amount mismatch';
 export const FEE_MISMATCH = 'Fee mismatch';
 export const TRANSACTION_SEND_ERROR = 'Transaction send error';
+export const ACCOUNT_NOT_MIGRATABLE = 'Account not migratable';
 export const MEMO_PROGRAM_ID = new PublicKey('Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo');
 
 export class EscrowClient {
@@ -66,6 +77,7 @@ export class EscrowClient {
       throw new Error(ACCOUNT_ALREADY_SETTLED);
     }
     const [vault] = await CardProgram.findProgramAuthority();
+    const [config] = await CardProgram.findConfigAccount();
     const exchangeInstruction = await this.cancelInstruction({
       vaultOwner: vault,
       vaultToken: new PublicKey(escrow.data.vaultToken),
@@ -73,6 +85,8 @@ export class EscrowClient {
       authority: this.authority.publicKey,
       escrow: escrow.pubkey,
       mint: new PublicKey(escrow.data.mint),
+      config,
+      card: _optionalKey(escrow.data.card),
     });
     const transaction = new Transaction().add(exchangeInstruction);
     if (input.memo) {
@@ -115,6 +129,7 @@ export class EscrowClient {
       throw new Error(ACCOUNT_ALREADY_SETTLED);
     }
     const [vault] = await CardProgram.findProgramAuthority();
+    const [config] = await CardProgram.findConfigAccount();
     const exchangeInstruction = await this.cancelInstruction({
       vaultOwner: vault,
       vaultToken: new PublicKey(escrow.data.vaultToken),
@@ -122,11 +137,14 @@ export class EscrowClient {
       authority: this.authority.publicKey,
       escrow: escrow.pubkey,
       mint: new PublicKey(escrow.data.mint),
+      config,
+      card: _optionalKey(escrow.data.card),
     });
     const closeInstruction = this.closeInstruction({
       escrow: escrow.pubkey,
       authority: this.authority.publicKey,
       feePayer: this.feePayer.publicKey,
+      config,
     });
     const transaction = new Transaction().add(exchangeInstruction, closeInstruction);
     if (input.memo) {
@@ -160,39 +178,47 @@ export class EscrowClient {
   };
 
   cancelInstruction = async (params: CancelEscrowParams): Promise<TransactionInstruction> => {
+    const keys = [
+      { pubkey: params.authority, isSigner: true, isWritable: false },
+      { pubkey: params.escrow, isSigner: false, isWritable: true },
+      {
+        pubkey: params.sourceToken,
+        isSigner: false,
+        isWritable: true,
+      },
+      {
+        pubkey: params.vaultToken,
+        isSigner: false,
+        isWritable: true,
+      },
+      { pubkey: params.mint, isSigner: false, isWritable: false },
+      { pubkey: params.vaultOwner, isSigner: false, isWritable: false },
+      { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      {
+        pubkey: SystemProgram.programId,
+        isSigner: false,
+        isWritable: false,
+      },
+      { pubkey: params.config, isSigner: false, isWritable: false },
+    ];
+    // the card the escrow was charged to gets the amount back to its counters
+    if (params.card) {
+      keys.push({ pubkey: params.card, isSigner: false, isWritable: true });
+    }
     return new TransactionInstruction({
       programId: CardProgram.PUBKEY,
       data: CancelEscrowArgs.serialize(),
-      keys: [
-        { pubkey: params.authority, isSigner: true, isWritable: false },
-        { pubkey: params.escrow, isSigner: false, isWritable: true },
-        {
-          pubkey: params.sourceToken,
-          isSigner: false,
-          isWritable: true,
-        },
-        {
-          pubkey: params.vaultToken,
-          isSigner: false,
-          isWritable: true,
-        },
-        { pubkey: params.mint, isSigner: false, isWritable: false },
-        { pubkey: params.vaultOwner, isSigner: false, isWritable: false },
-        { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
-        {
-          pubkey: SystemProgram.programId,
-          isSigner: false,
-          isWritable: false,
-        },
-      ],
+      keys,
     });
   };
 
   close = async (input: EscrowInput): Promise<string> => {
+    const [config] = await CardProgram.findConfigAccount();
     const exchangeInstruction = this.closeInstruction({
       escrow: new PublicKey(input.escrowAddress),
       authority: this.authority.publicKey,
       feePayer: this.feePayer.publicKey,
+      config,
     });
     const transaction = new Transaction().add(exchangeInstruction);
     if (input.memo) {
@@ -234,6 +260,12 @@ export class EscrowClient {
           isWritable: true,
         },
         { pubkey: params.feePayer, isSigner: false, isWritable: true },
+        {
+          pubkey: SystemProgram.programId,
+          isSigner: false,
+          isWritable: false,
+        },
+        { pubkey: params.config, isSigner: false, isWritable: false },
       ],
     });
   };
@@ -255,11 +287,19 @@ export class EscrowClient {
     const amount = new BN(input.amount);
     const feeBps = input.feeBps ?? 0;
     const fixedFee = new BN(input.fixedFee ?? 0);
+    const merchant = input.merchant ? new PublicKey(input.merchant) : undefined;
     const [sourceToken, destinationToken, collectionFeeToken] = await Promise.all([
       _findAssociatedTokenAddress(walletAddress, mint),
       _findAssociatedTokenAddress(this.fundingWallet, mint),
       _findAssociatedTokenAddress(this.feeWallet, mint),
     ]);
+    const [[config], [mintConfig], [feeSchedule], [card], merchantAccount] = await Promise.all([
+      CardProgram.findConfigAccount(),
+      CardProgram.findMintConfigAccount(mint),
+      CardProgram.findFeeScheduleAccount(mint),
+      CardProgram.findCardAccount(walletAddress),
+      merchant ? CardProgram.findMerchantAccount(merchant).then(([key]) => key) : undefined,
+    ]);
     const escrowParams: InitEscrowParams = {
       mint,
       bump,
@@ -276,6 +316,13 @@ export class EscrowClient {
       wallet: walletAddress,
       authority: this.authority.publicKey,
       payer: this.feePayer.publicKey,
+      config,
+      mintConfig,
+      feeSchedule,
+      card,
+      merchant,
+      merchantAccount,
+      mcc: input.mcc,
     };
 
     const transaction = new Transaction();
@@ -329,12 +376,25 @@ export class EscrowClient {
       destinationToken,
       collectionFeeToken,
       mint,
+      config,
+      mintConfig,
+      feeSchedule,
+      card,
+      merchant,
+      merchantAccount,
+      split,
+      hold,
+      mcc,
     } = params;
     const data = InitEscrowArgs.serialize({
       amount,
       feeBps,
       fixedFee,
       bump,
+      merchant: merchant ? merchant.toBase58() : null,
+      split: split ?? null,
+      hold: hold ? 1 : 0,
+      mcc: mcc ?? 0,
     });
     const keys = [
       {
@@ -407,7 +467,11 @@ export class EscrowClient {
         isSigner: false,
         isWritable: false,
       },
+      ...this.mintAccounts(config, mintConfig, feeSchedule, card),
     ];
+    if (merchantAccount) {
+      keys.push({ pubkey: merchantAccount, isSigner: false, isWritable: false });
+    }
     return new TransactionInstruction({
       keys,
       data,
@@ -428,6 +492,12 @@ export class EscrowClient {
       _findAssociatedTokenAddress(this.fundingWallet, mint),
       _findAssociatedTokenAddress(this.feeWallet, mint),
     ]);
+    const [[config], [mintConfig], [feeSchedule], [card]] = await Promise.all([
+      CardProgram.findConfigAccount(),
+      CardProgram.findMintConfigAccount(mint),
+      CardProgram.findFeeScheduleAccount(mint),
+      CardProgram.findCardAccount(walletAddress),
+    ]);
     const depositParams: InitDepositParams = {
       mint,
       user: walletAddress,
@@ -441,6 +511,10 @@ export class EscrowClient {
       key: reference,
       authority: this.authority.publicKey,
       payer: this.feePayer.publicKey,
+      config,
+      mintConfig,
+      feeSchedule,
+      card,
     };
 
     const transaction = new Transaction();
@@ -491,6 +565,10 @@ export class EscrowClient {
       collectionToken,
       collectionFeeToken,
       mint,
+      config,
+      mintConfig,
+      feeSchedule,
+      card,
     } = params;
     const data = InitDepositArgs.serialize({
       amount,
@@ -512,7 +590,7 @@ export class EscrowClient {
       {
         pubkey: this.feePayer.publicKey,
         isSigner: true,
-        isWritable: false,
+        isWritable: true,
       },
       {
         pubkey: deposit,
@@ -554,6 +632,7 @@ export class EscrowClient {
         isSigner: false,
         isWritable: false,
       },
+      ...this.mintAccounts(config, mintConfig, feeSchedule, card),
     ];
     return new TransactionInstruction({
       keys,
@@ -575,6 +654,12 @@ export class EscrowClient {
       _findAssociatedTokenAddress(source, mint),
       _findAssociatedTokenAddress(this.feeWallet, mint),
     ]);
+    const [[config], [mintConfig], [feeSchedule], [card]] = await Promise.all([
+      CardProgram.findConfigAccount(),
+      CardProgram.findMintConfigAccount(mint),
+      CardProgram.findFeeScheduleAccount(mint),
+      CardProgram.findCardAccount(source),
+    ]);
     let destinationToken = await _findAssociatedTokenAddress(destination, mint);
     try {
       const token = await spl.getOrCreateAssociatedTokenAccount(
@@ -607,6 +692,10 @@ export class EscrowClient {
       key: reference,
       authority: this.authority.publicKey,
       payer: this.feePayer.publicKey,
+      config,
+      mintConfig,
+      feeSchedule,
+      card,
     };
 
     const transaction = new Transaction();
@@ -652,13 +741,16 @@ export class EscrowClient {
       key,
       bump,
       wallet,
-      destination,
       authority,
       withdraw,
       sourceToken,
       destinationToken,
       collectionFeeToken,
       mint,
+      config,
+      mintConfig,
+      feeSchedule,
+      card,
     } = params;
     const data = InitWithdrawArgs.serialize({
       amount,
@@ -681,7 +773,7 @@ export class EscrowClient {
       {
         pubkey: this.feePayer.publicKey,
         isSigner: true,
-        isWritable: false,
+        isWritable: true,
       },
       {
         pubkey: withdraw,
@@ -723,11 +815,7 @@ export class EscrowClient {
         isSigner: false,
         isWritable: false,
       },
-      {
-        pubkey: destination,
-        isSigner: false,
-        isWritable: false,
-      },
+      ...this.mintAccounts(config, mintConfig, feeSchedule, card),
     ];
     return new TransactionInstruction({
       keys,
@@ -760,6 +848,7 @@ export class EscrowClient {
       destinationToken: new PublicKey(escrow.data.dstToken),
       feeToken: new PublicKey(escrow.data.feeToken),
       mint: new PublicKey(escrow.data.mint),
+      ...(await this.settlementAccounts(escrow)),
     });
     transaction.add(transactionInstruction);
     if (input.memo) {
@@ -789,11 +878,13 @@ export class EscrowClient {
       destinationToken: new PublicKey(escrow.data.dstToken),
       feeToken: new PublicKey(escrow.data.feeToken),
       mint: new PublicKey(escrow.data.mint),
+      ...(await this.settlementAccounts(escrow)),
     });
     const closeInstruction = this.closeInstruction({
       escrow: escrowAddress,
       authority: this.authority.publicKey,
       feePayer: this.feePayer.publicKey,
+      config: (await CardProgram.findConfigAccount())[0],
     });
     const transaction = new Transaction();
     transaction.add(settleInstruction);
@@ -831,35 +922,178 @@ export class EscrowClient {
   };
 
   settleInstruction = async (params: SettleEscrowParams): Promise<TransactionInstruction> => {
+    const keys = [
+      { pubkey: params.authority, isSigner: true, isWritable: false },
+      { pubkey: params.destinationToken, isSigner: false, isWritable: true },
+      { pubkey: params.feeToken, isSigner: false, isWritable: true },
+      {
+        pubkey: params.vaultToken,
+        isSigner: false,
+        isWritable: true,
+      },
+      { pubkey: params.escrow, isSigner: false, isWritable: true },
+      { pubkey: params.mint, isSigner: false, isWritable: false },
+      { pubkey: params.vaultOwner, isSigner: false, isWritable: false },
+      {
+        pubkey: spl.TOKEN_PROGRAM_ID,
+        isSigner: false,
+        isWritable: false,
+      },
+      {
+        pubkey: SystemProgram.programId,
+        isSigner: false,
+        isWritable: false,
+      },
+      { pubkey: params.config, isSigner: false, isWritable: false },
+    ];
+    if (params.merchantAccount) {
+      keys.push({ pubkey: params.merchantAccount, isSigner: false, isWritable: false });
+    }
+    for (const payeeToken of params.payeeTokens ?? []) {
+      keys.push({ pubkey: payeeToken, isSigner: false, isWritable: true });
+    }
     return new TransactionInstruction({
       programId: CardProgram.PUBKEY,
       data: SettleEscrowArgs.serialize(),
-      keys: [
-        { pubkey: params.authority, isSigner: true, isWritable: false },
-        { pubkey: params.destinationToken, isSigner: false, isWritable: true },
-        { pubkey: params.feeToken, isSigner: false, isWritable: true },
-        {
-          pubkey: params.vaultToken,
-          isSigner: false,
-          isWritable: true,
-        },
-        { pubkey: params.escrow, isSigner: false, isWritable: true },
-        { pubkey: params.mint, isSigner: false, isWritable: false },
-        { pubkey: params.vaultOwner, isSigner: false, isWritable: false },
-        {
-          pubkey: spl.TOKEN_PROGRAM_ID,
-          isSigner: false,
-          isWritable: false,
-        },
-        {
-          pubkey: SystemProgram.programId,
-          isSigner: false,
-          isWritable: false,
-        },
-      ],
+      keys,
+    });
+  };
+
+  // config, merchant and payee token accounts a settlement of the escrow reads
+  settlementAccounts = async (
+    escrow: Escrow,
+  ): Promise<Pick<SettleEscrowParams, 'config' | 'merchantAccount' | 'payeeTokens'>> => {
+    const [config] = await CardProgram.findConfigAccount();
+    const merchant = _optionalKey(escrow.data.merchant);
+    const merchantAccount = merchant
+      ? (await CardProgram.findMerchantAccount(merchant))[0]
+      : undefined;
+    const payeeTokens = escrow.data.split.payees().map((payee) => new PublicKey(payee.token));
+    return { config, merchantAccount, payeeTokens };
+  };
+
+  settleBatchInstruction = (params: SettleBatchParams): TransactionInstruction => {
+    const keys = [
+      { pubkey: params.authority, isSigner: true, isWritable: false },
+      { pubkey: params.feeToken, isSigner: false, isWritable: true },
+      { pubkey: params.mint, isSigner: false, isWritable: false },
+      { pubkey: params.vaultOwner, isSigner: false, isWritable: false },
+      { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: params.config, isSigner: false, isWritable: false },
+    ];
+    for (const { escrow, vaultToken, destinationToken } of params.escrows) {
+      keys.push(
+        { pubkey: escrow, isSigner: false, isWritable: true },
+        { pubkey: vaultToken, isSigner: false, isWritable: true },
+        { pubkey: destinationToken, isSigner: false, isWritable: true },
+      );
+    }
+    for (const merchantAccount of params.merchantAccounts ?? []) {
+      keys.push({ pubkey: merchantAccount, isSigner: false, isWritable: false });
+    }
+    for (const payeeToken of params.payeeTokens ?? []) {
+      keys.push({ pubkey: payeeToken, isSigner: false, isWritable: true });
+    }
+    return new TransactionInstruction({
+      programId: CardProgram.PUBKEY,
+      data: SettleBatchArgs.serialize({ count: params.escrows.length }),
+      keys,
+    });
+  };
+
+  initDepositBatch = (params: InitDepositBatchParams): TransactionInstruction => {
+    const data = InitDepositBatchArgs.serialize({
+      feeBps: params.feeBps,
+      deposits: params.deposits.map(
+        ({ amount, key, bump }) => new BatchDeposit({ amount, key: key.toBase58(), bump }),
+      ),
+    });
+    const keys = [
+      { pubkey: params.user, isSigner: true, isWritable: false },
+      { pubkey: params.authority, isSigner: true, isWritable: false },
+      { pubkey: params.payer, isSigner: true, isWritable: true },
+      { pubkey: params.sourceToken, isSigner: false, isWritable: true },
+      { pubkey: params.collectionToken, isSigner: false, isWritable: true },
+      { pubkey: params.collectionFeeToken, isSigner: false, isWritable: true },
+      { pubkey: params.mint, isSigner: false, isWritable: false },
+      { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: spl.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ...params.deposits.map(({ deposit }) => ({
+        pubkey: deposit,
+        isSigner: false,
+        isWritable: true,
+      })),
+      ...this.mintAccounts(params.config, params.mintConfig, params.feeSchedule, params.card),
+    ];
+    return new TransactionInstruction({
+      keys,
+      data,
+      programId: CardProgram.PUBKEY,
+    });
+  };
+
+  // grows an escrow written by the first release to the current layout, the fee payer
+  // paying the extra rent
+  migrate = async (input: EscrowInput): Promise<string> => {
+    const escrow = new PublicKey(input.escrowAddress);
+    const info = await this.connection.getAccountInfo(escrow, input.commitment);
+    if (info == null) {
+      throw new Error(FAILED_TO_FIND_ACCOUNT);
+    }
+    if (!Escrow.isLegacy(info)) {
+      throw new Error(ACCOUNT_NOT_MIGRATABLE);
+    }
+    const data = EscrowLegacyData.deserialize(info.data);
+    const params: MigrateParams = { payer: this.feePayer.publicKey, escrow };
+    // the lamports of a native escrow move from the program authority to its vault
+    if (new PublicKey(data.mint).equals(spl.NATIVE_MINT)) {
+      [params.vaultOwner] = await CardProgram.findProgramAuthority();
+      [params.vault] = await CardProgram.findEscrowVaultAccount(new PublicKey(data.reference));
+    }
+    const transaction = new Transaction().add(this.migrateInstruction(params));
+    transaction.recentBlockhash = (
+      await this.connection.getLatestBlockhash(input.commitment ?? 'finalized')
+    ).blockhash;
+    transaction.feePayer = this.feePayer.publicKey;
+    transaction.sign(this.feePayer);
+    return this.connection.sendRawTransaction(transaction.serialize());
+  };
+
+  migrateInstruction = (params: MigrateParams): TransactionInstruction => {
+    const keys = [
+      { pubkey: params.payer, isSigner: true, isWritable: true },
+      { pubkey: params.escrow, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+    ];
+    if (params.vaultOwner && params.vault) {
+      keys.push(
+        { pubkey: params.vaultOwner, isSigner: false, isWritable: true },
+        { pubkey: params.vault, isSigner: false, isWritable: true },
+      );
+    }
+    return new TransactionInstruction({
+      programId: CardProgram.PUBKEY,
+      data: MigrateArgs.serialize(),
+      keys,
     });
   };
 
+  // config, mint config, fee schedule and card accounts ending the deposit,
+  // withdrawal and escrow instructions
+  mintAccounts = (
+    config: PublicKey,
+    mintConfig: PublicKey,
+    feeSchedule: PublicKey,
+    card: PublicKey,
+  ) => [
+    { pubkey: config, isSigner: false, isWritable: false },
+    { pubkey: mintConfig, isSigner: false, isWritable: false },
+    { pubkey: feeSchedule, isSigner: false, isWritable: false },
+    { pubkey: card, isSigner: false, isWritable: true },
+  ];
+
   signTransaction = (transaction: Transaction): Buffer => {
     transaction.feePayer = this.feePayer.publicKey;
     transaction.partialSign(this.feePayer);
@@ -912,6 +1146,12 @@ export class EscrowClient {
   };
 }
 
+// the key stored in an escrow field, unless it is the default key standing for none
+const _optionalKey = (key: string): PublicKey | undefined => {
+  const pubkey = new PublicKey(key);
+  return pubkey.equals(PublicKey.default) ? undefined : pubkey;
+};
+
 const _findAssociatedTokenAddress = async (
   walletAddress: PublicKey,
   tokenMintAddress: PublicKey,
@@ -6,6 +6,10 @@ export interface InitializePaymentInput {
   amount: string;
   feeBps?: number;
   fixedFee?: string;
+  // id of the merchant paid on settlement, the funding wallet being paid otherwise
+  merchant?: string;
+  // merchant category code checked against the categories of the card of the wallet
+  mcc?: number;
   memo?: string;
   commitment?: Commitment;
   computeUnitPrice?: number;
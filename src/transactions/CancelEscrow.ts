@@ -14,4 +14,6 @@ export type CancelEscrowParams = {
   vaultToken: PublicKey;
   sourceToken: PublicKey;
   mint: PublicKey;
+  config: PublicKey;
+  card?: PublicKey;
 };
@@ -11,4 +11,5 @@ export type CloseEscrowParams = {
   authority: PublicKey;
   escrow: PublicKey;
   feePayer: PublicKey;
+  config: PublicKey;
 };
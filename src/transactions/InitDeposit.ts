@@ -38,4 +38,8 @@ export type InitDepositParams = {
   collectionToken: PublicKey;
   collectionFeeToken: PublicKey;
   mint: PublicKey;
+  config: PublicKey;
+  mintConfig: PublicKey;
+  feeSchedule: PublicKey;
+  card: PublicKey;
 };
@@ -0,0 +1,60 @@
+import { Borsh, StringPublicKey } from '@metaplex-foundation/mpl-core';
+import { PublicKey } from '@solana/web3.js';
+import BN from 'bn.js';
+
+// deposits of a batch fitting a legacy transaction, the program refusing more
+export const MAX_BATCH_DEPOSITS = 6;
+
+type BatchDepositArgs = {
+  amount: BN;
+  key: StringPublicKey;
+  bump: number;
+};
+
+export class BatchDeposit extends Borsh.Data<BatchDepositArgs> {
+  static readonly SCHEMA = BatchDeposit.struct([
+    ['amount', 'u64'],
+    ['key', 'pubkeyAsString'],
+    ['bump', 'u8'],
+  ]);
+
+  amount: BN;
+  key: StringPublicKey;
+  bump: number;
+}
+
+type Args = {
+  feeBps: number;
+  deposits: BatchDeposit[];
+};
+
+export class InitDepositBatchArgs extends Borsh.Data<Args> {
+  static readonly SCHEMA = new Map([
+    ...InitDepositBatchArgs.struct([
+      ['instruction', 'u8'],
+      ['feeBps', 'u16'],
+      ['deposits', [BatchDeposit]],
+    ]),
+    ...BatchDeposit.SCHEMA,
+  ]);
+
+  instruction = 33;
+  feeBps: number;
+  deposits: BatchDeposit[];
+}
+
+export type InitDepositBatchParams = {
+  feeBps: number;
+  deposits: { amount: BN; key: PublicKey; bump: number; deposit: PublicKey }[];
+  user: PublicKey;
+  authority: PublicKey;
+  payer: PublicKey;
+  sourceToken: PublicKey;
+  collectionToken: PublicKey;
+  collectionFeeToken: PublicKey;
+  mint: PublicKey;
+  config: PublicKey;
+  mintConfig: PublicKey;
+  feeSchedule: PublicKey;
+  card: PublicKey;
+};
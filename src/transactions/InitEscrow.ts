@@ -1,21 +1,33 @@
-import { Borsh } from '@metaplex-foundation/mpl-core';
+import { Borsh, StringPublicKey } from '@metaplex-foundation/mpl-core';
 import { PublicKey } from '@solana/web3.js';
 import BN from 'bn.js';
+import { SplitData } from '../accounts/escrow';
 
 type Args = {
   amount: BN;
   feeBps: number;
   fixedFee: BN;
   bump: number;
+  merchant: StringPublicKey | null;
+  split: SplitData | null;
+  hold: number;
+  mcc: number;
 };
 
 export class InitEscrowArgs extends Borsh.Data<Args> {
-  static readonly SCHEMA = InitEscrowArgs.struct([
-    ['instruction', 'u8'],
-    ['amount', 'u64'],
-    ['feeBps', 'u16'],
-    ['fixedFee', 'u64'],
-    ['bump', 'u8'],
+  static readonly SCHEMA = new Map([
+    ...InitEscrowArgs.struct([
+      ['instruction', 'u8'],
+      ['amount', 'u64'],
+      ['feeBps', 'u16'],
+      ['fixedFee', 'u64'],
+      ['bump', 'u8'],
+      ['merchant', { kind: 'option', type: 'pubkeyAsString' }],
+      ['split', { kind: 'option', type: SplitData }],
+      ['hold', 'u8'],
+      ['mcc', 'u16'],
+    ]),
+    ...SplitData.SCHEMA,
   ]);
 
   instruction = 2;
@@ -23,6 +35,10 @@ export class InitEscrowArgs extends Borsh.Data<Args> {
   feeBps: number;
   fixedFee: BN;
   bump: number;
+  merchant: StringPublicKey | null;
+  split: SplitData | null;
+  hold: number;
+  mcc: number;
 }
 
 export type InitEscrowParams = {
@@ -41,4 +57,13 @@ export type InitEscrowParams = {
   destinationToken: PublicKey;
   collectionFeeToken: PublicKey;
   mint: PublicKey;
+  config: PublicKey;
+  mintConfig: PublicKey;
+  feeSchedule: PublicKey;
+  card: PublicKey;
+  merchant?: PublicKey;
+  merchantAccount?: PublicKey;
+  split?: SplitData;
+  hold?: boolean;
+  mcc?: number;
 };
@@ -42,5 +42,9 @@ export type InitWithdrawParams = {
   destinationToken: PublicKey;
   collectionFeeToken: PublicKey;
   mint: PublicKey;
+  config: PublicKey;
+  mintConfig: PublicKey;
+  feeSchedule: PublicKey;
+  card: PublicKey;
   fixedFee: BN;
 };
@@ -0,0 +1,16 @@
+import { Borsh } from '@metaplex-foundation/mpl-core';
+import { PublicKey } from '@solana/web3.js';
+
+export class MigrateArgs extends Borsh.Data {
+  static readonly SCHEMA = MigrateArgs.struct([['instruction', 'u8']]);
+
+  instruction = 34;
+}
+
+export type MigrateParams = {
+  payer: PublicKey;
+  escrow: PublicKey;
+  // program authority and escrow vault, only when migrating a native escrow
+  vaultOwner?: PublicKey;
+  vault?: PublicKey;
+};
@@ -0,0 +1,33 @@
+import { Borsh } from '@metaplex-foundation/mpl-core';
+import { PublicKey } from '@solana/web3.js';
+
+type Args = {
+  count: number;
+};
+
+export class SettleBatchArgs extends Borsh.Data<Args> {
+  static readonly SCHEMA = SettleBatchArgs.struct([
+    ['instruction', 'u8'],
+    ['count', 'u8'],
+  ]);
+
+  instruction = 32;
+  count: number;
+}
+
+export type SettleBatchEscrow = {
+  escrow: PublicKey;
+  vaultToken: PublicKey;
+  destinationToken: PublicKey;
+};
+
+export type SettleBatchParams = {
+  authority: PublicKey;
+  feeToken: PublicKey;
+  mint: PublicKey;
+  vaultOwner: PublicKey;
+  config: PublicKey;
+  escrows: SettleBatchEscrow[];
+  merchantAccounts?: PublicKey[];
+  payeeTokens?: PublicKey[];
+};
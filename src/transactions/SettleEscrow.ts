@@ -1,15 +1,7 @@
-import { Borsh, StringPublicKey } from '@metaplex-foundation/mpl-core';
+import { Borsh } from '@metaplex-foundation/mpl-core';
 import { PublicKey } from '@solana/web3.js';
-import BN from 'bn.js';
 
-type Args = {
-  amount: BN;
-  feeBps: number;
-  key: StringPublicKey;
-  bump: number;
-};
-
-export class SettleEscrowArgs extends Borsh.Data<Args> {
+export class SettleEscrowArgs extends Borsh.Data {
   static readonly SCHEMA = SettleEscrowArgs.struct([['instruction', 'u8']]);
 
   instruction = 3;
@@ -24,4 +16,7 @@ export type SettleEscrowParams = {
   destinationToken: PublicKey;
   feeToken: PublicKey;
   mint: PublicKey;
+  config: PublicKey;
+  merchantAccount?: PublicKey;
+  payeeTokens?: PublicKey[];
 };
@@ -4,3 +4,6 @@ export * from './InitEscrow';
 export * from './SettleEscrow';
 export * from './CancelEscrow';
 export * from './CloseEscrow';
+export * from './SettleBatch';
+export * from './InitDepositBatch';
+export * from './Migrate';